@@ -0,0 +1,200 @@
+use super::error::{ParseError, Result};
+use super::report::elapsed_ms;
+use super::solver::DynSolver;
+use super::utils::{expect_token, parse_number};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Stage {
+    Parse,
+    Part1,
+    Part2,
+}
+
+impl Stage {
+    pub const ALL: [Stage; 3] = [Stage::Parse, Stage::Part1, Stage::Part2];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Stage::Parse => "parse",
+            Stage::Part1 => "part1",
+            Stage::Part2 => "part2",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Stage> {
+        Stage::ALL.into_iter().find(|stage| stage.name() == s)
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Stats {
+    pub min: Duration,
+    pub median: Duration,
+    pub max: Duration,
+}
+
+impl Stats {
+    /// samples must not be empty
+    pub fn from_samples(samples: &mut [Duration]) -> Stats {
+        samples.sort();
+        let mid = samples.len() / 2;
+        let median = if samples.len().is_multiple_of(2) {
+            (samples[mid - 1] + samples[mid]) / 2
+        } else {
+            samples[mid]
+        };
+        Stats {
+            min: samples[0],
+            median,
+            max: samples[samples.len() - 1],
+        }
+    }
+}
+
+/// Timings of parsing and both parts for a single day
+pub struct DayBench {
+    pub day: u8,
+    pub name: &'static str,
+    pub stats: BTreeMap<Stage, Stats>,
+}
+
+/// Input is parsed anew on each iteration, so every stage gets the same number of samples
+pub fn bench_day(solver: &dyn DynSolver, input: &str, iterations: usize) -> Result<DayBench> {
+    let mut samples: BTreeMap<Stage, Vec<Duration>> = BTreeMap::new();
+    let mut timed = |stage: Stage, start: Instant| {
+        samples.entry(stage).or_default().push(start.elapsed());
+    };
+    for _ in 0..iterations.max(1) {
+        let start = Instant::now();
+        let parsed = solver.parse(input)?;
+        timed(Stage::Parse, start);
+
+        let start = Instant::now();
+        solver.part1(parsed.as_ref())?;
+        timed(Stage::Part1, start);
+
+        let start = Instant::now();
+        solver.part2(parsed.as_ref())?;
+        timed(Stage::Part2, start);
+    }
+    Ok(DayBench {
+        day: solver.day(),
+        name: solver.name(),
+        stats: samples
+            .into_iter()
+            .map(|(stage, mut stage_samples)| (stage, Stats::from_samples(&mut stage_samples)))
+            .collect(),
+    })
+}
+
+/// Saved median timings, one "<day> <stage> <median ms>" line per stage
+#[derive(Debug, Default)]
+pub struct Baseline {
+    medians: BTreeMap<(u8, Stage), Duration>,
+}
+
+pub struct Regression {
+    pub day: u8,
+    pub stage: Stage,
+    pub baseline: Duration,
+    pub current: Duration,
+}
+
+impl Regression {
+    /// relative change in percents
+    pub fn change(&self) -> f64 {
+        relative_change(self.baseline, self.current)
+    }
+}
+
+pub fn relative_change(baseline: Duration, current: Duration) -> f64 {
+    (current.as_secs_f64() / baseline.as_secs_f64() - 1.0) * 100.0
+}
+
+impl Baseline {
+    pub fn from_benches(benches: &[DayBench]) -> Baseline {
+        let mut medians = BTreeMap::new();
+        for b in benches {
+            for (stage, stats) in &b.stats {
+                medians.insert((b.day, *stage), stats.median);
+            }
+        }
+        Baseline { medians }
+    }
+
+    pub fn parse(s: &str) -> Result<Baseline, ParseError> {
+        let mut medians = BTreeMap::new();
+        for line in s.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let in_text = |e: ParseError| e.within(s, line);
+            let mut tokens = line.split_whitespace();
+            let day: u8 = parse_number(
+                line,
+                expect_token(line, tokens.next(), "day").map_err(in_text)?,
+                "day",
+            )
+            .map_err(in_text)?;
+            let stage_str = expect_token(line, tokens.next(), "stage").map_err(in_text)?;
+            let stage = Stage::parse(stage_str).ok_or_else(|| {
+                ParseError::at(s, stage_str, format!("unknown stage '{}'", stage_str))
+            })?;
+            let median_ms: f64 = parse_number(
+                line,
+                expect_token(line, tokens.next(), "median").map_err(in_text)?,
+                "median",
+            )
+            .map_err(in_text)?;
+            if !median_ms.is_finite() || median_ms < 0.0 {
+                return Err(ParseError::at(
+                    s,
+                    line,
+                    "median must be a non-negative number",
+                ));
+            }
+            medians.insert((day, stage), Duration::from_secs_f64(median_ms / 1000.0));
+        }
+        Ok(Baseline { medians })
+    }
+
+    pub fn get(&self, day: u8, stage: Stage) -> Option<Duration> {
+        self.medians.get(&(day, stage)).copied()
+    }
+
+    /// stages whose median got slower than the baseline by more than `threshold` percents
+    pub fn regressions(&self, benches: &[DayBench], threshold: f64) -> Vec<Regression> {
+        let mut res = Vec::new();
+        for b in benches {
+            for (stage, stats) in &b.stats {
+                if let Some(baseline) = self.get(b.day, *stage) {
+                    if baseline.is_zero() {
+                        continue;
+                    }
+                    if relative_change(baseline, stats.median) > threshold {
+                        res.push(Regression {
+                            day: b.day,
+                            stage: *stage,
+                            baseline,
+                            current: stats.median,
+                        });
+                    }
+                }
+            }
+        }
+        res
+    }
+}
+
+impl fmt::Display for Baseline {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for ((day, stage), median) in &self.medians {
+            writeln!(f, "{} {} {:.6}", day, stage.name(), elapsed_ms(*median))?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,564 @@
+use std::fmt;
+use std::path::PathBuf;
+
+pub const USAGE: &str = "\
+usage:
+    advent-of-code [run] [DAY] [options]   run a single day, the latest one by default
+    advent-of-code [run] --all [options]   run every registered day
+    advent-of-code bench [DAY] [options]   time parsing and both parts of a day
+    advent-of-code bench --all [options]   time every registered day
+    advent-of-code list                    list registered days
+    advent-of-code disasm [options]        print the day 24 program blocks side by side
+
+options:
+    -p, --part <1|2>          run only one part of the solution
+    -t, --test                use the example, dayNN/input_test.txt instead of dayNN/input.txt
+    -i, --input <PATH>        read input from PATH, '-' for stdin
+    -d, --data-dir <DIR>      directory with inputs, AOC_DATA_DIR or ./data by default
+    -a, --all                 run all days
+    -f, --format <FMT>        'text' (default) or 'json', one record per part and line
+    -v, --verbose             info output to stderr for all days, -vv for debug, -vvv for trace
+    --log <SPEC>              debug output filter, e.g. 'day23=debug,day16=trace', overrides AOC_LOG
+    -h, --help                print this message
+
+bench options:
+    -n, --iterations <N>      number of runs, 10 by default
+    --baseline <PATH>         compare medians with a saved baseline
+    --save-baseline <PATH>    save medians as a new baseline
+    --threshold <PCT>         slowdown in percents reported as a regression, 25 by default
+
+disasm options, along with -t, -i and -d:
+    --columns <N>             blocks next to each other, the rest goes below, all by default
+    --color                   highlight the instructions that differ from the rest of their row
+
+missing puzzle inputs are downloaded into the data directory when AOC_SESSION holds
+the session cookie of adventofcode.com, AOC_URL overrides the site address
+
+exit codes: 0 on success, 1 if some day failed or regressed, 2 on bad arguments";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Part {
+    One,
+    Two,
+    Both,
+}
+
+impl Part {
+    pub fn numbers(&self) -> &'static [u8] {
+        match self {
+            Part::One => &[1],
+            Part::Two => &[2],
+            Part::Both => &[1, 2],
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Days {
+    Latest,
+    Single(u8),
+    All,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InputArg {
+    Default,
+    Test,
+    Path(PathBuf),
+    Stdin,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Text,
+    Json,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RunArgs {
+    pub days: Days,
+    pub part: Part,
+    pub input: InputArg,
+    pub data_dir: Option<PathBuf>,
+    pub format: Format,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct BenchArgs {
+    pub days: Days,
+    pub input: InputArg,
+    pub data_dir: Option<PathBuf>,
+    pub iterations: usize,
+    pub baseline: Option<PathBuf>,
+    pub save_baseline: Option<PathBuf>,
+    pub threshold: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DisasmArgs {
+    pub input: InputArg,
+    pub data_dir: Option<PathBuf>,
+    pub columns: Option<usize>,
+    pub color: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Command {
+    Run(RunArgs),
+    Bench(BenchArgs),
+    Disasm(DisasmArgs),
+    List,
+    Help,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Args {
+    pub command: Command,
+    pub log: Option<String>,
+    pub verbosity: u8,
+}
+
+#[derive(Debug)]
+pub struct UsageError(String);
+
+impl fmt::Display for UsageError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+fn usage_error<T>(message: impl Into<String>) -> Result<T, UsageError> {
+    Err(UsageError(message.into()))
+}
+
+/// Splits "--flag=value" into flag and value, other args are left as is
+fn split_flag_value(arg: &str) -> (&str, Option<&str>) {
+    match arg.split_once('=') {
+        Some((flag, value)) if arg.starts_with("--") => (flag, Some(value)),
+        _ => (arg, None),
+    }
+}
+
+pub fn parse_args<I: IntoIterator<Item = String>>(args: I) -> Result<Args, UsageError> {
+    let mut log: Option<String> = None;
+    let mut verbosity: u8 = 0;
+    let command = parse_command(args.into_iter(), &mut log, &mut verbosity)?;
+    Ok(Args {
+        command,
+        log,
+        verbosity,
+    })
+}
+
+fn parse_command<I: Iterator<Item = String>>(
+    args: I,
+    log: &mut Option<String>,
+    verbosity: &mut u8,
+) -> Result<Command, UsageError> {
+    let mut args = args.peekable();
+
+    let (bench, disasm) = match args.peek().map(|s| s.as_str()) {
+        Some("list") => {
+            args.next();
+            if let Some(extra) = args.next() {
+                return usage_error(format!("unexpected argument '{}'", extra));
+            }
+            return Ok(Command::List);
+        }
+        Some("run") => {
+            args.next();
+            (false, false)
+        }
+        Some("bench") => {
+            args.next();
+            (true, false)
+        }
+        Some("disasm") => {
+            args.next();
+            (false, true)
+        }
+        _ => (false, false),
+    };
+
+    let mut day: Option<u8> = None;
+    let mut all = false;
+    let mut part: Option<Part> = None;
+    let mut input = InputArg::Default;
+    let mut data_dir: Option<PathBuf> = None;
+    let mut format: Option<Format> = None;
+    let mut iterations: Option<usize> = None;
+    let mut baseline: Option<PathBuf> = None;
+    let mut save_baseline: Option<PathBuf> = None;
+    let mut threshold: Option<f64> = None;
+    let mut columns: Option<usize> = None;
+    let mut color = false;
+
+    let mut set_input = |new_input: InputArg| {
+        if input != InputArg::Default {
+            return usage_error("--test and --input can't be used together or repeated");
+        }
+        input = new_input;
+        Ok(())
+    };
+
+    while let Some(arg) = args.next() {
+        let (flag, inline_value) = split_flag_value(&arg);
+        let mut value = |name: &str| match inline_value {
+            Some(v) => Ok(v.to_string()),
+            None => args
+                .next()
+                .map_or_else(|| usage_error(format!("{} requires a value", name)), Ok),
+        };
+        match flag {
+            "-h" | "--help" => return Ok(Command::Help),
+            "-v" | "--verbose" => *verbosity += 1,
+            "-vv" => *verbosity += 2,
+            "-vvv" => *verbosity += 3,
+            "--log" => *log = Some(value(flag)?),
+            "-a" | "--all" => all = true,
+            "-t" | "--test" => set_input(InputArg::Test)?,
+            "-i" | "--input" => {
+                let path = value(flag)?;
+                set_input(if path == "-" {
+                    InputArg::Stdin
+                } else {
+                    InputArg::Path(PathBuf::from(path))
+                })?
+            }
+            "-d" | "--data-dir" => data_dir = Some(PathBuf::from(value(flag)?)),
+            "-f" | "--format" => {
+                format = Some(match value(flag)?.as_str() {
+                    "text" => Format::Text,
+                    "json" => Format::Json,
+                    other => {
+                        return usage_error(format!("format must be text or json, not '{}'", other))
+                    }
+                });
+            }
+            "-n" | "--iterations" => {
+                let n = value(flag)?;
+                iterations = match n.parse() {
+                    Ok(n) if n > 0 => Some(n),
+                    _ => {
+                        return usage_error(format!(
+                            "iterations must be a positive number, not '{}'",
+                            n
+                        ))
+                    }
+                };
+            }
+            "--baseline" => baseline = Some(PathBuf::from(value(flag)?)),
+            "--save-baseline" => save_baseline = Some(PathBuf::from(value(flag)?)),
+            "--threshold" => {
+                let t = value(flag)?;
+                threshold = match t.parse::<f64>() {
+                    Ok(t) if t >= 0.0 => Some(t),
+                    _ => {
+                        return usage_error(format!(
+                            "threshold must be a non-negative number, not '{}'",
+                            t
+                        ))
+                    }
+                };
+            }
+            "--columns" => {
+                let n = value(flag)?;
+                columns = match n.parse() {
+                    Ok(n) if n > 0 => Some(n),
+                    _ => {
+                        return usage_error(format!(
+                            "columns must be a positive number, not '{}'",
+                            n
+                        ))
+                    }
+                };
+            }
+            "--color" => color = true,
+            "-p" | "--part" => {
+                if part.is_some() {
+                    return usage_error("--part is given more than once");
+                }
+                part = Some(match value(flag)?.as_str() {
+                    "1" => Part::One,
+                    "2" => Part::Two,
+                    other => return usage_error(format!("part must be 1 or 2, not '{}'", other)),
+                });
+            }
+            _ if flag.starts_with('-') && flag.len() > 1 => {
+                return usage_error(format!("unknown option '{}'", flag))
+            }
+            _ => {
+                if day.is_some() {
+                    return usage_error(format!("unexpected argument '{}'", arg));
+                }
+                day = match arg.parse() {
+                    Ok(d) => Some(d),
+                    Err(_) => return usage_error(format!("day must be a number, not '{}'", arg)),
+                };
+            }
+        }
+    }
+
+    if disasm {
+        if day.is_some() || all || part.is_some() || format.is_some() {
+            return usage_error("disasm always shows day 24, it only takes input options");
+        }
+        if iterations.is_some()
+            || baseline.is_some()
+            || save_baseline.is_some()
+            || threshold.is_some()
+        {
+            return usage_error("benchmark options can only be used with bench");
+        }
+        return Ok(Command::Disasm(DisasmArgs {
+            input,
+            data_dir,
+            columns,
+            color,
+        }));
+    }
+    if columns.is_some() || color {
+        return usage_error("--columns and --color can only be used with disasm");
+    }
+
+    let days = match (day, all) {
+        (Some(_), true) => return usage_error("a day can't be given together with --all"),
+        (Some(d), false) => Days::Single(d),
+        (None, true) => Days::All,
+        (None, false) => Days::Latest,
+    };
+    if days == Days::All && matches!(input, InputArg::Path(_) | InputArg::Stdin) {
+        return usage_error("--input can't be used with --all, every day needs its own input");
+    }
+
+    if bench {
+        if part.is_some() || format.is_some() {
+            return usage_error("--part and --format can't be used with bench");
+        }
+        return Ok(Command::Bench(BenchArgs {
+            days,
+            input,
+            data_dir,
+            iterations: iterations.unwrap_or(10),
+            baseline,
+            save_baseline,
+            threshold: threshold.unwrap_or(25.0),
+        }));
+    }
+    if iterations.is_some() || baseline.is_some() || save_baseline.is_some() || threshold.is_some()
+    {
+        return usage_error("benchmark options can only be used with bench");
+    }
+
+    Ok(Command::Run(RunArgs {
+        days,
+        part: part.unwrap_or(Part::Both),
+        input,
+        data_dir,
+        format: format.unwrap_or(Format::Text),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &str) -> Result<Args, UsageError> {
+        parse_args(args.split_whitespace().map(String::from))
+    }
+
+    fn command(args: &str) -> Command {
+        parse(args).unwrap().command
+    }
+
+    fn error(args: &str) -> String {
+        parse(args).unwrap_err().to_string()
+    }
+
+    #[test]
+    fn runs_the_latest_day_by_default() {
+        let expected = Command::Run(RunArgs {
+            days: Days::Latest,
+            part: Part::Both,
+            input: InputArg::Default,
+            data_dir: None,
+            format: Format::Text,
+        });
+        assert_eq!(command(""), expected);
+        assert_eq!(command("run"), expected);
+    }
+
+    #[test]
+    fn parses_run_options() {
+        assert_eq!(
+            command("run 7 -p 2 --input=- --data-dir=data -f json"),
+            Command::Run(RunArgs {
+                days: Days::Single(7),
+                part: Part::Two,
+                input: InputArg::Stdin,
+                data_dir: Some(PathBuf::from("data")),
+                format: Format::Json,
+            })
+        );
+        assert_eq!(
+            command("--all --test --part=1"),
+            Command::Run(RunArgs {
+                days: Days::All,
+                part: Part::One,
+                input: InputArg::Test,
+                data_dir: None,
+                format: Format::Text,
+            })
+        );
+    }
+
+    #[test]
+    fn splits_only_long_flags_at_the_equals_sign() {
+        assert_eq!(split_flag_value("--input=a=b"), ("--input", Some("a=b")));
+        assert_eq!(split_flag_value("--log="), ("--log", Some("")));
+        assert_eq!(split_flag_value("-i=path"), ("-i=path", None));
+        assert_eq!(split_flag_value("--test"), ("--test", None));
+        assert_eq!(error("-i=path"), "unknown option '-i=path'");
+    }
+
+    #[test]
+    fn counts_verbosity_and_keeps_the_log_spec() {
+        let args = parse("-v --log day23=debug 3").unwrap();
+        assert_eq!(args.verbosity, 1);
+        assert_eq!(args.log.as_deref(), Some("day23=debug"));
+        assert_eq!(parse("-vv").unwrap().verbosity, 2);
+        assert_eq!(parse("-vvv --verbose").unwrap().verbosity, 4);
+        assert_eq!(parse("list").unwrap().verbosity, 0);
+    }
+
+    #[test]
+    fn parses_bench_with_its_defaults() {
+        assert_eq!(
+            command("bench 16"),
+            Command::Bench(BenchArgs {
+                days: Days::Single(16),
+                input: InputArg::Default,
+                data_dir: None,
+                iterations: 10,
+                baseline: None,
+                save_baseline: None,
+                threshold: 25.0,
+            })
+        );
+        assert_eq!(
+            command("bench --all -n 3 --baseline old.json --save-baseline=new.json --threshold 0"),
+            Command::Bench(BenchArgs {
+                days: Days::All,
+                input: InputArg::Default,
+                data_dir: None,
+                iterations: 3,
+                baseline: Some(PathBuf::from("old.json")),
+                save_baseline: Some(PathBuf::from("new.json")),
+                threshold: 0.0,
+            })
+        );
+    }
+
+    #[test]
+    fn parses_disasm() {
+        assert_eq!(
+            command("disasm"),
+            Command::Disasm(DisasmArgs {
+                input: InputArg::Default,
+                data_dir: None,
+                columns: None,
+                color: false,
+            })
+        );
+        assert_eq!(
+            command("disasm -i monad.txt -d data --columns=7 --color"),
+            Command::Disasm(DisasmArgs {
+                input: InputArg::Path(PathBuf::from("monad.txt")),
+                data_dir: Some(PathBuf::from("data")),
+                columns: Some(7),
+                color: true,
+            })
+        );
+    }
+
+    #[test]
+    fn parses_list_and_help() {
+        assert_eq!(command("list"), Command::List);
+        assert_eq!(command("-h"), Command::Help);
+        assert_eq!(command("bench 3 --help"), Command::Help);
+    }
+
+    #[test]
+    fn rejects_bad_arguments() {
+        assert_eq!(error("list 3"), "unexpected argument '3'");
+        assert_eq!(error("3 4"), "unexpected argument '4'");
+        assert_eq!(
+            error("-t -t"),
+            "--test and --input can't be used together or repeated"
+        );
+        assert_eq!(
+            error("-t -i input.txt"),
+            "--test and --input can't be used together or repeated"
+        );
+        assert_eq!(error("--input"), "--input requires a value");
+        assert_eq!(error("--log"), "--log requires a value");
+        assert_eq!(error("-f yaml"), "format must be text or json, not 'yaml'");
+        assert_eq!(
+            error("bench -n 0"),
+            "iterations must be a positive number, not '0'"
+        );
+        assert_eq!(
+            error("bench --threshold -1"),
+            "threshold must be a non-negative number, not '-1'"
+        );
+        assert_eq!(
+            error("bench --threshold x"),
+            "threshold must be a non-negative number, not 'x'"
+        );
+        assert_eq!(
+            error("disasm --columns 0"),
+            "columns must be a positive number, not '0'"
+        );
+        assert_eq!(error("-p 1 -p 2"), "--part is given more than once");
+        assert_eq!(error("-p 3"), "part must be 1 or 2, not '3'");
+        assert_eq!(error("--bogus"), "unknown option '--bogus'");
+        assert_eq!(error("seven"), "day must be a number, not 'seven'");
+        assert_eq!(error("3 --all"), "a day can't be given together with --all");
+        assert_eq!(
+            error("--all -i -"),
+            "--input can't be used with --all, every day needs its own input"
+        );
+        assert_eq!(
+            error("bench -p 1"),
+            "--part and --format can't be used with bench"
+        );
+        assert_eq!(
+            error("bench -f json"),
+            "--part and --format can't be used with bench"
+        );
+        assert_eq!(
+            error("-n 3"),
+            "benchmark options can only be used with bench"
+        );
+        assert_eq!(
+            error("disasm --baseline old.json"),
+            "benchmark options can only be used with bench"
+        );
+        assert_eq!(
+            error("disasm 24"),
+            "disasm always shows day 24, it only takes input options"
+        );
+        assert_eq!(
+            error("disasm -p 1"),
+            "disasm always shows day 24, it only takes input options"
+        );
+        assert_eq!(
+            error("--color"),
+            "--columns and --color can only be used with disasm"
+        );
+        assert_eq!(
+            error("bench --columns 3"),
+            "--columns and --color can only be used with disasm"
+        );
+    }
+}
@@ -1,24 +1,30 @@
-use std::fs;
+use super::error::Result;
+use super::solver::{Answer, Solver};
+use super::utils::{parse_lines, parse_number};
 
-fn count_increases(v: &Vec<u32>) -> u32 {
+pub fn count_increases(v: &[u32]) -> u32 {
     v.windows(2).map(|p| if p[1] > p[0] { 1 } else { 0 }).sum()
 }
 
-pub fn sonar() {
-    let mut depths: Vec<u32> = Vec::new();
-    let input = fs::read_to_string("data/day01/input.txt").expect("Cannot read file!");
-    for line in input.lines() {
-        let depth: u32 = line.parse().expect("Cannot parse u32 from line");
-        depths.push(depth);
+pub struct Sonar;
+
+impl Solver for Sonar {
+    type Input = Vec<u32>;
+
+    const DAY: u8 = 1;
+    const NAME: &'static str = "sonar";
+
+    fn parse(&self, input: &str) -> Result<Vec<u32>> {
+        parse_lines(Self::DAY, input, |line| parse_number(line, line, "depth"))
     }
 
-    // task 1
-    println!("number of increasing depths: {}", count_increases(&depths));
+    fn part1(&self, depths: &Vec<u32>) -> Result<Answer> {
+        Ok(count_increases(depths).into())
+    }
 
-    // task 2
-    let depths_ma = Vec::from_iter(depths.windows(3).map(|ds| ds.iter().sum()));
-    println!(
-        "number of increasing depths (3-steps moving average): {}",
-        count_increases(&depths_ma)
-    );
+    fn part2(&self, depths: &Vec<u32>) -> Result<Answer> {
+        // 3-steps moving average
+        let depths_ma = Vec::from_iter(depths.windows(3).map(|ds| ds.iter().sum()));
+        Ok(count_increases(&depths_ma).into())
+    }
 }
@@ -1,40 +1,44 @@
-use std::fs;
+use super::error::{ParseError, Result};
+use super::solver::{Answer, Solver};
+use super::utils::{expect_token, parse_lines, parse_number};
 
 #[derive(Debug)]
-enum SubmarineMove {
+pub enum SubmarineMove {
     Up(u16),
     Forward(u16),
     Down(u16),
 }
 
 impl SubmarineMove {
-    fn parse(line: &str) -> SubmarineMove {
+    pub fn parse(line: &str) -> Result<SubmarineMove, ParseError> {
         let mut line_parts = line.split_whitespace();
-        let direction = line_parts.next().expect("Can't get direction from line");
-        let amount: u16 = line_parts
-            .next()
-            .expect("Can't get move amount from line")
-            .parse()
-            .expect("Can't parse move amount as u32");
+        let direction = expect_token(line, line_parts.next(), "direction")?;
+        let amount: u16 = parse_number(
+            line,
+            expect_token(line, line_parts.next(), "move amount")?,
+            "move amount",
+        )?;
         match direction {
-            "up" => SubmarineMove::Up(amount),
-            "forward" => SubmarineMove::Forward(amount),
-            "down" => SubmarineMove::Down(amount),
-            &_ => {
-                panic!("can't parse submarine move from line {}", line)
-            }
+            "up" => Ok(SubmarineMove::Up(amount)),
+            "forward" => Ok(SubmarineMove::Forward(amount)),
+            "down" => Ok(SubmarineMove::Down(amount)),
+            &_ => Err(ParseError::at(
+                line,
+                direction,
+                format!("unknown direction '{}'", direction),
+            )),
         }
     }
 
-    fn as_deltas_wo_aim(&self) -> (i32, i32) {
+    pub fn as_deltas_wo_aim(&self) -> (i32, i32) {
         match self {
             SubmarineMove::Down(a) => (0, *a as i32),
-            SubmarineMove::Up(a) => (0, -1 * (*a as i32)),
+            SubmarineMove::Up(a) => (0, -(*a as i32)),
             SubmarineMove::Forward(a) => (*a as i32, 0),
         }
     }
 
-    fn perform(&self, curr_horiz: i32, curr_depth: i32, curr_aim: i32) -> (i32, i32, i32) {
+    pub fn perform(&self, curr_horiz: i32, curr_depth: i32, curr_aim: i32) -> (i32, i32, i32) {
         match self {
             SubmarineMove::Down(plus_aim) => {
                 (curr_horiz, curr_depth, curr_aim + (*plus_aim as i32))
@@ -51,31 +55,30 @@ impl SubmarineMove {
     }
 }
 
-pub fn submarine_path() {
-    let input = fs::read_to_string("data/day02/input.txt").expect("Cannot read file!");
-    let moves = Vec::from_iter(input.lines().map(SubmarineMove::parse));
+pub struct SubmarinePath;
+
+impl Solver for SubmarinePath {
+    type Input = Vec<SubmarineMove>;
+
+    const DAY: u8 = 2;
+    const NAME: &'static str = "submarine_path";
 
-    // part 1
-    let (horiz, depth) = moves
-        .iter()
-        .map(SubmarineMove::as_deltas_wo_aim)
-        .fold((0, 0), |acc, deltas| (acc.0 + deltas.0, acc.1 + deltas.1));
+    fn parse(&self, input: &str) -> Result<Vec<SubmarineMove>> {
+        parse_lines(Self::DAY, input, SubmarineMove::parse)
+    }
 
-    println!(
-        "[part1] submarine will be at ({}, {}), product is: {}",
-        horiz,
-        depth,
-        horiz * depth
-    );
+    fn part1(&self, moves: &Vec<SubmarineMove>) -> Result<Answer> {
+        let (horiz, depth) = moves
+            .iter()
+            .map(SubmarineMove::as_deltas_wo_aim)
+            .fold((0, 0), |acc, deltas| (acc.0 + deltas.0, acc.1 + deltas.1));
+        Ok((horiz * depth).into())
+    }
 
-    let (horiz, depth, aim) = moves.iter().fold((0, 0, 0), |acc, submarine_move| {
-        submarine_move.perform(acc.0, acc.1, acc.2)
-    });
-    println!(
-        "[part2] submarine will be at ({}, {}), aimed at {}, product is: {}",
-        horiz,
-        depth,
-        aim,
-        horiz * depth
-    );
+    fn part2(&self, moves: &Vec<SubmarineMove>) -> Result<Answer> {
+        let (horiz, depth, _aim) = moves.iter().fold((0, 0, 0), |acc, submarine_move| {
+            submarine_move.perform(acc.0, acc.1, acc.2)
+        });
+        Ok((horiz * depth).into())
+    }
 }
@@ -1,15 +1,24 @@
-use std::fs;
-
-fn parse_u32_from_binary(s: &str) -> u32 {
-    s.chars()
-        .rev()
-        .enumerate()
-        .map(|(i, ch)| match ch {
-            '1' => u32::pow(2, i as u32),
-            '0' => 0,
-            _ => panic!("Encountered non-binary char!"),
-        })
-        .sum()
+use super::error::{ParseError, Result};
+use super::solver::{Answer, Solver};
+use super::utils::parse_lines;
+
+pub fn parse_u32_from_binary(s: &str) -> Result<u32, ParseError> {
+    let mut res: u32 = 0;
+    for (i, ch) in s.char_indices() {
+        res = res << 1
+            | match ch {
+                '1' => 1,
+                '0' => 0,
+                _ => {
+                    return Err(ParseError::at(
+                        s,
+                        &s[i..i + ch.len_utf8()],
+                        format!("encountered non-binary char '{}'", ch),
+                    ))
+                }
+            };
+    }
+    Ok(res)
 }
 
 fn bit_at(input: &u32, n: usize) -> bool {
@@ -20,12 +29,12 @@ fn bit_at(input: &u32, n: usize) -> bool {
     }
 }
 
-fn count_bits(readings: &Vec<u32>, reading_len: usize) -> Vec<u32> {
+fn count_bits(readings: &[u32], reading_len: usize) -> Vec<u32> {
     let mut bit_counts: Vec<u32> = vec![0; reading_len];
     for reading in readings.iter() {
-        for position in 0..reading_len {
+        for (position, count) in bit_counts.iter_mut().enumerate() {
             if bit_at(reading, position) {
-                bit_counts[position] += 1;
+                *count += 1;
             }
         }
     }
@@ -33,7 +42,7 @@ fn count_bits(readings: &Vec<u32>, reading_len: usize) -> Vec<u32> {
 }
 
 fn filtering_rating(
-    readings: &Vec<u32>,
+    readings: &[u32],
     reading_len: usize,
     cmp: fn(f32, f32) -> bool,
     keep_if_equal: bool,
@@ -43,13 +52,13 @@ fn filtering_rating(
     let mut mask = vec![true; readings.len()];
     let mut mask_sum = readings.len();
 
-    fn get_true_idx(m: &Vec<bool>) -> Option<usize> {
+    fn get_true_idx(m: &[bool]) -> Option<usize> {
         for (i, m) in m.iter().enumerate() {
             if *m {
                 return Some(i);
             }
         }
-        return None;
+        None
     }
 
     for filtering_position in (0..reading_len).rev() {
@@ -70,58 +79,83 @@ fn filtering_rating(
             if mask[i] && bit_at(reading, filtering_position) != bit_to_keep {
                 mask[i] = false;
                 mask_sum -= 1;
-                for position in 0..reading_len {
+                for (position, count) in bit_counts.iter_mut().enumerate() {
                     if bit_at(reading, position) {
-                        bit_counts[position] -= 1;
+                        *count -= 1;
                     }
                 }
             }
         }
     }
-    return readings[get_true_idx(&mask).unwrap()];
+    readings[get_true_idx(&mask).unwrap()]
+}
+
+pub struct Readings {
+    readings: Vec<u32>,
+    reading_len: usize,
 }
 
-pub fn submarine_ratings() {
-    let input = fs::read_to_string("data/day03/input.txt").expect("Can't read input file");
-    let reading_len = input.lines().next().unwrap().len();
-    let readings = Vec::from_iter(input.lines().map(parse_u32_from_binary));
-    let readings_n = readings.len();
+pub struct SubmarineRatings;
 
-    // part 1
-    let bit_counts = count_bits(&readings, reading_len);
+impl Solver for SubmarineRatings {
+    type Input = Readings;
 
-    let gamma = parse_u32_from_binary(&String::from_iter(bit_counts.iter().rev().map(|bc| {
-        if bc > &((readings_n / 2) as u32) {
-            '1'
-        } else {
-            '0'
-        }
-    })));
-    let epsilon = !gamma % u32::pow(2, reading_len as u32); // dropping bytes higher than reading_len
-    println!(
-        "gamma = {:#0b}, epsilon = {:#0b}, power consumption = {}",
-        gamma,
-        epsilon,
-        gamma * epsilon
-    );
-
-    // part 2
-    let o2_gen_rating = filtering_rating(
-        &readings,
-        reading_len,
-        |bit_count, half_readings_count| bit_count > half_readings_count,
-        true,
-    );
-    let co2_scrub_rating = filtering_rating(
-        &readings,
-        reading_len,
-        |bit_count, half_readings_count| bit_count < half_readings_count,
-        false,
-    );
-    println!(
-        "oxygen generator rating: {}, CO2 scrubber rating: {}, life support rating: {}",
-        o2_gen_rating,
-        co2_scrub_rating,
-        o2_gen_rating * co2_scrub_rating
-    )
+    const DAY: u8 = 3;
+    const NAME: &'static str = "submarine_ratings";
+
+    fn parse(&self, input: &str) -> Result<Readings> {
+        let reading_len = match input.lines().next() {
+            Some(line) => line.len(),
+            None => {
+                return Err(ParseError::at_end(input, "no readings").in_input(
+                    Self::DAY,
+                    input,
+                    input,
+                ))
+            }
+        };
+        let readings = parse_lines(Self::DAY, input, |line| {
+            if line.len() != reading_len {
+                return Err(ParseError::at_end(
+                    line,
+                    format!("all readings must be {} bits long", reading_len),
+                ));
+            }
+            parse_u32_from_binary(line)
+        })?;
+        Ok(Readings {
+            reading_len,
+            readings,
+        })
+    }
+
+    fn part1(&self, input: &Readings) -> Result<Answer> {
+        let bit_counts = count_bits(&input.readings, input.reading_len);
+        let readings_n = input.readings.len();
+
+        let gamma: u32 = bit_counts
+            .iter()
+            .enumerate()
+            .filter(|&(_, bc)| bc > &((readings_n / 2) as u32))
+            .map(|(position, _)| 1 << position)
+            .sum();
+        let epsilon = !gamma % u32::pow(2, input.reading_len as u32); // dropping bytes higher than reading_len
+        Ok((gamma * epsilon).into())
+    }
+
+    fn part2(&self, input: &Readings) -> Result<Answer> {
+        let o2_gen_rating = filtering_rating(
+            &input.readings,
+            input.reading_len,
+            |bit_count, half_readings_count| bit_count > half_readings_count,
+            true,
+        );
+        let co2_scrub_rating = filtering_rating(
+            &input.readings,
+            input.reading_len,
+            |bit_count, half_readings_count| bit_count < half_readings_count,
+            false,
+        );
+        Ok((o2_gen_rating * co2_scrub_rating).into())
+    }
 }
@@ -1,10 +1,12 @@
+use super::error::{Error, ParseError, Result};
+use super::solver::{Answer, Solver};
+use super::utils::{expect_token, parse_number};
 use std::convert::TryInto;
-use std::fs;
 
 const BOARD_SIDE: usize = 5;
 
-#[derive(Debug)]
-struct Board {
+#[derive(Debug, Clone)]
+pub struct Board {
     values: [u32; BOARD_SIDE * BOARD_SIDE],
     marked: [bool; BOARD_SIDE * BOARD_SIDE],
 }
@@ -14,9 +16,10 @@ impl Board {
         if !(i < BOARD_SIDE && j < BOARD_SIDE) {
             return None;
         }
-        return Some(i * BOARD_SIDE + j);
+        Some(i * BOARD_SIDE + j)
     }
 
+    #[allow(dead_code)]
     fn print(&self) {
         for i in 0..BOARD_SIDE {
             for j in 0..BOARD_SIDE {
@@ -33,20 +36,14 @@ impl Board {
     }
 
     fn get(&self, i: usize, j: usize) -> Option<u32> {
-        match Board::_flat_idx(i, j) {
-            None => None,
-            Some(fi) => Some(self.values[fi]),
-        }
+        Board::_flat_idx(i, j).map(|fi| self.values[fi])
     }
 
     fn is_marked(&self, i: usize, j: usize) -> Option<bool> {
-        match Board::_flat_idx(i, j) {
-            None => None,
-            Some(fi) => Some(self.marked[fi]),
-        }
+        Board::_flat_idx(i, j).map(|fi| self.marked[fi])
     }
 
-    fn is_won(&self) -> bool {
+    pub fn is_won(&self) -> bool {
         for i in 0..BOARD_SIDE {
             let mut all_marked_in_row = true;
             let mut all_marked_in_col = true;
@@ -62,7 +59,7 @@ impl Board {
                 return true;
             }
         }
-        return false;
+        false
     }
 
     fn unmarked(&self) -> impl Iterator<Item = u32> + '_ {
@@ -73,24 +70,32 @@ impl Board {
             .map(|(&value, _marked)| value)
     }
 
-    fn score(&self, last_drawn: &u32) -> u32 {
+    pub fn score(&self, last_drawn: &u32) -> u32 {
         self.unmarked().sum::<u32>() * last_drawn
     }
 
-    fn parse(s: &str) -> Board {
+    pub fn parse(s: &str) -> Result<Board, ParseError> {
         let values_vec: Vec<u32> = s
-            .replace("\n", " ")
-            .split(" ")
-            .filter(|s| s.len() > 0)
-            .map(|s| s.parse().expect("Cannot parse integer"))
-            .collect();
-        Board {
-            values: values_vec.try_into().expect("Size mismatch"),
+            .split_whitespace()
+            .map(|token| parse_number(s, token, "board value"))
+            .collect::<Result<_, _>>()?;
+        let values_n = values_vec.len();
+        Ok(Board {
+            values: values_vec.try_into().map_err(|_| {
+                ParseError::at_end(
+                    s,
+                    format!(
+                        "board must have {} values, found {}",
+                        BOARD_SIDE * BOARD_SIDE,
+                        values_n
+                    ),
+                )
+            })?,
             marked: [false; BOARD_SIDE * BOARD_SIDE],
-        }
+        })
     }
 
-    fn mark(&mut self, marked_value: &u32) {
+    pub fn mark(&mut self, marked_value: &u32) {
         for (fi, value) in self.values.iter().enumerate() {
             if value == marked_value {
                 self.marked[fi] = true;
@@ -99,40 +104,73 @@ impl Board {
     }
 }
 
-pub fn squid() {
-    let input = fs::read_to_string("data/day04/input.txt").expect("Can't read input file");
-    let mut input_blocks = input.split("\n\n");
-
-    let drawn_numbers: Vec<u32> = input_blocks
-        .next()
-        .unwrap()
-        .split(",")
-        .map(|s| s.parse().expect("Can't parse drawn number as u32"))
-        .collect();
-
-    let mut boards: Vec<Board> = input_blocks.map(Board::parse).collect();
-    let mut won_boards = vec![false; boards.len()];
-    let total_board_count = boards.len();
-
-    println!("drawing numbers");
-    for drawn_number in drawn_numbers {
-        print!("{} ", drawn_number);
-        for (board_idx, board) in boards.iter_mut().enumerate() {
-            board.mark(&drawn_number);
-            if board.is_won() && !won_boards[board_idx] {
-                won_boards[board_idx] = true;
-                let won_boards_count: usize = won_boards.iter().map(|&b| b as usize).sum();
-                if won_boards_count == 1 {
-                    println!("\n\nboard {} has won first", board_idx);
-                    board.print();
-                    println!("score = {}\n", board.score(&drawn_number));
-                }
-                if won_boards_count == total_board_count {
-                    println!("\n\nboard {} has won last", board_idx);
-                    board.print();
-                    println!("score = {}\n", board.score(&drawn_number));
+pub struct Bingo {
+    drawn_numbers: Vec<u32>,
+    boards: Vec<Board>,
+}
+
+impl Bingo {
+    /// Scores of the boards in the order they win
+    pub fn winning_scores(&self) -> Vec<u32> {
+        let mut boards = self.boards.clone();
+        let mut won_boards = vec![false; boards.len()];
+        let mut scores: Vec<u32> = Vec::new();
+
+        for drawn_number in &self.drawn_numbers {
+            for (board_idx, board) in boards.iter_mut().enumerate() {
+                board.mark(drawn_number);
+                if board.is_won() && !won_boards[board_idx] {
+                    won_boards[board_idx] = true;
+                    scores.push(board.score(drawn_number));
                 }
             }
         }
+        scores
+    }
+}
+
+pub struct Squid;
+
+impl Solver for Squid {
+    type Input = Bingo;
+
+    const DAY: u8 = 4;
+    const NAME: &'static str = "squid";
+
+    fn parse(&self, input: &str) -> Result<Bingo> {
+        let mut input_blocks = input.split("\n\n");
+
+        let drawn_numbers_str = expect_token(input, input_blocks.next(), "drawn numbers")
+            .map_err(|e| e.in_input(Self::DAY, input, input))?;
+        let drawn_numbers: Vec<u32> = drawn_numbers_str
+            .trim_end()
+            .split(",")
+            .map(|s| parse_number(drawn_numbers_str, s, "drawn number"))
+            .collect::<Result<_, _>>()
+            .map_err(|e| e.in_input(Self::DAY, input, drawn_numbers_str))?;
+
+        let boards = input_blocks
+            .filter(|block| !block.trim().is_empty())
+            .map(|block| Board::parse(block).map_err(|e| e.in_input(Self::DAY, input, block)))
+            .collect::<Result<_>>()?;
+
+        Ok(Bingo {
+            drawn_numbers,
+            boards,
+        })
+    }
+
+    fn part1(&self, bingo: &Bingo) -> Result<Answer> {
+        match bingo.winning_scores().first() {
+            Some(score) => Ok((*score).into()),
+            None => Err(Error::no_solution(Self::DAY, "no board has won")),
+        }
+    }
+
+    fn part2(&self, bingo: &Bingo) -> Result<Answer> {
+        match bingo.winning_scores().last() {
+            Some(score) => Ok((*score).into()),
+            None => Err(Error::no_solution(Self::DAY, "no board has won")),
+        }
     }
 }
@@ -1,56 +1,67 @@
-use std::fs;
+use super::error::{ParseError, Result};
+use super::solver::{Answer, Solver};
+use super::utils::{expect_token, parse_lines, parse_number};
 
 #[derive(Debug, Clone)]
-struct Point {
-    x: usize,
-    y: usize,
+pub struct Point {
+    pub x: usize,
+    pub y: usize,
 }
 
 impl Point {
-    fn parse(s: &str) -> Point {
-        let mut coords = s.split(",").map(|s| {
-            s.parse::<usize>()
-                .expect("Can't parse integer value from point spec")
-        });
-        Point {
-            x: coords.next().unwrap(),
-            y: coords.next().unwrap(),
-        }
+    pub fn parse(s: &str) -> Result<Point, ParseError> {
+        let mut coords = s.split(",");
+        Ok(Point {
+            x: parse_number(
+                s,
+                expect_token(s, coords.next(), "x coordinate")?,
+                "x coordinate",
+            )?,
+            y: parse_number(
+                s,
+                expect_token(s, coords.next(), "y coordinate")?,
+                "y coordinate",
+            )?,
+        })
     }
 }
 
 #[derive(Debug, Clone)]
-struct Line {
-    start: Point,
-    end: Point,
+pub struct Line {
+    pub start: Point,
+    pub end: Point,
 }
 
 impl Line {
-    fn is_hv(&self) -> bool {
+    pub fn is_hv(&self) -> bool {
         self.start.x == self.end.x || self.start.y == self.end.y
     }
 
-    fn is_45deg(&self) -> bool {
+    pub fn is_45deg(&self) -> bool {
         fn diff(v1: usize, v2: usize) -> usize {
-            (v1 as i32 - v2 as i32).abs() as usize
+            (v1 as i32 - v2 as i32).unsigned_abs() as usize
         }
 
         diff(self.start.x, self.end.x) == diff(self.start.y, self.end.y)
     }
 
-    fn parse(s: &str) -> Line {
-        let mut points = s.split(" -> ").map(Point::parse);
-        Line {
-            start: points.next().unwrap(),
-            end: points.next().unwrap(),
-        }
+    pub fn parse(s: &str) -> Result<Line, ParseError> {
+        let mut points = s.split(" -> ");
+        let mut next_point = |what: &str| {
+            let point_str = expect_token(s, points.next(), what)?;
+            Point::parse(point_str).map_err(|e| e.within(s, point_str))
+        };
+        Ok(Line {
+            start: next_point("line start")?,
+            end: next_point("line end")?,
+        })
     }
 
     fn ends(&self) -> Vec<&Point> {
         vec![&self.start, &self.end]
     }
 
-    fn points(&self) -> Vec<Point> {
+    pub fn points(&self) -> Vec<Point> {
         fn range_ordered(v1: usize, v2: usize) -> Vec<usize> {
             match v2 > v1 {
                 true => (v1..=v2).collect(),
@@ -97,12 +108,11 @@ struct LineField<'a> {
 
 impl LineField<'_> {
     fn from_lines<'a>(lines: Vec<&'a Line>) -> LineField<'a> {
-        let (mut min_x, mut min_y, mut max_x, mut max_y) = (
-            lines[0].start.x,
-            lines[0].start.y,
-            lines[0].start.x,
-            lines[0].start.y,
-        );
+        let (mut min_x, mut min_y, mut max_x, mut max_y) = match lines.first() {
+            Some(line) => (line.start.x, line.start.y, line.start.x, line.start.y),
+            // e.g. no horizontal or vertical lines, nothing overlaps on a single empty cell
+            None => (0, 0, 0, 0),
+        };
         // very ugly, how to rewrite with iterators?
         for line in &lines {
             for pt in line.ends() {
@@ -166,23 +176,35 @@ impl LineField<'_> {
     }
 }
 
-pub fn vent_lines() {
-    let input = fs::read_to_string("data/day05/input.txt").expect("Can't read input file");
-
-    let all_lines: Vec<Line> = input.lines().map(Line::parse).collect();
-
-    // part 1
-    let field = LineField::from_lines(all_lines.iter().filter(|l| l.is_hv()).collect());
-    // field.print();
-    println!("number of overlapping points: {}", field.count_overlaps());
-
-    // part 2
-    let field = LineField::from_lines(
-        all_lines
-            .iter()
-            .filter(|l| l.is_hv() || l.is_45deg())
-            .collect(),
-    );
-    // field.print();
-    println!("number of overlapping points: {}", field.count_overlaps());
+pub struct VentLines;
+
+impl Solver for VentLines {
+    type Input = Vec<Line>;
+
+    const DAY: u8 = 5;
+    const NAME: &'static str = "vent_lines";
+
+    fn parse(&self, input: &str) -> Result<Vec<Line>> {
+        if input.trim().is_empty() {
+            return Err(ParseError::at_end(input, "no lines").in_input(Self::DAY, input, input));
+        }
+        parse_lines(Self::DAY, input, Line::parse)
+    }
+
+    fn part1(&self, all_lines: &Vec<Line>) -> Result<Answer> {
+        let field = LineField::from_lines(all_lines.iter().filter(|l| l.is_hv()).collect());
+        // field.print();
+        Ok(field.count_overlaps().into())
+    }
+
+    fn part2(&self, all_lines: &Vec<Line>) -> Result<Answer> {
+        let field = LineField::from_lines(
+            all_lines
+                .iter()
+                .filter(|l| l.is_hv() || l.is_45deg())
+                .collect(),
+        );
+        // field.print();
+        Ok(field.count_overlaps().into())
+    }
 }
@@ -1,4 +1,6 @@
-use std::fs;
+use super::error::{ParseError, Result};
+use super::solver::{Answer, Solver};
+use super::utils::parse_number;
 
 fn count_combinations(n: usize, r: usize) -> usize {
     if r > n {
@@ -8,6 +10,7 @@ fn count_combinations(n: usize, r: usize) -> usize {
     }
 }
 
+#[allow(clippy::needless_range_loop)]
 fn count_lanterfish(t: usize) -> usize {
     let a: usize = 7;
     let b: usize = 9;
@@ -50,21 +53,41 @@ fn count_lanterfish(t: usize) -> usize {
     total
 }
 
-pub fn lanternfish() {
-    let input = fs::read_to_string("data/day06/input.txt").expect("Can't read input file");
-    let n0s: Vec<usize> = input
-        .lines()
-        .next()
-        .expect("Input must contain at least one line")
-        .split(',')
-        .map(|dts| dts.parse().expect("Unable to parse integer"))
-        .collect();
+fn count_school(n0s: &Vec<usize>, n_days: usize) -> usize {
+    let mut total: usize = 0;
+    for n0 in n0s {
+        total += count_lanterfish(n_days - n0);
+    }
+    total
+}
 
-    for n_days in [80, 256] {
-        let mut total: usize = 0;
-        for n0 in &n0s {
-            total += count_lanterfish(n_days - n0);
-        }
-        println!("After {} days: {}", n_days, total);
+pub struct Lanternfish;
+
+impl Solver for Lanternfish {
+    type Input = Vec<usize>;
+
+    const DAY: u8 = 6;
+    const NAME: &'static str = "lanternfish";
+
+    fn parse(&self, input: &str) -> Result<Vec<usize>> {
+        let line = input.lines().next().unwrap_or("");
+        line.split(',')
+            .map(|dts| {
+                let n0: usize = parse_number(line, dts, "timer value")?;
+                if n0 > 8 {
+                    return Err(ParseError::at(line, dts, "timer value must be at most 8"));
+                }
+                Ok(n0)
+            })
+            .collect::<Result<_, _>>()
+            .map_err(|e| e.in_input(Self::DAY, input, line))
+    }
+
+    fn part1(&self, n0s: &Vec<usize>) -> Result<Answer> {
+        Ok(count_school(n0s, 80).into())
+    }
+
+    fn part2(&self, n0s: &Vec<usize>) -> Result<Answer> {
+        Ok(count_school(n0s, 256).into())
     }
 }
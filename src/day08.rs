@@ -1,5 +1,7 @@
+use super::error::{Error, ParseError, Result};
+use super::solver::{Answer, Solver};
+use super::utils::{expect_token, parse_lines};
 use std::collections::HashMap;
-use std::fs;
 
 const N_SEGMENTS: usize = 7;
 const N_DIGITS: usize = 10;
@@ -38,40 +40,90 @@ impl OccurenceBasedHash {
     }
 }
 
-pub fn messed_up_displays() {
-    let input = fs::read_to_string("data/day08/input.txt").expect("Can't read input file");
+pub struct DisplayEntry {
+    digits: String,
+    readings: String,
+}
+
+impl DisplayEntry {
+    pub fn parse(s: &str) -> Result<DisplayEntry, ParseError> {
+        let mut parts = s.split(" | ");
+        let digits = expect_token(s, parts.next(), "digits")?;
+        let readings = expect_token(s, parts.next(), "readings")?;
+        for part in [digits, readings] {
+            for (idx, ch) in part.char_indices() {
+                if ch != ' ' && !('a'..='g').contains(&ch) {
+                    return Err(ParseError::at(
+                        s,
+                        &part[idx..idx + ch.len_utf8()],
+                        format!("segment must be a letter from 'a' to 'g', not '{}'", ch),
+                    ));
+                }
+            }
+        }
+        Ok(DisplayEntry {
+            digits: digits.to_string(),
+            readings: readings.to_string(),
+        })
+    }
+}
+
+pub struct MessedUpDisplays;
+
+impl Solver for MessedUpDisplays {
+    type Input = Vec<DisplayEntry>;
 
-    let mut digit_by_ob_hash: HashMap<OccurenceBasedHash, usize> = HashMap::new();
-    let segment_occurrences_in_digits = count_segment_occurrences(DIGITS);
-    for (i, d) in DIGITS.split(' ').enumerate() {
-        digit_by_ob_hash.insert(
-            OccurenceBasedHash::calculate(d, segment_occurrences_in_digits),
-            i,
-        );
+    const DAY: u8 = 8;
+    const NAME: &'static str = "messed_up_displays";
+
+    fn parse(&self, input: &str) -> Result<Vec<DisplayEntry>> {
+        parse_lines(Self::DAY, input, DisplayEntry::parse)
     }
 
-    let mut sum_of_readings: u32 = 0;
-    for entry in input.lines() {
-        let messed_up_digits = entry.split(" | ").next().expect("Can't get digits");
-        let segment_occurences = count_segment_occurrences(messed_up_digits);
-
-        let messed_up_readings = entry.split(" | ").nth(1).expect("Can't get reading");
-        let readings: Vec<&usize> = messed_up_readings
-            .split(' ')
-            .map(|mur| {
-                digit_by_ob_hash
-                    .get(&OccurenceBasedHash::calculate(mur, segment_occurences))
-                    .unwrap()
-            })
-            .collect();
-        let reading: u32 = readings
+    /// digits 1, 4, 7 and 8 are the only ones with unique number of segments
+    fn part1(&self, entries: &Vec<DisplayEntry>) -> Result<Answer> {
+        Ok(entries
             .iter()
-            .rev()
-            .enumerate()
-            .map(|(i, r)| (**r as u32) * (10 as u32).pow(i as u32))
-            .sum();
+            .flat_map(|e| e.readings.split(' '))
+            .filter(|mur| [2, 3, 4, 7].contains(&mur.len()))
+            .count()
+            .into())
+    }
+
+    fn part2(&self, entries: &Vec<DisplayEntry>) -> Result<Answer> {
+        let mut digit_by_ob_hash: HashMap<OccurenceBasedHash, usize> = HashMap::new();
+        let segment_occurrences_in_digits = count_segment_occurrences(DIGITS);
+        for (i, d) in DIGITS.split(' ').enumerate() {
+            digit_by_ob_hash.insert(
+                OccurenceBasedHash::calculate(d, segment_occurrences_in_digits),
+                i,
+            );
+        }
 
-        sum_of_readings += reading;
+        let mut sum_of_readings: u32 = 0;
+        for entry in entries {
+            let segment_occurences = count_segment_occurrences(&entry.digits);
+
+            let readings: Vec<&usize> = entry
+                .readings
+                .split(' ')
+                .map(|mur| {
+                    digit_by_ob_hash
+                        .get(&OccurenceBasedHash::calculate(mur, segment_occurences))
+                        .ok_or_else(|| {
+                            Error::no_solution(Self::DAY, format!("can't decode reading '{}'", mur))
+                        })
+                })
+                .collect::<Result<_>>()?;
+            let reading: u32 = readings
+                .iter()
+                .rev()
+                .enumerate()
+                .map(|(i, r)| (**r as u32) * 10_u32.pow(i as u32))
+                .sum();
+
+            sum_of_readings += reading;
+        }
+        Ok(sum_of_readings.into())
     }
-    println!("{:?}", sum_of_readings);
 }
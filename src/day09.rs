@@ -1,8 +1,10 @@
-use super::utils::read_input;
+use super::error::{ParseError, Result};
+use super::solver::{Answer, Solver};
+use super::utils::parse_digit_grid;
 use std::collections::HashSet;
 
 #[derive(Debug)]
-struct Heightmap {
+pub struct Heightmap {
     map: Vec<Vec<u8>>,
     delta_up: Vec<Vec<i16>>,
     delta_right: Vec<Vec<i16>>,
@@ -10,20 +12,18 @@ struct Heightmap {
     delta_left: Vec<Vec<i16>>,
 }
 
-fn vec2d_size(vv: &Vec<Vec<u8>>) -> (usize, usize) {
+fn vec2d_size(vv: &[Vec<u8>]) -> (usize, usize) {
     (vv.iter().next().unwrap().len(), vv.len())
 }
 
 impl Heightmap {
     const MAX: u8 = 9;
 
-    fn parse(s: &String) -> Heightmap {
-        let map: Vec<Vec<u8>> = s
-            .lines()
-            .map(|l| l.chars().map(|ch| ch.to_digit(10).unwrap() as u8).collect())
-            .collect();
+    pub fn parse(s: &str) -> Result<Heightmap, ParseError> {
+        let map: Vec<Vec<u8>> = parse_digit_grid(s)?;
 
-        fn delta_map(map: &Vec<Vec<u8>>, di: i16, dj: i16) -> Vec<Vec<i16>> {
+        #[allow(clippy::needless_range_loop)]
+        fn delta_map(map: &[Vec<u8>], di: i16, dj: i16) -> Vec<Vec<i16>> {
             let mut delta_map: Vec<Vec<i16>> = map
                 .iter()
                 .map(|r| r.iter().map(|&d| d as i16).collect())
@@ -51,16 +51,17 @@ impl Heightmap {
             delta_map
         }
 
-        Heightmap {
+        Ok(Heightmap {
             delta_up: delta_map(&map, -1, 0),
             delta_right: delta_map(&map, 0, 1),
             delta_down: delta_map(&map, 1, 0),
             delta_left: delta_map(&map, 0, -1),
             map,
-        }
+        })
     }
 
-    fn lowest_mask(&self) -> Vec<Vec<bool>> {
+    #[allow(clippy::needless_range_loop)]
+    pub fn lowest_mask(&self) -> Vec<Vec<bool>> {
         let (width, height) = vec2d_size(&self.map);
         let mut is_lowest: Vec<Vec<bool>> = vec![vec![false; width]; height];
         for i in 0..height {
@@ -74,6 +75,7 @@ impl Heightmap {
         is_lowest
     }
 
+    #[allow(dead_code)]
     fn print(&self) {
         let lowest_mask = self.lowest_mask();
         for (i, row) in self.map.iter().enumerate() {
@@ -89,7 +91,7 @@ impl Heightmap {
         }
     }
 
-    fn risk_levels_sum(&self) -> u32 {
+    pub fn risk_levels_sum(&self) -> u32 {
         let lowest_mask = self.lowest_mask();
         let mut res: u32 = 0;
         for (i, row) in self.map.iter().enumerate() {
@@ -126,7 +128,8 @@ impl Heightmap {
         basin
     }
 
-    fn basins(&self) -> Vec<HashSet<(usize, usize)>> {
+    #[allow(clippy::needless_range_loop)]
+    pub fn basins(&self) -> Vec<HashSet<(usize, usize)>> {
         let lowest_mask = self.lowest_mask();
         let (width, height) = vec2d_size(&self.map);
         let mut basins: Vec<HashSet<(usize, usize)>> = Vec::new();
@@ -142,18 +145,28 @@ impl Heightmap {
     }
 }
 
-pub fn smoke_basin() {
-    let input = read_input(9, false);
-    let hm = Heightmap::parse(&input);
-    hm.print();
+pub struct SmokeBasin;
+
+impl Solver for SmokeBasin {
+    type Input = Heightmap;
 
-    // part 1
-    println!("Total risk level is {}", hm.risk_levels_sum());
+    const DAY: u8 = 9;
+    const NAME: &'static str = "smoke_basin";
+
+    fn parse(&self, input: &str) -> Result<Heightmap> {
+        Heightmap::parse(input).map_err(|e| e.in_input(Self::DAY, input, input))
+    }
 
-    // part 2
-    let basins = hm.basins();
-    let mut basin_sizes: Vec<usize> = basins.iter().map(|b| b.len()).collect();
-    basin_sizes.sort_by(|a, b| b.cmp(a));
-    let first_three_sum: usize = basin_sizes.iter().take(3).product();
-    println!("Sum of the three larges basin sizes: {}", first_three_sum);
+    fn part1(&self, hm: &Heightmap) -> Result<Answer> {
+        Ok(hm.risk_levels_sum().into())
+    }
+
+    /// product of the three largest basin sizes
+    fn part2(&self, hm: &Heightmap) -> Result<Answer> {
+        let basins = hm.basins();
+        let mut basin_sizes: Vec<usize> = basins.iter().map(|b| b.len()).collect();
+        basin_sizes.sort_by(|a, b| b.cmp(a));
+        let first_three_product: usize = basin_sizes.iter().take(3).product();
+        Ok(first_three_product.into())
+    }
 }
@@ -1,127 +1,156 @@
-use super::utils::read_input;
-
-#[derive(PartialEq)]
-enum BracketType {
-    Regular,
-    Square,
-    Curly,
-    Angle,
+pub mod checker;
+pub mod repair;
+pub mod stream;
+pub mod table;
+
+use super::error::{Error, ParseError, Result};
+use super::solver::{Answer, Solver};
+use super::utils::parse_lines;
+use checker::{BracketChecker, LineStatus};
+use table::BracketTable;
+
+/// Pair of delimiters, the same character on both ends makes a quote-like one
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct BracketType {
+    opening: char,
+    closing: char,
 }
 
 impl BracketType {
-    fn corrupted_score(&self) -> u32 {
-        match self {
-            BracketType::Regular => 3,
-            BracketType::Square => 57,
-            BracketType::Curly => 1197,
-            BracketType::Angle => 25137,
-        }
+    pub const REGULAR: BracketType = BracketType::new('(', ')');
+    pub const SQUARE: BracketType = BracketType::new('[', ']');
+    pub const CURLY: BracketType = BracketType::new('{', '}');
+    pub const ANGLE: BracketType = BracketType::new('<', '>');
+
+    pub const fn new(opening: char, closing: char) -> BracketType {
+        BracketType { opening, closing }
     }
-    fn incomplete_score(&self) -> u64 {
-        match self {
-            BracketType::Regular => 1,
-            BracketType::Square => 2,
-            BracketType::Curly => 3,
-            BracketType::Angle => 4,
-        }
+
+    pub fn opening(&self) -> char {
+        self.opening
+    }
+
+    pub fn closing(&self) -> char {
+        self.closing
+    }
+
+    pub fn is_symmetric(&self) -> bool {
+        self.opening == self.closing
     }
 }
 
-enum BracketRole {
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BracketRole {
     Opening,
     Closing,
+    /// symmetric delimiter, closes if its type is the last one opened and opens otherwise
+    Either,
 }
 
-struct Bracket {
-    role: BracketRole,
-    type_: BracketType,
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Bracket {
+    pub role: BracketRole,
+    pub type_: BracketType,
 }
 
-impl Bracket {
-    fn parse(c: &char) -> Bracket {
-        match c {
-            '(' => Bracket {
-                role: BracketRole::Opening,
-                type_: BracketType::Regular,
-            },
-            '[' => Bracket {
-                role: BracketRole::Opening,
-                type_: BracketType::Square,
-            },
-            '{' => Bracket {
-                role: BracketRole::Opening,
-                type_: BracketType::Curly,
-            },
-            '<' => Bracket {
-                role: BracketRole::Opening,
-                type_: BracketType::Angle,
-            },
-            ')' => Bracket {
-                role: BracketRole::Closing,
-                type_: BracketType::Regular,
-            },
-            ']' => Bracket {
-                role: BracketRole::Closing,
-                type_: BracketType::Square,
-            },
-            '}' => Bracket {
-                role: BracketRole::Closing,
-                type_: BracketType::Curly,
-            },
-            '>' => Bracket {
-                role: BracketRole::Closing,
-                type_: BracketType::Angle,
-            },
-            _ => {
-                panic!("Can't parse bracket!")
-            }
-        }
-    }
+pub struct NavigationSubsystem {
+    table: BracketTable,
+    lines: Vec<LineStatus>,
 }
 
-pub fn brackets_parsing() {
-    let input = read_input(10, false);
-
-    let mut corrupted_lines_score: u32 = 0; // pt 1
-    let mut incomplete_line_scores: Vec<u64> = Vec::new(); // pt 2
-    for line in input.lines() {
-        let brackets: Vec<Bracket> = line.chars().map(|c| Bracket::parse(&c)).collect();
-        let mut stack: Vec<BracketType> = Vec::new();
-
-        let mut is_corrupted = false;
-        for b in brackets {
-            match b.role {
-                BracketRole::Opening => stack.push(b.type_),
-                BracketRole::Closing => match stack.pop() {
-                    Some(type_) => {
-                        if type_ != b.type_ {
-                            corrupted_lines_score += b.type_.corrupted_score();
-                            is_corrupted = true;
-                            break;
-                        }
-                    }
-                    None => {
-                        panic!("We can't handle unopened brackets yet!")
+impl NavigationSubsystem {
+    pub fn new(table: BracketTable, lines: Vec<LineStatus>) -> NavigationSubsystem {
+        NavigationSubsystem { table, lines }
+    }
+
+    /// corrupted line score for each corrupted line and incomplete line score for each incomplete one
+    pub fn scores(&self) -> Result<(u64, Vec<u64>)> {
+        let overflow = |what: &str| {
+            Error::no_solution(
+                BracketsParsing::DAY,
+                format!("{} doesn't fit in 64 bits", what),
+            )
+        };
+        let base = self.table.completion_base();
+        let mut corrupted_lines_score: u64 = 0; // pt 1
+        let mut incomplete_line_scores: Vec<u64> = Vec::new(); // pt 2
+        for (line_idx, status) in self.lines.iter().enumerate() {
+            match status {
+                LineStatus::Valid => {}
+                LineStatus::Corrupted { found, .. } => {
+                    corrupted_lines_score = corrupted_lines_score
+                        .checked_add(self.table.scores(found).corrupted)
+                        .ok_or_else(|| overflow("total score of the corrupted lines"))?;
+                }
+                LineStatus::Incomplete { completion } => {
+                    let mut incomplete_line_score: u64 = 0;
+                    for b in completion.chars().filter_map(|c| self.table.bracket(c)) {
+                        incomplete_line_score = incomplete_line_score
+                            .checked_mul(base)
+                            .and_then(|score| {
+                                score.checked_add(self.table.scores(&b.type_).incomplete)
+                            })
+                            .ok_or_else(|| overflow(&format!("score of line {}", line_idx + 1)))?;
                     }
-                },
+                    incomplete_line_scores.push(incomplete_line_score);
+                }
+                LineStatus::Unopened { column, .. } => {
+                    return Err(Error::Parse {
+                        day: BracketsParsing::DAY,
+                        line: line_idx + 1,
+                        column: *column,
+                        message: "closing bracket was never opened".to_string(),
+                    })
+                }
             }
         }
-        if !is_corrupted && stack.len() > 0 {
-            let mut incomplete_line_score: u64 = 0;
-            for b in stack.iter().rev() {
-                incomplete_line_score *= 5;
-                incomplete_line_score += b.incomplete_score();
+        Ok((corrupted_lines_score, incomplete_line_scores))
+    }
+}
+
+pub struct BracketsParsing;
+
+impl Solver for BracketsParsing {
+    type Input = NavigationSubsystem;
+
+    const DAY: u8 = 10;
+    const NAME: &'static str = "brackets_parsing";
+
+    fn parse(&self, input: &str) -> Result<NavigationSubsystem> {
+        let table = BracketTable::default();
+        let checker = BracketChecker::with_table(table.clone());
+        let lines = parse_lines(Self::DAY, input, |line| {
+            // the checker skips anything else, the puzzle input has only brackets
+            if let Some((idx, c)) = line
+                .char_indices()
+                .find(|(_, c)| table.bracket(*c).is_none())
+            {
+                return Err(ParseError::at(
+                    line,
+                    &line[idx..idx + c.len_utf8()],
+                    format!("'{}' is not a bracket", c),
+                ));
             }
-            incomplete_line_scores.push(incomplete_line_score);
+            Ok(checker.check_line(line))
+        })?;
+        Ok(NavigationSubsystem::new(table, lines))
+    }
+
+    /// total score for corrupted lines
+    fn part1(&self, subsystem: &NavigationSubsystem) -> Result<Answer> {
+        Ok(subsystem.scores()?.0.into())
+    }
+
+    /// middle score of the incomplete lines
+    fn part2(&self, subsystem: &NavigationSubsystem) -> Result<Answer> {
+        let mut incomplete_line_scores = subsystem.scores()?.1;
+        if incomplete_line_scores.is_empty() {
+            return Err(Error::no_solution(
+                Self::DAY,
+                "there are no incomplete lines",
+            ));
         }
+        incomplete_line_scores.sort();
+        Ok(incomplete_line_scores[incomplete_line_scores.len() / 2].into())
     }
-    println!(
-        "Total score for corrupted lines is {}",
-        corrupted_lines_score
-    );
-    incomplete_line_scores.sort();
-    println!(
-        "Incomplete line scores: {:?}",
-        incomplete_line_scores[incomplete_line_scores.len() / 2]
-    );
 }
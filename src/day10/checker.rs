@@ -0,0 +1,131 @@
+//! Bracket matching on arbitrary text, one line at a time
+//!
+//! Characters other than brackets are skipped, so the checker works on source code as well as
+//! on the puzzle input.
+
+use super::table::BracketTable;
+use super::{BracketRole, BracketType};
+
+/// What the checker found in a line, columns are 1-based and count characters
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LineStatus {
+    Valid,
+    /// closing bracket that doesn't match the last opened one
+    Corrupted {
+        column: usize,
+        expected: BracketType,
+        found: BracketType,
+    },
+    /// some brackets are left open, `completion` closes them in order
+    Incomplete {
+        completion: String,
+    },
+    /// closing bracket with nothing opened before it
+    Unopened {
+        column: usize,
+        found: BracketType,
+    },
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct BracketChecker {
+    table: BracketTable,
+}
+
+impl BracketChecker {
+    /// Brackets of the puzzle
+    pub fn new() -> BracketChecker {
+        BracketChecker::default()
+    }
+
+    pub fn with_table(table: BracketTable) -> BracketChecker {
+        BracketChecker { table }
+    }
+
+    pub fn table(&self) -> &BracketTable {
+        &self.table
+    }
+
+    pub fn check_line(&self, line: &str) -> LineStatus {
+        let mut state = LineState::default();
+        for c in line.chars() {
+            if let Some(status) = state.feed(&self.table, c) {
+                return status;
+            }
+        }
+        state.finish()
+    }
+
+    /// Status of every line of the text, `stream` checks a reader without loading it
+    pub fn check(&self, text: &str) -> Vec<LineStatus> {
+        text.lines().map(|line| self.check_line(line)).collect()
+    }
+}
+
+/// Bracket stack of a line that is fed one character at a time
+#[derive(Debug, Default)]
+pub(crate) struct LineState {
+    stack: Vec<BracketType>,
+    /// characters fed so far
+    column: usize,
+}
+
+impl LineState {
+    /// Corrupted or unopened status as soon as the character makes the line so
+    pub(crate) fn feed(&mut self, table: &BracketTable, c: char) -> Option<LineStatus> {
+        self.column += 1;
+        let bracket = table.bracket(c)?;
+        // a symmetric delimiter closes only what it has opened itself
+        let closes = match bracket.role {
+            BracketRole::Opening => false,
+            BracketRole::Closing => true,
+            BracketRole::Either => self.stack.last() == Some(&bracket.type_),
+        };
+        if !closes {
+            self.stack.push(bracket.type_);
+            return None;
+        }
+        match self.stack.pop() {
+            Some(expected) if expected != bracket.type_ => Some(LineStatus::Corrupted {
+                column: self.column,
+                expected,
+                found: bracket.type_,
+            }),
+            Some(_) => None,
+            None => Some(LineStatus::Unopened {
+                column: self.column,
+                found: bracket.type_,
+            }),
+        }
+    }
+
+    pub(crate) fn depth(&self) -> usize {
+        self.stack.len()
+    }
+
+    pub(crate) fn column(&self) -> usize {
+        self.column
+    }
+
+    /// Valid or incomplete status at the end of the line, the state is ready for the next one
+    pub(crate) fn finish(&mut self) -> LineStatus {
+        self.column = 0;
+        if self.stack.is_empty() {
+            LineStatus::Valid
+        } else {
+            LineStatus::Incomplete {
+                completion: self
+                    .stack
+                    .drain(..)
+                    .rev()
+                    .map(|type_| type_.closing())
+                    .collect(),
+            }
+        }
+    }
+
+    pub(crate) fn reset(&mut self) {
+        self.stack.clear();
+        self.column = 0;
+    }
+}
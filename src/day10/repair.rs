@@ -0,0 +1,193 @@
+//! Fewest edits that balance a line
+//!
+//! Interval dynamic programming over the brackets of the line: the first bracket of a span is
+//! either deleted, given an inserted partner or paired with some later bracket, substituting
+//! whichever of the two doesn't fit. Cubic in the number of brackets, which is fine for lines
+//! of code but not for whole files. Other characters are kept as they are.
+
+use super::checker::{BracketChecker, LineStatus};
+use super::{BracketRole, BracketType};
+
+/// Columns are 1-based and point into the original line, an insertion goes before its column
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Edit {
+    Insert { column: usize, c: char },
+    Delete { column: usize, c: char },
+    Substitute { column: usize, from: char, to: char },
+}
+
+impl Edit {
+    fn column(&self) -> usize {
+        match self {
+            Edit::Insert { column, .. }
+            | Edit::Delete { column, .. }
+            | Edit::Substitute { column, .. } => *column,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Repair {
+    /// in the order of the columns
+    pub edits: Vec<Edit>,
+    /// the line with the edits applied
+    pub line: String,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Choice {
+    Delete,
+    /// closing partner inserted for the first bracket
+    Insert,
+    /// first bracket paired with the one at the index, both turned into the type if needed
+    Pair(usize, BracketType),
+}
+
+struct Solver<'a> {
+    /// char index in the line with the char and the role it has on its own
+    brackets: Vec<(usize, char, BracketRole)>,
+    types: &'a [BracketType],
+    /// cost and choice for brackets[i..j] at [i][j]
+    best: Vec<Vec<(usize, Choice)>>,
+}
+
+impl<'a> Solver<'a> {
+    fn pair_cost(&self, i: usize, k: usize) -> (usize, BracketType) {
+        let (_, first, _) = self.brackets[i];
+        let (_, second, _) = self.brackets[k];
+        self.types
+            .iter()
+            .map(|t| {
+                let cost = (first != t.opening()) as usize + (second != t.closing()) as usize;
+                (cost, *t)
+            })
+            .min_by_key(|(cost, _)| *cost)
+            .expect("the table has no brackets")
+    }
+
+    fn solve(&mut self) {
+        let n = self.brackets.len();
+        self.best = vec![vec![(0, Choice::Delete); n + 1]; n + 1];
+        for len in 1..=n {
+            for i in 0..=n - len {
+                let j = i + len;
+                // both cost one edit, stray closers go and openers get closed as in a completion
+                let alone = match self.brackets[i].2 {
+                    BracketRole::Closing => Choice::Delete,
+                    _ => Choice::Insert,
+                };
+                let mut best = (1 + self.best[i + 1][j].0, alone);
+                for k in i + 1..j {
+                    let (cost, type_) = self.pair_cost(i, k);
+                    let cost = cost + self.best[i + 1][k].0 + self.best[k + 1][j].0;
+                    if cost < best.0 {
+                        best = (cost, Choice::Pair(k, type_));
+                    }
+                }
+                self.best[i][j] = best;
+            }
+        }
+    }
+
+    /// Edits for brackets[i..j], insertions at the end of the span go before the 0-based `end`
+    fn edits(&self, i: usize, j: usize, end: usize, edits: &mut Vec<Edit>) {
+        if i == j {
+            return;
+        }
+        let (pos, c, _) = self.brackets[i];
+        match self.best[i][j].1 {
+            Choice::Delete => {
+                edits.push(Edit::Delete { column: pos + 1, c });
+                self.edits(i + 1, j, end, edits);
+            }
+            Choice::Insert => {
+                // closes the whole span
+                self.edits(i + 1, j, end, edits);
+                edits.push(Edit::Insert {
+                    column: end + 1,
+                    c: self.type_of(c).closing(),
+                });
+            }
+            Choice::Pair(k, type_) => {
+                if c != type_.opening() {
+                    edits.push(Edit::Substitute {
+                        column: pos + 1,
+                        from: c,
+                        to: type_.opening(),
+                    });
+                }
+                let (closing_pos, closing, _) = self.brackets[k];
+                self.edits(i + 1, k, closing_pos, edits);
+                if closing != type_.closing() {
+                    edits.push(Edit::Substitute {
+                        column: closing_pos + 1,
+                        from: closing,
+                        to: type_.closing(),
+                    });
+                }
+                self.edits(k + 1, j, end, edits);
+            }
+        }
+    }
+
+    fn type_of(&self, c: char) -> BracketType {
+        *self
+            .types
+            .iter()
+            .find(|t| t.opening() == c || t.closing() == c)
+            .expect("brackets come from the table")
+    }
+}
+
+impl BracketChecker {
+    /// Fewest insertions, deletions and substitutions that make the line valid
+    pub fn repair(&self, line: &str) -> Repair {
+        if self.check_line(line) == LineStatus::Valid {
+            return Repair {
+                edits: Vec::new(),
+                line: line.to_string(),
+            };
+        }
+        let brackets: Vec<(usize, char, BracketRole)> = line
+            .chars()
+            .enumerate()
+            .filter_map(|(pos, c)| Some((pos, c, self.table().bracket(c)?.role)))
+            .collect();
+        let types: Vec<BracketType> = self.table().types().collect();
+        let mut solver = Solver {
+            brackets,
+            types: &types,
+            best: Vec::new(),
+        };
+        solver.solve();
+        let mut edits = Vec::new();
+        let n = solver.brackets.len();
+        solver.edits(0, n, line.chars().count(), &mut edits);
+
+        // insertions at the same column stay in the order they were made
+        edits.sort_by_key(|edit| edit.column());
+        let mut repaired = String::with_capacity(line.len() + edits.len());
+        let mut pending = edits.iter().peekable();
+        for (pos, c) in line.chars().enumerate() {
+            let mut c = Some(c);
+            while let Some(edit) = pending.next_if(|edit| edit.column() == pos + 1) {
+                match edit {
+                    Edit::Insert { c, .. } => repaired.push(*c),
+                    Edit::Delete { .. } => c = None,
+                    Edit::Substitute { to, .. } => c = Some(*to),
+                }
+            }
+            repaired.extend(c);
+        }
+        // only insertions at the end of the line are left
+        for edit in pending {
+            if let Edit::Insert { c, .. } = edit {
+                repaired.push(*c);
+            }
+        }
+        Repair {
+            edits,
+            line: repaired,
+        }
+    }
+}
@@ -0,0 +1,186 @@
+//! Bracket checking over a reader, for inputs that don't fit into memory
+//!
+//! Bytes are decoded as UTF-8 on the fly, an invalid sequence counts as a single character that
+//! is not a bracket. Memory is bounded by the read buffer and the nesting limit whatever the
+//! length of the lines, and problems are reported as soon as they are found.
+
+use std::fmt;
+use std::io::{self, BufRead, BufReader, ErrorKind, Read};
+
+use super::checker::{BracketChecker, LineState, LineStatus};
+
+/// Brackets a line may keep open before the stream gives up on it
+pub const DEFAULT_MAX_DEPTH: usize = 1 << 16;
+
+/// Line that is not valid, 1-based
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Report {
+    pub line: usize,
+    pub status: LineStatus,
+}
+
+#[derive(Debug)]
+pub enum StreamError {
+    /// the stream ends after it
+    Io(io::Error),
+    /// more brackets open than the limit, the rest of the line is skipped
+    TooDeep { line: usize, column: usize },
+}
+
+impl fmt::Display for StreamError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            StreamError::Io(source) => write!(f, "can't read brackets: {}", source),
+            StreamError::TooDeep { line, column } => {
+                write!(f, "{}:{}: brackets are nested too deep", line, column)
+            }
+        }
+    }
+}
+
+impl std::error::Error for StreamError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            StreamError::Io(source) => Some(source),
+            StreamError::TooDeep { .. } => None,
+        }
+    }
+}
+
+/// Reports of the lines that are not valid, in the order of the input
+pub struct BracketStream<'a, R> {
+    checker: &'a BracketChecker,
+    reader: BufReader<R>,
+    state: LineState,
+    max_depth: usize,
+    /// 1-based number of the line being read
+    line: usize,
+    /// the line already has a report, the rest of it doesn't matter
+    skipping: bool,
+    done: bool,
+}
+
+impl<'a, R: Read> BracketStream<'a, R> {
+    pub fn new(checker: &'a BracketChecker, reader: R) -> BracketStream<'a, R> {
+        BracketStream {
+            checker,
+            reader: BufReader::new(reader),
+            state: LineState::default(),
+            max_depth: DEFAULT_MAX_DEPTH,
+            line: 1,
+            skipping: false,
+            done: false,
+        }
+    }
+
+    pub fn with_max_depth(mut self, max_depth: usize) -> BracketStream<'a, R> {
+        self.max_depth = max_depth;
+        self
+    }
+
+    fn peek_byte(&mut self) -> io::Result<Option<u8>> {
+        loop {
+            match self.reader.fill_buf() {
+                Ok(buf) => return Ok(buf.first().copied()),
+                Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    fn next_char(&mut self) -> io::Result<Option<char>> {
+        let first = match self.peek_byte()? {
+            Some(byte) => byte,
+            None => return Ok(None),
+        };
+        self.reader.consume(1);
+        let len = match first {
+            0x00..=0x7f => return Ok(Some(first as char)),
+            0xc2..=0xdf => 2,
+            0xe0..=0xef => 3,
+            0xf0..=0xf4 => 4,
+            _ => return Ok(Some(char::REPLACEMENT_CHARACTER)),
+        };
+        let mut bytes = [first, 0, 0, 0];
+        for byte in bytes.iter_mut().take(len).skip(1) {
+            match self.peek_byte()? {
+                Some(next) if next & 0xc0 == 0x80 => {
+                    *byte = next;
+                    self.reader.consume(1);
+                }
+                // the byte that broke the sequence starts the next character
+                _ => return Ok(Some(char::REPLACEMENT_CHARACTER)),
+            }
+        }
+        // overlong and surrogate encodings are left for the standard library to reject
+        let c = std::str::from_utf8(&bytes[..len])
+            .ok()
+            .and_then(|s| s.chars().next());
+        Ok(Some(c.unwrap_or(char::REPLACEMENT_CHARACTER)))
+    }
+
+    /// Report for the line that has just ended
+    fn end_line(&mut self) -> Option<Report> {
+        let line = self.line;
+        self.line += 1;
+        if std::mem::take(&mut self.skipping) {
+            self.state.reset();
+            return None;
+        }
+        match self.state.finish() {
+            LineStatus::Valid => None,
+            status => Some(Report { line, status }),
+        }
+    }
+}
+
+impl<'a, R: Read> Iterator for BracketStream<'a, R> {
+    type Item = Result<Report, StreamError>;
+
+    fn next(&mut self) -> Option<Result<Report, StreamError>> {
+        while !self.done {
+            let c = match self.next_char() {
+                Ok(Some(c)) => c,
+                Ok(None) => {
+                    self.done = true;
+                    return self.end_line().map(Ok);
+                }
+                Err(e) => {
+                    self.done = true;
+                    return Some(Err(StreamError::Io(e)));
+                }
+            };
+            if c == '\n' {
+                match self.end_line() {
+                    Some(report) => return Some(Ok(report)),
+                    None => continue,
+                }
+            }
+            if self.skipping {
+                continue;
+            }
+            if let Some(status) = self.state.feed(self.checker.table(), c) {
+                self.skipping = true;
+                return Some(Ok(Report {
+                    line: self.line,
+                    status,
+                }));
+            }
+            if self.state.depth() > self.max_depth {
+                self.skipping = true;
+                return Some(Err(StreamError::TooDeep {
+                    line: self.line,
+                    column: self.state.column(),
+                }));
+            }
+        }
+        None
+    }
+}
+
+impl BracketChecker {
+    /// Checks the reader line by line without loading it
+    pub fn stream<R: Read>(&self, reader: R) -> BracketStream<'_, R> {
+        BracketStream::new(self, reader)
+    }
+}
@@ -0,0 +1,141 @@
+//! Bracket pairs the checker knows about and their scores
+//!
+//! A table is written one pair per line: opening and closing character, score of a corrupted
+//! line ending on the closing one and its score in completions, e.g.
+//!
+//! ```text
+//! ( ) 3 1
+//! « » 5 5
+//! " " 7 6
+//! ```
+
+use super::{Bracket, BracketRole, BracketType};
+use crate::error::ParseError;
+use crate::utils::{expect_token, parse_number};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Scores {
+    pub corrupted: u64,
+    pub incomplete: u64,
+}
+
+impl Scores {
+    pub fn new(corrupted: u64, incomplete: u64) -> Scores {
+        Scores {
+            corrupted,
+            incomplete,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BracketTable {
+    pairs: Vec<(BracketType, Scores)>,
+}
+
+impl BracketTable {
+    /// None if some character is used by two pairs
+    pub fn new(pairs: Vec<(BracketType, Scores)>) -> Option<BracketTable> {
+        let mut table = BracketTable { pairs: Vec::new() };
+        for (type_, scores) in pairs {
+            table.add(type_, scores)?;
+        }
+        Some(table)
+    }
+
+    /// None if one of the characters is already taken
+    fn add(&mut self, type_: BracketType, scores: Scores) -> Option<()> {
+        if self.bracket(type_.opening()).is_some() || self.bracket(type_.closing()).is_some() {
+            return None;
+        }
+        self.pairs.push((type_, scores));
+        Some(())
+    }
+
+    pub fn parse(text: &str) -> Result<BracketTable, ParseError> {
+        let mut table = BracketTable { pairs: Vec::new() };
+        for line in text.lines().filter(|line| !line.trim().is_empty()) {
+            let (type_, scores) = parse_pair(line).map_err(|e| e.within(text, line))?;
+            table.add(type_, scores).ok_or_else(|| {
+                ParseError::at(text, line.trim_start(), "bracket is already in the table")
+            })?;
+        }
+        Ok(table)
+    }
+
+    pub fn types(&self) -> impl Iterator<Item = BracketType> + '_ {
+        self.pairs.iter().map(|(type_, _)| *type_)
+    }
+
+    /// Role and type of the character, None if it's not in the table
+    pub fn bracket(&self, c: char) -> Option<Bracket> {
+        self.types().find_map(|type_| {
+            let role = match (c == type_.opening(), c == type_.closing()) {
+                (true, true) => BracketRole::Either,
+                (true, false) => BracketRole::Opening,
+                (false, true) => BracketRole::Closing,
+                (false, false) => return None,
+            };
+            Some(Bracket { role, type_ })
+        })
+    }
+
+    /// Base of completion scores, one more than any incomplete score so that each completion
+    /// has its own score
+    pub fn completion_base(&self) -> u64 {
+        self.pairs
+            .iter()
+            .map(|(_, scores)| scores.incomplete)
+            .max()
+            .unwrap_or(0)
+            .saturating_add(1)
+    }
+
+    /// Panics if the type is not in the table
+    pub fn scores(&self, type_: &BracketType) -> Scores {
+        self.pairs
+            .iter()
+            .find(|(t, _)| t == type_)
+            .map(|(_, scores)| *scores)
+            .expect("bracket type is not in the table")
+    }
+}
+
+impl Default for BracketTable {
+    /// the four pairs of the puzzle with its scores
+    fn default() -> BracketTable {
+        BracketTable {
+            pairs: vec![
+                (BracketType::REGULAR, Scores::new(3, 1)),
+                (BracketType::SQUARE, Scores::new(57, 2)),
+                (BracketType::CURLY, Scores::new(1197, 3)),
+                (BracketType::ANGLE, Scores::new(25137, 4)),
+            ],
+        }
+    }
+}
+
+fn parse_pair(line: &str) -> Result<(BracketType, Scores), ParseError> {
+    let mut tokens = line.split_whitespace();
+    let mut delimiter = |what: &str| -> Result<char, ParseError> {
+        let token = expect_token(line, tokens.next(), what)?;
+        let mut chars = token.chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) => Ok(c),
+            _ => Err(ParseError::at(
+                line,
+                token,
+                format!("{} must be a single character", what),
+            )),
+        }
+    };
+    let type_ = BracketType::new(delimiter("opening bracket")?, delimiter("closing bracket")?);
+    let token = expect_token(line, tokens.next(), "corrupted score")?;
+    let corrupted = parse_number(line, token, "corrupted score")?;
+    let token = expect_token(line, tokens.next(), "incomplete score")?;
+    let incomplete = parse_number(line, token, "incomplete score")?;
+    if let Some(token) = tokens.next() {
+        return Err(ParseError::at(line, token, "unexpected token"));
+    }
+    Ok((type_, Scores::new(corrupted, incomplete)))
+}
@@ -1,27 +1,35 @@
-use super::utils::read_input;
+use super::error::{ParseError, Result};
+use super::solver::{Answer, Solver};
+use super::utils::parse_digit_grid;
 use std::convert::TryInto;
 
 const CAVE_SIZE: usize = 10;
 
-struct DumboCave {
+#[derive(Clone)]
+pub struct DumboCave {
     energy: [[u8; CAVE_SIZE]; CAVE_SIZE],
 }
 
 impl DumboCave {
-    fn parse(s: &String) -> DumboCave {
-        let energies_vvec: Vec<Vec<u8>> = s
-            .lines()
-            .map(|l| l.chars().map(|c| c.to_digit(10).unwrap() as u8).collect())
-            .collect();
+    pub fn parse(s: &str) -> Result<DumboCave, ParseError> {
+        let energies_vvec: Vec<Vec<u8>> = parse_digit_grid(s)?;
+        let size_error = || {
+            ParseError::at(
+                s,
+                s,
+                format!("cave must be {}x{} octopuses", CAVE_SIZE, CAVE_SIZE),
+            )
+        };
         let enery_ar_vec: Vec<[u8; CAVE_SIZE]> = energies_vvec
             .into_iter()
-            .map(|v| v.try_into().unwrap())
-            .collect();
-        DumboCave {
-            energy: enery_ar_vec.try_into().unwrap(),
-        }
+            .map(|v| v.try_into().map_err(|_| size_error()))
+            .collect::<Result<_, _>>()?;
+        Ok(DumboCave {
+            energy: enery_ar_vec.try_into().map_err(|_| size_error())?,
+        })
     }
 
+    #[allow(dead_code)]
     fn print(&self) {
         println!();
         for i in 0..CAVE_SIZE {
@@ -37,7 +45,7 @@ impl DumboCave {
         }
     }
 
-    fn step(&mut self) -> u32 {
+    pub fn step(&mut self) -> u32 {
         let mut has_flashed = [[false; CAVE_SIZE]; CAVE_SIZE];
         for i in 0..CAVE_SIZE {
             for j in 0..CAVE_SIZE {
@@ -45,6 +53,7 @@ impl DumboCave {
             }
         }
 
+        #[allow(clippy::needless_range_loop)]
         fn sum_bool_mask(mask: &[[bool; CAVE_SIZE]; CAVE_SIZE]) -> u32 {
             let mut sum: u32 = 0;
             for i in 0..CAVE_SIZE {
@@ -94,26 +103,39 @@ impl DumboCave {
     }
 }
 
-pub fn dumbos() {
-    let input = read_input(11, false);
+pub struct Dumbos;
+
+impl Solver for Dumbos {
+    type Input = DumboCave;
+
+    const DAY: u8 = 11;
+    const NAME: &'static str = "dumbos";
 
-    let mut dc = DumboCave::parse(&input);
-    // dc.print();
-    let mut flashed_total: u32 = 0;
-    for _ in 0..100 {
-        flashed_total += dc.step();
+    fn parse(&self, input: &str) -> Result<DumboCave> {
+        DumboCave::parse(input).map_err(|e| e.in_input(Self::DAY, input, input))
     }
-    dc.print();
-    println!("Total flashes: {}", flashed_total);
 
-    let mut unsync_step_count: usize = 100;
-    loop {
-        if dc.step() == ((CAVE_SIZE * CAVE_SIZE) as u32) {
-            break;
-        } else {
-            unsync_step_count += 1
+    /// total flashes after 100 steps
+    fn part1(&self, dc: &DumboCave) -> Result<Answer> {
+        let mut dc = dc.clone();
+        let mut flashed_total: u32 = 0;
+        for _ in 0..100 {
+            flashed_total += dc.step();
+        }
+        Ok(flashed_total.into())
+    }
+
+    /// first step at which all octopuses flash simultaneously
+    fn part2(&self, dc: &DumboCave) -> Result<Answer> {
+        let mut dc = dc.clone();
+        let mut unsync_step_count: usize = 0;
+        loop {
+            if dc.step() == ((CAVE_SIZE * CAVE_SIZE) as u32) {
+                break;
+            } else {
+                unsync_step_count += 1
+            }
         }
+        Ok((unsync_step_count + 1).into())
     }
-    dc.print();
-    println!("Synchronized flash as step: {}", unsync_step_count + 1);
 }
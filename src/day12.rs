@@ -1,10 +1,12 @@
-use super::utils::read_input;
+use super::error::{ParseError, Result};
+use super::solver::{Answer, Solver};
+use super::utils::expect_token;
 use std::clone::Clone;
 use std::collections::{HashMap, HashSet};
 use std::fmt;
 
 #[derive(Eq, Hash, PartialEq, Clone, Debug, Ord, PartialOrd)]
-enum CaveName {
+pub enum CaveName {
     Start,
     Small(String),
     Big(String),
@@ -24,35 +26,29 @@ impl fmt::Display for CaveName {
 }
 
 impl CaveName {
-    fn parse(s: &str) -> CaveName {
+    pub fn parse(s: &str) -> Result<CaveName, ParseError> {
         let res = match s {
             "start" => CaveName::Start,
             "end" => CaveName::End,
             other => {
                 let s = String::from(other);
                 // checking only first letter
-                if s.chars().next().unwrap() < 'Z' {
-                    CaveName::Big(s)
-                } else {
-                    CaveName::Small(s)
+                match s.chars().next() {
+                    None => return Err(ParseError::at(other, other, "cave name is empty")),
+                    Some(first) if first < 'Z' => CaveName::Big(s),
+                    Some(_) => CaveName::Small(s),
                 }
             }
         };
-        res
+        Ok(res)
     }
 
-    fn is_big(&self) -> bool {
-        match self {
-            CaveName::Big(_) => true,
-            _ => false,
-        }
+    pub fn is_big(&self) -> bool {
+        matches!(self, CaveName::Big(_))
     }
 
-    fn is_small(&self) -> bool {
-        match self {
-            CaveName::Small(_) => true,
-            _ => false,
-        }
+    pub fn is_small(&self) -> bool {
+        matches!(self, CaveName::Small(_))
     }
 
     fn cmp_big_small(&self, other: &CaveName) -> std::cmp::Ordering {
@@ -71,12 +67,24 @@ impl CaveName {
     }
 }
 
-type Network = HashMap<CaveName, Vec<CaveName>>;
+pub type Network = HashMap<CaveName, Vec<CaveName>>;
 
-fn parse_cave_network(input: String) -> Network {
+pub fn parse_cave_network(input: &str) -> Result<Network, ParseError> {
     let mut network: Network = HashMap::new();
     for line in input.lines() {
-        let cave_names: Vec<CaveName> = line.split('-').map(CaveName::parse).collect();
+        let mut names = line.split('-');
+        let mut cave_names: Vec<CaveName> = Vec::new();
+        for what in ["first cave", "second cave"] {
+            let name = expect_token(line, names.next(), what).map_err(|e| e.within(input, line))?;
+            cave_names.push(CaveName::parse(name).map_err(|e| e.within(input, name))?);
+        }
+        if let Some(extra) = names.next() {
+            return Err(ParseError::at(
+                input,
+                extra,
+                "connection must be between two caves",
+            ));
+        }
         for cave_name in &cave_names {
             if !network.contains_key(cave_name) {
                 network.insert(cave_name.clone(), Vec::new());
@@ -92,7 +100,7 @@ fn parse_cave_network(input: String) -> Network {
     for conn_to in network.values_mut() {
         conn_to.sort_by(|a, b| a.cmp_big_small(b));
     }
-    network
+    Ok(network)
 }
 
 #[allow(dead_code)]
@@ -106,7 +114,7 @@ fn print_cave_network(network: &Network) {
     }
 }
 
-fn find_paths<'a>(network: &Network, part2: bool) -> Vec<Vec<&CaveName>> {
+pub fn find_paths(network: &Network, part2: bool) -> Vec<Vec<&CaveName>> {
     fn find_paths_recursive<'a>(
         network: &'a Network,
         now_at: &'a CaveName,
@@ -120,7 +128,7 @@ fn find_paths<'a>(network: &Network, part2: bool) -> Vec<Vec<&CaveName>> {
         visited.insert(now_at);
 
         let mut res: Vec<Vec<&'a CaveName>> = Vec::new();
-        for next in network.get(now_at).unwrap() {
+        for next in network.get(now_at).into_iter().flatten() {
             let mut visiting_small_cave_second_time = false;
             let visit_next = match visited.contains(next) {
                 false => true,
@@ -160,27 +168,35 @@ fn find_paths<'a>(network: &Network, part2: bool) -> Vec<Vec<&CaveName>> {
     find_paths_recursive(network, &CaveName::Start, &HashSet::new(), !part2)
 }
 
-pub fn path_in_caves() {
-    let input = read_input(12, false);
-
-    let network = parse_cave_network(input);
-    // print_cave_network(&network);
-
-    let paths = find_paths(&network, false);
-    println!("Total paths to the end: {}", paths.len());
-
-    let paths = find_paths(&network, true);
-    // for path in &paths {
-    //     println!(
-    //         "{}",
-    //         path.iter()
-    //             .map(|cn| format!("{}", cn))
-    //             .collect::<Vec<String>>()
-    //             .join(",")
-    //     );
-    // }
-    println!(
-        "Total paths to the end with one small cave visited twice: {}",
-        paths.len()
-    );
+pub struct PathInCaves;
+
+impl Solver for PathInCaves {
+    type Input = Network;
+
+    const DAY: u8 = 12;
+    const NAME: &'static str = "path_in_caves";
+
+    fn parse(&self, input: &str) -> Result<Network> {
+        parse_cave_network(input).map_err(|e| e.in_input(Self::DAY, input, input))
+    }
+
+    /// total paths to the end
+    fn part1(&self, network: &Network) -> Result<Answer> {
+        Ok(find_paths(network, false).len().into())
+    }
+
+    /// total paths to the end with one small cave visited twice
+    fn part2(&self, network: &Network) -> Result<Answer> {
+        let paths = find_paths(network, true);
+        // for path in &paths {
+        //     println!(
+        //         "{}",
+        //         path.iter()
+        //             .map(|cn| format!("{}", cn))
+        //             .collect::<Vec<String>>()
+        //             .join(",")
+        //     );
+        // }
+        Ok(paths.len().into())
+    }
 }
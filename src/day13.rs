@@ -1,122 +1,193 @@
-use super::utils::read_input;
+use super::error::{Error, ParseError, Result};
+use super::solver::{Answer, Solver};
+use super::utils::{expect_token, parse_number};
 use std::collections::HashSet;
 
 #[derive(Debug)]
-enum Fold {
+pub enum Fold {
     AlongX(usize),
     AlongY(usize),
 }
 
 impl Fold {
-    fn parse(s: &str) -> Fold {
-        let fold_str = s.split(' ').nth(2).unwrap();
-        let fold_coord: usize = fold_str
-            .split('=')
-            .nth(1)
-            .unwrap()
-            .parse()
-            .expect("Can't parse fold coordinate");
-        match fold_str.chars().nth(0).unwrap() {
-            'x' => Fold::AlongX(fold_coord),
-            'y' => Fold::AlongY(fold_coord),
-            _ => panic!("Can't parse orientation"),
+    pub fn parse(s: &str) -> Result<Fold, ParseError> {
+        let fold_str = s
+            .strip_prefix("fold along ")
+            .ok_or_else(|| ParseError::at(s, s, "expected 'fold along '"))?;
+        let mut fold_parts = fold_str.split('=');
+        let orientation = expect_token(s, fold_parts.next(), "fold orientation")?;
+        let fold_coord: usize = parse_number(
+            s,
+            expect_token(s, fold_parts.next(), "fold coordinate")?,
+            "fold coordinate",
+        )?;
+        match orientation {
+            "x" => Ok(Fold::AlongX(fold_coord)),
+            "y" => Ok(Fold::AlongY(fold_coord)),
+            _ => Err(ParseError::at(
+                s,
+                orientation,
+                "fold orientation must be 'x' or 'y'",
+            )),
         }
     }
 }
 
-#[derive(Debug, Eq, PartialEq, Hash)]
-struct Point {
-    x: usize,
-    y: usize,
+#[derive(Debug, Eq, PartialEq, Hash, Clone)]
+pub struct Point {
+    pub x: usize,
+    pub y: usize,
 }
 
 impl Point {
-    fn parse(s: &str) -> Point {
-        let x_y: Vec<usize> = s
-            .split(',')
-            .map(|s| s.parse().expect("Can't parse coord"))
-            .collect();
-        Point {
-            x: x_y[0],
-            y: x_y[1],
-        }
+    pub fn parse(s: &str) -> Result<Point, ParseError> {
+        let mut x_y = s.split(',');
+        Ok(Point {
+            x: parse_number(
+                s,
+                expect_token(s, x_y.next(), "x coordinate")?,
+                "x coordinate",
+            )?,
+            y: parse_number(
+                s,
+                expect_token(s, x_y.next(), "y coordinate")?,
+                "y coordinate",
+            )?,
+        })
     }
 
-    fn folded(&self, fold: &Fold) -> Point {
+    /// The point must not be further than the fold position past the fold
+    pub fn folded(&self, fold: &Fold) -> Point {
         match fold {
             Fold::AlongX(fold_pos) => Point {
                 y: self.y,
-                x: fold_pos - (*fold_pos as i32 - self.x as i32).abs() as usize,
+                x: fold_pos - fold_pos.abs_diff(self.x),
             },
             Fold::AlongY(fold_pos) => Point {
                 x: self.x,
-                y: fold_pos - (*fold_pos as i32 - self.y as i32).abs() as usize,
+                y: fold_pos - fold_pos.abs_diff(self.y),
             },
         }
     }
 }
 
-struct Origami {
-    points: HashSet<Point>,
+#[derive(Clone)]
+pub struct Origami {
+    pub points: HashSet<Point>,
+}
+
+impl Default for Origami {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Origami {
-    fn new() -> Origami {
+    pub fn new() -> Origami {
         Origami {
             points: HashSet::new(),
         }
     }
 
-    fn print(&self) {
-        let max_x = self.points.iter().map(|p| p.x).max().unwrap() + 1;
-        let max_y = self.points.iter().map(|p| p.y).max().unwrap() + 1;
+    #[allow(clippy::needless_range_loop)]
+    pub fn render(&self) -> String {
+        let max_x = self.points.iter().map(|p| p.x + 1).max().unwrap_or(0);
+        let max_y = self.points.iter().map(|p| p.y + 1).max().unwrap_or(0);
         let mut points_mask: Vec<Vec<bool>> = vec![vec![false; max_x]; max_y];
         for p in &self.points {
             points_mask[p.y][p.x] = true;
         }
+        let mut res = String::new();
         for y in 0..max_y {
             for x in 0..max_x {
-                print!("{}", if points_mask[y][x] { '#' } else { '.' });
+                res.push(if points_mask[y][x] { '#' } else { '.' });
             }
-            println!();
+            res.push('\n');
         }
+        res
     }
 
-    fn folded(&self, fold: &Fold) -> Origami {
+    #[allow(dead_code)]
+    fn print(&self) {
+        print!("{}", self.render());
+    }
+
+    pub fn folded(&self, fold: &Fold) -> Origami {
         Origami {
             points: HashSet::from_iter(self.points.iter().map(|p| p.folded(fold))),
         }
     }
 
-    fn count_points(&self) -> usize {
+    pub fn count_points(&self) -> usize {
         self.points.len()
     }
 }
 
-pub fn folding_origami() {
-    let input = read_input(13, false);
+pub struct Manual {
+    origami: Origami,
+    folds: Vec<Fold>,
+}
+
+pub struct FoldingOrigami;
 
-    let mut origami: Origami = Origami::new();
-    let mut folds: Vec<Fold> = Vec::new();
-    let mut parsing_points = true;
-    for line in input.lines() {
-        if line.len() == 0 {
-            parsing_points = false;
-            continue;
-        }
-        if parsing_points {
-            origami.points.insert(Point::parse(line));
-        } else {
-            folds.push(Fold::parse(line));
+impl Solver for FoldingOrigami {
+    type Input = Manual;
+
+    const DAY: u8 = 13;
+    const NAME: &'static str = "folding_origami";
+
+    fn parse(&self, input: &str) -> Result<Manual> {
+        let mut origami: Origami = Origami::new();
+        let mut folds: Vec<Fold> = Vec::new();
+        let mut parsing_points = true;
+        // points folded so far, each fold must fit them on the other side
+        let mut folded: Option<Origami> = None;
+        for line in input.lines() {
+            if line.is_empty() {
+                parsing_points = false;
+                continue;
+            }
+            let in_input = |e: ParseError| e.in_input(Self::DAY, input, line);
+            if parsing_points {
+                origami.points.insert(Point::parse(line).map_err(in_input)?);
+            } else {
+                let fold = Fold::parse(line).map_err(in_input)?;
+                let current = folded.get_or_insert_with(|| origami.clone());
+                let (fold_pos, max) = match fold {
+                    Fold::AlongX(x) => (x, current.points.iter().map(|p| p.x).max()),
+                    Fold::AlongY(y) => (y, current.points.iter().map(|p| p.y).max()),
+                };
+                if max.is_some_and(|max| max.saturating_sub(fold_pos) > fold_pos) {
+                    return Err(in_input(ParseError::at(
+                        line,
+                        line,
+                        "fold would move points past the edge of the paper",
+                    )));
+                }
+                *current = current.folded(&fold);
+                folds.push(fold);
+            }
         }
+        Ok(Manual { origami, folds })
+    }
+
+    /// points after the first fold
+    fn part1(&self, manual: &Manual) -> Result<Answer> {
+        let first_fold = manual
+            .folds
+            .first()
+            .ok_or_else(|| Error::no_solution(Self::DAY, "there are no folds"))?;
+        let origami = manual.origami.folded(first_fold);
+        Ok(origami.count_points().into())
     }
 
-    // origami.print();
-    println!();
-    for (i, fold) in folds.iter().enumerate() {
-        origami = origami.folded(fold);
-        // origami.print();
-        println!("points after fold {}: {}", i + 1, origami.count_points());
+    /// code drawn by the points after all the folds
+    fn part2(&self, manual: &Manual) -> Result<Answer> {
+        let mut origami = manual.origami.clone();
+        for fold in &manual.folds {
+            origami = origami.folded(fold);
+            // origami.print();
+        }
+        Ok(origami.render().into())
     }
-    origami.print();
 }
@@ -1,13 +1,14 @@
-use super::utils::read_input;
+use super::error::{Error, ParseError, Result};
+use super::solver::{Answer, Solver};
+use super::utils::expect_token;
 use std::collections::HashMap;
 
-fn char_windows<'a>(src: &'a String, win_size: usize) -> impl Iterator<Item = &'a str> {
+fn char_windows(src: &str, win_size: usize) -> impl Iterator<Item = &str> {
     // credit: https://stackoverflow.com/a/51261570
     src.char_indices().flat_map(move |(from, _)| {
         src[from..]
             .char_indices()
-            .skip(win_size - 1)
-            .next()
+            .nth(win_size - 1)
             .map(|(to, c)| &src[from..from + to + c.len_utf8()])
     })
 }
@@ -18,18 +19,33 @@ fn ch2idx(ch: char) -> usize {
     ch as usize - 'A' as usize
 }
 
+/// element is a single uppercase letter
+fn parse_element(text: &str, token: &str) -> Result<usize, ParseError> {
+    let mut chars = token.chars();
+    match (chars.next(), chars.next()) {
+        (Some(ch), None) if ch.is_ascii_uppercase() => Ok(ch2idx(ch)),
+        _ => Err(ParseError::at(
+            text,
+            token,
+            format!("element must be an uppercase letter, not '{}'", token),
+        )),
+    }
+}
+
 fn idx2ch(i: usize) -> char {
     (('A' as usize + i) as u8) as char
 }
 
+#[allow(clippy::needless_range_loop)]
 fn character_counts(
     pair_table: &[[i64; N_CHAR]; N_CHAR],
-    intial_polymer: &String,
+    intial_polymer: &str,
 ) -> HashMap<char, i64> {
     let mut counts: HashMap<char, i64> = HashMap::new();
     let mut ch: char;
     let mut count: i64;
-    let last_char = intial_polymer.chars().last().unwrap(); // last char is unchanged
+    // last char is unchanged, the parser makes sure there is one
+    let last_char = intial_polymer.chars().last().unwrap();
     for i_left in 0..N_CHAR {
         ch = idx2ch(i_left);
         count = 0;
@@ -46,60 +62,115 @@ fn character_counts(
     counts
 }
 
-pub fn polymers() {
-    let input = read_input(14, false);
-
-    let mut insertion_rules: HashMap<(usize, usize), usize> = HashMap::new();
-    for line in input.lines().skip(2) {
-        let mut line_parts = line.split(" -> ");
-        let between = line_parts.next().unwrap();
-        let ch_left = between.chars().nth(0).unwrap();
-        let ch_right = between.chars().nth(1).unwrap();
-        let insert = line_parts.next().unwrap().chars().next().unwrap();
-        insertion_rules.insert((ch2idx(ch_left), ch2idx(ch_right)), ch2idx(insert));
-    }
+pub struct PolymerManual {
+    initial_polymer: String,
+    insertion_rules: HashMap<(usize, usize), usize>,
+}
 
-    // creating a table of numbers of character pairs
-    let mut pair_table: [[i64; N_CHAR]; N_CHAR] = [[0; N_CHAR]; N_CHAR];
-    let initial_polymer = input.lines().next().unwrap().to_string();
-    for pair in char_windows(&initial_polymer, 2) {
-        let ch_left = pair.chars().nth(0).unwrap();
-        let ch_right = pair.chars().nth(1).unwrap();
-        pair_table[ch2idx(ch_left)][ch2idx(ch_right)] += 1;
-    }
+impl PolymerManual {
+    /// most common element count minus least common one after given number of steps
+    pub fn polymerize(&self, iterations: usize) -> Option<i64> {
+        // creating a table of numbers of character pairs
+        let mut pair_table: [[i64; N_CHAR]; N_CHAR] = [[0; N_CHAR]; N_CHAR];
+        for pair in char_windows(&self.initial_polymer, 2) {
+            let ch_left = pair.chars().nth(0).unwrap();
+            let ch_right = pair.chars().nth(1).unwrap();
+            pair_table[ch2idx(ch_left)][ch2idx(ch_right)] += 1;
+        }
+
+        let mut pair_table_update: [[i64; N_CHAR]; N_CHAR];
 
-    let mut pair_table_update: [[i64; N_CHAR]; N_CHAR];
-
-    let iterations: usize = 40;
-    let mut n_pairs: i64;
-    for _ in 0..iterations {
-        pair_table_update = [[0; N_CHAR]; N_CHAR];
-        for i_left in 0..N_CHAR {
-            for i_right in 0..N_CHAR {
-                n_pairs = pair_table[i_left][i_right];
-                if n_pairs == 0 {
-                    continue;
+        let mut n_pairs: i64;
+        for _ in 0..iterations {
+            pair_table_update = [[0; N_CHAR]; N_CHAR];
+            for i_left in 0..N_CHAR {
+                for i_right in 0..N_CHAR {
+                    n_pairs = pair_table[i_left][i_right];
+                    if n_pairs == 0 {
+                        continue;
+                    }
+                    if let Some(i_insert) = self.insertion_rules.get(&(i_left, i_right)) {
+                        pair_table_update[i_left][i_right] -= n_pairs;
+                        pair_table_update[i_left][*i_insert] += n_pairs;
+                        pair_table_update[*i_insert][i_right] += n_pairs;
+                    }
                 }
-                if let Some(i_insert) = insertion_rules.get(&(i_left, i_right)) {
-                    pair_table_update[i_left][i_right] -= n_pairs;
-                    pair_table_update[i_left][*i_insert] += n_pairs;
-                    pair_table_update[*i_insert][i_right] += n_pairs;
+            }
+            for i_left in 0..N_CHAR {
+                for i_right in 0..N_CHAR {
+                    pair_table[i_left][i_right] += pair_table_update[i_left][i_right];
                 }
             }
         }
-        for i_left in 0..N_CHAR {
-            for i_right in 0..N_CHAR {
-                pair_table[i_left][i_right] += pair_table_update[i_left][i_right];
-            }
+
+        let counts = character_counts(&pair_table, &self.initial_polymer);
+        let max_count = counts.values().max()?;
+        let min_count = counts.values().min()?;
+        Some(max_count - min_count)
+    }
+}
+
+pub struct Polymers;
+
+impl Solver for Polymers {
+    type Input = PolymerManual;
+
+    const DAY: u8 = 14;
+    const NAME: &'static str = "polymers";
+
+    fn parse(&self, input: &str) -> Result<PolymerManual> {
+        let initial_polymer = input.lines().next().unwrap_or("");
+        if initial_polymer.is_empty() {
+            return Err(
+                ParseError::at_end(initial_polymer, "polymer template is missing").in_input(
+                    Self::DAY,
+                    input,
+                    initial_polymer,
+                ),
+            );
+        }
+        for (idx, ch) in initial_polymer.char_indices() {
+            parse_element(initial_polymer, &initial_polymer[idx..idx + ch.len_utf8()])
+                .map_err(|e| e.in_input(Self::DAY, input, initial_polymer))?;
         }
+
+        let mut insertion_rules: HashMap<(usize, usize), usize> = HashMap::new();
+        for line in input.lines().skip(2) {
+            let parse_rule = || -> Result<((usize, usize), usize), ParseError> {
+                let mut line_parts = line.split(" -> ");
+                let between = expect_token(line, line_parts.next(), "element pair")?;
+                if between.len() != 2 || !between.is_char_boundary(1) {
+                    return Err(ParseError::at(line, between, "expected a pair of elements"));
+                }
+                let insert = expect_token(line, line_parts.next(), "inserted element")?;
+                Ok((
+                    (
+                        parse_element(line, &between[..1])?,
+                        parse_element(line, &between[1..])?,
+                    ),
+                    parse_element(line, insert)?,
+                ))
+            };
+            let (pair, insert) = parse_rule().map_err(|e| e.in_input(Self::DAY, input, line))?;
+            insertion_rules.insert(pair, insert);
+        }
+        Ok(PolymerManual {
+            initial_polymer: initial_polymer.to_string(),
+            insertion_rules,
+        })
     }
 
-    let counts = character_counts(&pair_table, &initial_polymer);
-    let max_count = counts.values().max().unwrap();
-    let min_count = counts.values().min().unwrap();
-    println!(
-        "Most common N - least common N after {} iterations: {}",
-        iterations,
-        max_count - min_count
-    );
+    fn part1(&self, manual: &PolymerManual) -> Result<Answer> {
+        manual
+            .polymerize(10)
+            .map(Answer::from)
+            .ok_or_else(|| Error::no_solution(Self::DAY, "polymer is empty"))
+    }
+
+    fn part2(&self, manual: &PolymerManual) -> Result<Answer> {
+        manual
+            .polymerize(40)
+            .map(Answer::from)
+            .ok_or_else(|| Error::no_solution(Self::DAY, "polymer is empty"))
+    }
 }
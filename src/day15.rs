@@ -1,4 +1,6 @@
-use super::utils::read_input;
+use super::error::{ParseError, Result};
+use super::solver::{Answer, Solver};
+use super::utils::parse_digit_grid;
 use std::collections::{HashMap, HashSet};
 
 #[derive(Eq, PartialEq, Hash, Clone, Copy, Debug)]
@@ -7,26 +9,27 @@ struct Node {
     j: usize,
 }
 
-struct Map {
+pub struct Map {
     risks: Vec<Vec<u8>>,
     imax: usize,
     jmax: usize,
 }
 
 impl Map {
-    fn parse(s: &String) -> Map {
-        let mut risks: Vec<Vec<u8>> = Vec::new();
-        let mut row: Vec<u8>;
-        for line in s.lines() {
-            row = Vec::new();
-            for ch in line.chars() {
-                row.push(ch.to_digit(10).unwrap() as u8);
-            }
-            risks.push(row);
+    pub fn parse(s: &str) -> Result<Map, ParseError> {
+        let risks: Vec<Vec<u8>> = parse_digit_grid(s)?;
+        // tiling wraps risks around within 1..=9
+        if let Some(line) = s.lines().find(|line| line.contains('0')) {
+            let idx = line.find('0').unwrap();
+            return Err(ParseError::at(
+                s,
+                &line[idx..idx + 1],
+                "risk level must be at least 1",
+            ));
         }
         let imax = risks.len() - 1;
-        let jmax = risks.iter().next().unwrap().len() - 1;
-        Map { risks, imax, jmax }
+        let jmax = risks[0].len() - 1;
+        Ok(Map { risks, imax, jmax })
     }
 
     #[allow(dead_code)]
@@ -39,7 +42,8 @@ impl Map {
         }
     }
 
-    fn tiled_map(&self) -> Map {
+    #[allow(clippy::needless_range_loop)]
+    pub fn tiled_map(&self) -> Map {
         let imax_tiled = (self.imax + 1) * 5 - 1;
         let jmax_tiled = (self.jmax + 1) * 5 - 1;
         let mut risks_new: Vec<Vec<u8>> = vec![vec![0; jmax_tiled + 1]; imax_tiled + 1];
@@ -92,7 +96,7 @@ struct PriorityQueueRecord {
     from: Option<Node>,
 }
 
-fn dijkstra(map: &Map) -> u32 {
+pub fn dijkstra(map: &Map) -> u32 {
     let start = PriorityQueueRecord {
         node: Node { i: 0, j: 0 },
         cumcost: 0,
@@ -103,6 +107,9 @@ fn dijkstra(map: &Map) -> u32 {
         j: map.jmax,
     };
 
+    if start.node == end_node {
+        return 0;
+    }
     let mut now = &start;
 
     let mut discarded: HashSet<Node> = HashSet::new();
@@ -132,11 +139,11 @@ fn dijkstra(map: &Map) -> u32 {
                 }
             }
             new_priority_queue.insert(
-                next_node.clone(),
+                next_node,
                 PriorityQueueRecord {
                     node: next_node,
                     cumcost: new_cumcost,
-                    from: Some(now.node.clone()),
+                    from: Some(now.node),
                 },
             );
         }
@@ -147,12 +154,24 @@ fn dijkstra(map: &Map) -> u32 {
     }
 }
 
-pub fn chitons() {
-    let input = read_input(15, false);
+pub struct Chitons;
+
+impl Solver for Chitons {
+    type Input = Map;
+
+    const DAY: u8 = 15;
+    const NAME: &'static str = "chitons";
 
-    let map = Map::parse(&input);
-    println!("cost is {}", dijkstra(&map));
+    fn parse(&self, input: &str) -> Result<Map> {
+        Map::parse(input).map_err(|e| e.in_input(Self::DAY, input, input))
+    }
+
+    fn part1(&self, map: &Map) -> Result<Answer> {
+        Ok(dijkstra(map).into())
+    }
 
-    let large_map = map.tiled_map();
-    println!("cost (on large map) is {}", dijkstra(&large_map));
+    /// cost on the large map
+    fn part2(&self, map: &Map) -> Result<Answer> {
+        Ok(dijkstra(&map.tiled_map()).into())
+    }
 }
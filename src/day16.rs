@@ -1,120 +1,222 @@
-use super::utils::read_input;
+use super::error::{Error, ParseError, Result};
+use super::solver::{Answer, Solver};
+use super::trace::{enabled, trace, Level};
 
-type Bits = Vec<bool>;
-type BitsSlice<'a> = &'a [bool];
+pub type Bits = Vec<bool>;
+pub type BitsSlice<'a> = &'a [bool];
 
-fn print_bits(b: BitsSlice) {
-    for bit in b {
-        print!("{}", if *bit { '1' } else { '0' })
-    }
-    println!();
+fn bits2string(b: BitsSlice) -> String {
+    b.iter().map(|bit| if *bit { '1' } else { '0' }).collect()
 }
 
-fn hexdump2bits(hexdump: &String) -> Bits {
-    fn hexdigit2bits(ch: char) -> Bits {
-        let num = ch.to_digit(16).unwrap();
-        (0..4)
-            .rev()
-            .map(|offset| (num >> offset) % 2 == 1)
-            .collect()
+pub fn hexdump2bits(hexdump: &str) -> Result<Bits, ParseError> {
+    let mut bits: Bits = Vec::new();
+    for (idx, ch) in hexdump.char_indices() {
+        let num = ch.to_digit(16).ok_or_else(|| {
+            ParseError::at(
+                hexdump,
+                &hexdump[idx..idx + ch.len_utf8()],
+                format!("'{}' is not a hex digit", ch),
+            )
+        })?;
+        bits.extend((0..4).rev().map(|offset| (num >> offset) % 2 == 1));
     }
+    Ok(bits)
+}
+
+/// Hex digits of the bits, zero-padded to a whole digit
+pub fn bits2hexdump(bits: BitsSlice) -> String {
+    bits.chunks(4)
+        .map(|chunk| {
+            let padded = bits2num(chunk) << (4 - chunk.len());
+            std::char::from_digit(padded as u32, 16)
+                .unwrap()
+                .to_ascii_uppercase()
+        })
+        .collect()
+}
 
-    hexdump.chars().flat_map(hexdigit2bits).collect()
+/// Appends the lowest `width` bits of the number, None if it doesn't fit
+fn push_num(bits: &mut Bits, num: u64, width: usize) -> Option<()> {
+    if width < 64 && num >> width != 0 {
+        return None;
+    }
+    bits.extend((0..width).rev().map(|offset| (num >> offset) % 2 == 1));
+    Some(())
 }
 
-fn bits2num(bits: BitsSlice) -> u64 {
+pub fn bits2num(bits: BitsSlice) -> u64 {
     bits.iter()
         .rev()
         .enumerate()
-        .map(|(i, bit)| if *bit { (2 as u64).pow(i as u32) } else { 0 })
+        .map(|(i, bit)| if *bit { 2_u64.pow(i as u32) } else { 0 })
         .sum()
 }
 
-#[derive(Debug)]
-struct PacketHeader {
-    version: u8,
-    type_id: u8,
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PacketHeader {
+    pub version: u8,
+    pub type_id: u8,
 }
 
-#[derive(Debug)]
-enum Packet {
+/// How an operator packet says where its subpackets end
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LengthType {
+    /// total length of the subpackets in bits, length type id 0
+    Bits,
+    /// number of subpackets, length type id 1
+    Count,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Packet {
     Literal(PacketHeader, u64),
-    Op(PacketHeader, Vec<Packet>),
+    Op(PacketHeader, LengthType, Vec<Packet>),
 }
 
 impl Packet {
-    fn sum_versions(&self) -> u32 {
+    pub fn sum_versions(&self) -> u32 {
         match self {
             Packet::Literal(h, _) => h.version as u32,
-            Packet::Op(h, subpackets) => {
+            Packet::Op(h, _, subpackets) => {
                 let subpackets_version_sum: u32 = subpackets.iter().map(|p| p.sum_versions()).sum();
                 (h.version as u32) + subpackets_version_sum
             }
         }
     }
 
-    fn value(&self) -> u64 {
+    /// None if some operator doesn't have enough subpackets
+    pub fn value(&self) -> Option<u64> {
         match self {
-            Packet::Literal(_, v) => *v,
-            Packet::Op(h, sub) => {
-                let mut subpacket_values = sub.iter().map(|p| p.value());
+            Packet::Literal(_, v) => Some(*v),
+            Packet::Op(h, _, sub) => {
+                let subpacket_values = sub
+                    .iter()
+                    .map(|p| p.value())
+                    .collect::<Option<Vec<u64>>>()?;
+                let mut subpacket_values = subpacket_values.into_iter();
                 match h.type_id {
-                    0 => subpacket_values.sum(),
-                    1 => subpacket_values.product(),
-                    2 => subpacket_values.min().unwrap(),
-                    3 => subpacket_values.max().unwrap(),
+                    0 => Some(subpacket_values.sum()),
+                    1 => Some(subpacket_values.product()),
+                    2 => subpacket_values.min(),
+                    3 => subpacket_values.max(),
                     5..=7 => {
-                        let first = subpacket_values.next().unwrap();
-                        let second = subpacket_values.next().unwrap();
+                        let first = subpacket_values.next()?;
+                        let second = subpacket_values.next()?;
                         if (first > second && h.type_id == 5)
                             || (first < second && h.type_id == 6)
                             || (first == second && h.type_id == 7)
                         {
-                            1
+                            Some(1)
                         } else {
-                            0
+                            Some(0)
                         }
                     }
-                    _ => {
-                        panic!();
-                    }
+                    _ => None,
                 }
             }
         }
     }
-}
 
-const DEBUG: bool = true;
+    /// Hex transmission of the packet, None if it can't be encoded
+    pub fn to_hexdump(&self) -> Option<String> {
+        let mut bits = Bits::new();
+        encode_packet(self, &mut bits)?;
+        Some(bits2hexdump(&bits))
+    }
+}
 
-fn parse_packet(bits: BitsSlice) -> Option<(Packet, BitsSlice)> {
-    if DEBUG {
-        println!("\ncurrent bits, len {}", bits.len());
+/// Appends the bits of the packet, None if a field doesn't fit its bits or the type id
+/// doesn't match the kind of the packet, `bits` is left as it was then
+pub fn encode_packet(packet: &Packet, bits: &mut Bits) -> Option<()> {
+    let start = bits.len();
+    let res = push_packet(packet, bits);
+    if res.is_none() {
+        bits.truncate(start);
     }
-    if bits.len() < 10 {
-        if DEBUG {
-            print_bits(bits);
-            println!("not a packet, exiting!")
+    res
+}
+
+fn push_packet(packet: &Packet, bits: &mut Bits) -> Option<()> {
+    let header = match packet {
+        Packet::Literal(header, _) | Packet::Op(header, _, _) => header,
+    };
+    push_num(bits, header.version as u64, 3)?;
+    push_num(bits, header.type_id as u64, 3)?;
+    match packet {
+        Packet::Literal(header, value) => {
+            if header.type_id != 4 {
+                return None;
+            }
+            // the fewest groups that hold the value, at least one
+            let groups = (64 - value.leading_zeros() as usize).div_ceil(4);
+            for group in (0..groups.max(1)).rev() {
+                bits.push(group > 0);
+                push_num(bits, (value >> (4 * group)) % 16, 4)?;
+            }
+        }
+        Packet::Op(header, length_type, subpackets) => {
+            if header.type_id == 4 {
+                return None;
+            }
+            match length_type {
+                LengthType::Bits => {
+                    let mut subpacket_bits = Bits::new();
+                    for subpacket in subpackets {
+                        push_packet(subpacket, &mut subpacket_bits)?;
+                    }
+                    bits.push(false);
+                    push_num(bits, subpacket_bits.len() as u64, 15)?;
+                    bits.extend(subpacket_bits);
+                }
+                LengthType::Count => {
+                    bits.push(true);
+                    push_num(bits, subpackets.len() as u64, 11)?;
+                    for subpacket in subpackets {
+                        push_packet(subpacket, bits)?;
+                    }
+                }
+            }
         }
-        return None;
     }
+    Some(())
+}
 
-    if DEBUG {
-        print_bits(bits);
+/// None if the bits end in the middle of a packet, a literal is over 64 bits or subpackets
+/// don't fill the length they are given
+pub fn parse_packet(bits: BitsSlice) -> Option<(Packet, BitsSlice)> {
+    // packet layout is traced right under its bits
+    let tracing = enabled!(Level::Trace);
+    let mut layout = String::new();
+    let mut annotate = |fields: &str| {
+        if tracing {
+            layout.push_str(fields)
+        }
+    };
+
+    trace!("current bits, len {}", bits.len());
+    trace!("bits   {}", bits2string(bits));
+    if bits.len() < 10 {
+        trace!("not a packet, exiting!");
+        return None;
     }
 
     let version = bits2num(&bits[0..=2]) as u8;
     let type_id = bits2num(&bits[3..=5]) as u8;
-    if DEBUG {
-        print!("VVVTTT")
-    }
+    annotate("VVVTTT");
     let header = PacketHeader { version, type_id };
     if type_id == 4 {
         let mut number_bits: Bits = Vec::new();
         let mut group_start: usize = 6;
         loop {
+            if group_start + 5 > bits.len() {
+                return None;
+            }
             number_bits.extend(&bits[group_start + 1..group_start + 5]);
-            if DEBUG {
-                print!("FDDDD")
+            annotate("FDDDD");
+            if number_bits.len() > 64 {
+                trace!("literal doesn't fit into 64 bits");
+                return None;
             }
             if !bits[group_start] {
                 group_start += 5;
@@ -122,32 +224,32 @@ fn parse_packet(bits: BitsSlice) -> Option<(Packet, BitsSlice)> {
             }
             group_start += 5;
         }
-        if DEBUG {
-            println!("\nvalue");
-        }
-        return Some((
+        trace!("layout {}", layout);
+        trace!("literal value");
+        Some((
             Packet::Literal(header, bits2num(number_bits.as_slice())),
             &bits[group_start..],
-        ));
+        ))
     } else {
         let length_type_id = bits2num(&bits[6..=6]);
-        if DEBUG {
-            print!("I")
-        }
+        annotate("I");
         let mut subpackets: Vec<Packet> = Vec::new();
         if length_type_id == 0 {
-            let length_bits = bits2num(&bits[7..=21]) as usize;
-            if DEBUG {
-                print!("LLLLLLLLLLLLLLL")
+            if bits.len() < 22 {
+                return None;
             }
-            if DEBUG {
-                println!("\nop packet with len in bits: {}", length_bits);
+            let length_bits = bits2num(&bits[7..=21]) as usize;
+            if bits.len() < 22 + length_bits {
+                return None;
             }
+            annotate("LLLLLLLLLLLLLLL");
+            trace!("layout {}", layout);
+            trace!("op packet with len in bits: {}", length_bits);
             let subpackets_bits: Bits = bits
                 .iter()
                 .skip(22)
                 .take(length_bits)
-                .map(|b| *b)
+                .copied()
                 .collect::<Bits>();
             let mut subpackets: Vec<Packet> = Vec::new();
 
@@ -156,36 +258,69 @@ fn parse_packet(bits: BitsSlice) -> Option<(Packet, BitsSlice)> {
                 subpackets.push(subpacket);
                 subpacket_bits = left_bits;
             }
-            return Some((Packet::Op(header, subpackets), &bits[22 + length_bits..]));
-        } else if length_type_id == 1 {
+            // the subpackets take up exactly the length, anything left is a broken one
+            if !subpacket_bits.is_empty() {
+                return None;
+            }
+            Some((
+                Packet::Op(header, LengthType::Bits, subpackets),
+                &bits[22 + length_bits..],
+            ))
+        } else {
+            if bits.len() < 18 {
+                return None;
+            }
             let n_subpackets = bits2num(&bits[7..=17]) as usize;
 
-            if DEBUG {
-                println!("LLLLLLLLLLL");
-                println!("op packet with {} subpackets", n_subpackets);
-            }
+            annotate("LLLLLLLLLLL");
+            trace!("layout {}", layout);
+            trace!("op packet with {} subpackets", n_subpackets);
             let mut subpackets_start = 18;
             for _ in 0..n_subpackets {
                 if let Some((subpacket, left_bits)) = parse_packet(&bits[subpackets_start..]) {
                     subpackets.push(subpacket);
                     subpackets_start = bits.len() - left_bits.len();
                 } else {
-                    panic!("Can't parse expected subpacket")
+                    return None;
                 }
             }
-            return Some((Packet::Op(header, subpackets), &bits[subpackets_start..]));
+            Some((
+                Packet::Op(header, LengthType::Count, subpackets),
+                &bits[subpackets_start..],
+            ))
         }
-        panic!("");
     }
 }
 
-pub fn bits_decoding() {
-    let input = read_input(16, false);
+pub struct BitsDecoding;
+
+impl Solver for BitsDecoding {
+    type Input = Packet;
+
+    const DAY: u8 = 16;
+    const NAME: &'static str = "bits_decoding";
+
+    fn parse(&self, input: &str) -> Result<Packet> {
+        let hexdump = input.trim();
+        let in_input = |e: ParseError| e.in_input(Self::DAY, input, hexdump);
+        let bits = hexdump2bits(hexdump).map_err(in_input)?;
+        match parse_packet(&bits) {
+            Some((packet, _)) => Ok(packet),
+            None => Err(in_input(ParseError::at_end(
+                hexdump,
+                "transmission has a truncated or malformed packet",
+            ))),
+        }
+    }
+
+    /// sum of versions in all packets
+    fn part1(&self, packet: &Packet) -> Result<Answer> {
+        Ok(packet.sum_versions().into())
+    }
 
-    let bits = hexdump2bits(&input);
-    if let Some((packet, _)) = parse_packet(&bits) {
-        println!();
-        println!("versions sum: {}", packet.sum_versions());
-        println!("value: {}", packet.value());
+    fn part2(&self, packet: &Packet) -> Result<Answer> {
+        packet.value().map(Answer::from).ok_or_else(|| {
+            Error::no_solution(Self::DAY, "some operator packet has too few subpackets")
+        })
     }
 }
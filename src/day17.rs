@@ -1,4 +1,6 @@
-use super::utils::read_input;
+use super::error::{Error, ParseError, Result};
+use super::solver::{Answer, Solver};
+use super::utils::{expect_token, parse_number};
 use std::collections::{HashMap, HashSet};
 
 fn calc_v_range(y_range: &(i32, i32), t: i32) -> (f32, f32) {
@@ -23,62 +25,104 @@ fn int_vy_in_range(vy_range: &(f32, f32)) -> Vec<i32> {
     res
 }
 
-pub fn probe_launch() {
-    let input = read_input(17, false);
+pub struct TargetArea {
+    x_range: (i32, i32),
+    y_range: (i32, i32),
+}
+
+impl TargetArea {
+    pub fn parse(s: &str) -> Result<TargetArea, ParseError> {
+        let parse_range = |range_str: &str, what: &str| -> Result<(i32, i32), ParseError> {
+            let mut it = range_str.split("..").map(|ss| ss.trim());
+            let from: i32 = parse_number(s, expect_token(s, it.next(), what)?, what)?;
+            let to: i32 = parse_number(s, expect_token(s, it.next(), what)?, what)?;
+            Ok((from, to))
+        };
 
-    fn parse_range(s: &str) -> (i32, i32) {
-        let mut it = s.split("..").map(|ss| ss.parse().expect(""));
-        (it.next().unwrap(), it.next().unwrap())
+        let mut ranges = expect_token(s, s.split("x=").nth(1), "x range")?.split(", y=");
+        Ok(TargetArea {
+            x_range: parse_range(expect_token(s, ranges.next(), "x range")?, "x range")?,
+            y_range: parse_range(expect_token(s, ranges.next(), "y range")?, "y range")?,
+        })
     }
 
-    let mut ranges = input.split("x=").nth(1).unwrap().split(", y=");
-    let x_range = parse_range(ranges.next().unwrap());
-    let y_range = parse_range(ranges.next().unwrap());
+    /// all initial velocity vectors that end up within target area
+    pub fn launch_vectors(&self) -> HashSet<(i32, i32)> {
+        let x_range = self.x_range;
+        let y_range = self.y_range;
 
-    let mut t: i32 = 0;
+        let mut t: i32 = 0;
 
-    let mut t_by_vy: HashMap<i32, Vec<i32>> = HashMap::new();
+        let mut t_by_vy: HashMap<i32, Vec<i32>> = HashMap::new();
 
-    loop {
-        t += 1;
-        let vy_range = calc_v_range(&y_range, t); // vy range to end up in y range after t
+        loop {
+            t += 1;
+            let vy_range = calc_v_range(&y_range, t); // vy range to end up in y range after t
 
-        let int_vys = int_vy_in_range(&vy_range);
-        println!("{}: {:?}, vy = {:?}", t, vy_range, int_vys);
-        for int_vy in int_vys {
-            if !t_by_vy.contains_key(&int_vy) {
-                t_by_vy.insert(int_vy, Vec::new());
+            let int_vys = int_vy_in_range(&vy_range);
+            // println!("{}: {:?}, vy = {:?}", t, vy_range, int_vys);
+            for int_vy in int_vys {
+                t_by_vy.entry(int_vy).or_default();
+                t_by_vy.get_mut(&int_vy).unwrap().push(t);
+            }
+            if t > 1000 {
+                // TEMP
+                break;
             }
-            t_by_vy.get_mut(&int_vy).unwrap().push(t);
-        }
-        if t > 1000 {
-            // TEMP
-            break;
         }
-    }
 
-    println!("\n\n{:?}\n\n", t_by_vy);
-
-    let mut vectors: HashSet<(i32, i32)> = HashSet::new();
-
-    for (vy, t_variants) in t_by_vy.iter() {
-        for t in t_variants {
-            let mut vx: i32 = 0;
-            loop {
-                vx += 1;
-                let vx_t = [vx, *t - 1];
-                let tx = 1 + (vx_t).iter().min().unwrap();
-                let x_coord = (tx * vx - ((tx - 1) * tx) / 2) as i32;
-                if x_coord >= x_range.0 && x_coord <= x_range.1 {
-                    if tx != *t {}
-                    println!("{} ({}): v = ({}, {})", t, tx, vx, vy);
-                    vectors.insert((vx, *vy));
-                } else if x_coord > 5 * x_range.1 {
-                    break;
+        let mut vectors: HashSet<(i32, i32)> = HashSet::new();
+
+        for (vy, t_variants) in t_by_vy.iter() {
+            for t in t_variants {
+                let mut vx: i32 = 0;
+                loop {
+                    vx += 1;
+                    let vx_t = [vx, *t - 1];
+                    let tx = 1 + (vx_t).iter().min().unwrap();
+                    let x_coord = tx * vx - ((tx - 1) * tx) / 2;
+                    if x_coord >= x_range.0 && x_coord <= x_range.1 {
+                        // println!("{} ({}): v = ({}, {})", t, tx, vx, vy);
+                        vectors.insert((vx, *vy));
+                    } else if x_coord > 5 * x_range.1 {
+                        break;
+                    }
                 }
             }
         }
+        vectors
+    }
+}
+
+pub struct ProbeLaunch;
+
+impl Solver for ProbeLaunch {
+    type Input = TargetArea;
+
+    const DAY: u8 = 17;
+    const NAME: &'static str = "probe_launch";
+
+    fn parse(&self, input: &str) -> Result<TargetArea> {
+        TargetArea::parse(input).map_err(|e| e.in_input(Self::DAY, input, input))
+    }
+
+    /// highest y position reached on a trajectory that ends up in the target area
+    fn part1(&self, target: &TargetArea) -> Result<Answer> {
+        let max_vy = target
+            .launch_vectors()
+            .iter()
+            .map(|(_, vy)| *vy)
+            .max()
+            .ok_or_else(|| Error::no_solution(Self::DAY, "target area can't be reached"))?;
+        if max_vy > 0 {
+            Ok((max_vy * (max_vy + 1) / 2).into())
+        } else {
+            Ok(0.into())
+        }
     }
 
-    println!("{:?}", vectors.len());
+    /// number of distinct initial velocity vectors
+    fn part2(&self, target: &TargetArea) -> Result<Answer> {
+        Ok(target.launch_vectors().len().into())
+    }
 }
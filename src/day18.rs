@@ -1,5 +1,7 @@
-use super::utils::read_input;
-use std::iter;
+use super::error::{Error, ParseError, Result};
+use super::solver::{Answer, Solver};
+use super::trace::{debug, enabled, trace, Level};
+use super::utils::{parse_lines, parse_number};
 
 #[derive(Debug, Clone)]
 enum NodeParentRef {
@@ -20,35 +22,35 @@ struct Node {
 }
 
 #[derive(Debug, Clone)]
-struct SnailfishNumber {
+pub struct SnailfishNumber {
     root_idx: usize,
     nodes: Vec<Node>,
 }
 
-const DEBUG: bool = false;
-
 impl SnailfishNumber {
-    fn print(&self) {
-        fn print_recursive(nodes: &Vec<Node>, idx: usize, indent: usize) {
-            let indentation: String = iter::repeat(' ').take(indent).collect();
+    /// tree turned 90 degrees counterclockwise, with node indices
+    fn render_tree(&self) -> String {
+        fn render_recursive(nodes: &Vec<Node>, idx: usize, indent: usize, res: &mut String) {
+            let indentation: String = std::iter::repeat_n(' ', indent).collect();
             let node = &nodes[idx];
             match node.content {
                 NodeContent::Number(num) => {
-                    println!("{}({}) {}", indentation, idx, num);
+                    res.push_str(&format!("{}({}) {}\n", indentation, idx, num));
                 }
                 NodeContent::Bracket(left_idx, right_idx) => {
-                    print_recursive(nodes, right_idx, indent + 5);
-                    println!("{}({}) <", indentation, idx);
-                    print_recursive(nodes, left_idx, indent + 5);
+                    render_recursive(nodes, right_idx, indent + 5, res);
+                    res.push_str(&format!("{}({}) <\n", indentation, idx));
+                    render_recursive(nodes, left_idx, indent + 5, res);
                 }
             }
         }
 
-        println!("\n\n");
-        print_recursive(&self.nodes, self.root_idx, 0)
+        let mut res = String::new();
+        render_recursive(&self.nodes, self.root_idx, 0, &mut res);
+        res
     }
 
-    fn sum(&self, other: &SnailfishNumber) -> SnailfishNumber {
+    pub fn sum(&self, other: &SnailfishNumber) -> SnailfishNumber {
         let mut self_ = self.clone();
         let mut other = other.clone();
         self_.shift_indices(1, true, None);
@@ -104,54 +106,81 @@ impl SnailfishNumber {
         }
     }
 
-    fn parse(s: &str) -> SnailfishNumber {
-        fn _parse_snailfish_num_recursive(s: &str) -> (SnailfishNumber, &str) {
-            let first_ch = s.chars().next().unwrap();
-            if DEBUG {
-                println!("'{}', first char is {}", s, first_ch);
+    pub fn parse(s: &str) -> Result<SnailfishNumber, ParseError> {
+        /// `rest` without the expected leading char
+        fn expect_char<'a>(
+            line: &str,
+            rest: &'a str,
+            expected: char,
+        ) -> Result<&'a str, ParseError> {
+            match rest.chars().next() {
+                Some(ch) if ch == expected => Ok(&rest[1..]),
+                Some(ch) => Err(ParseError::at(
+                    line,
+                    &rest[..ch.len_utf8()],
+                    format!("expected '{}', found '{}'", expected, ch),
+                )),
+                None => Err(ParseError::at_end(line, format!("expected '{}'", expected))),
             }
+        }
+
+        fn _parse_snailfish_num_recursive<'a>(
+            line: &str,
+            s: &'a str,
+        ) -> Result<(SnailfishNumber, &'a str), ParseError> {
+            let first_ch = match s.chars().next() {
+                Some(ch) => ch,
+                None => return Err(ParseError::at_end(line, "snailfish number is missing")),
+            };
+            trace!("'{}', first char is {}", s, first_ch);
             match first_ch {
                 '[' => {
-                    if DEBUG {
-                        println!("parsing as bracket");
-                    }
-                    let (left_num, rest) = _parse_snailfish_num_recursive(&s[1..]);
-                    let (right_num, rest) = _parse_snailfish_num_recursive(&rest[1..]);
-                    (left_num.sum(&right_num), &rest[1..])
+                    trace!("parsing as bracket");
+                    let (left_num, rest) = _parse_snailfish_num_recursive(line, &s[1..])?;
+                    let rest = expect_char(line, rest, ',')?;
+                    let (right_num, rest) = _parse_snailfish_num_recursive(line, rest)?;
+                    let rest = expect_char(line, rest, ']')?;
+                    Ok((left_num.sum(&right_num), rest))
                 }
                 _ => {
-                    if DEBUG {
-                        println!("parsing as number");
-                    }
-                    let mut first_non_digit_idx: usize = 0;
-                    for (i, ch) in s.chars().enumerate() {
-                        if !ch.is_digit(10) {
-                            first_non_digit_idx = i;
-                            break;
-                        }
-                    }
+                    trace!("parsing as number");
+                    let first_non_digit_idx = s
+                        .char_indices()
+                        .find(|(_, ch)| !ch.is_ascii_digit())
+                        .map(|(i, _)| i)
+                        .unwrap_or(s.len());
                     if first_non_digit_idx == 0 {
-                        panic!("Something's wrong when parsing numeric value")
+                        return Err(ParseError::at(
+                            line,
+                            &s[..first_ch.len_utf8()],
+                            format!("expected '[' or a digit, found '{}'", first_ch),
+                        ));
                     }
-                    let num: u32 = s[..first_non_digit_idx]
-                        .parse()
-                        .expect("Can't parse numeric value in snailfish number");
+                    let num: u32 = parse_number(line, &s[..first_non_digit_idx], "regular number")?;
                     let root_node = Node {
                         parent_ref: None,
                         content: NodeContent::Number(num),
                     };
-                    (
+                    Ok((
                         SnailfishNumber {
                             root_idx: 0,
                             nodes: Vec::from([root_node]),
                         },
                         &s[first_non_digit_idx..],
-                    )
+                    ))
                 }
             }
         }
 
-        _parse_snailfish_num_recursive(s).0
+        let (number, rest) = _parse_snailfish_num_recursive(s, s)?;
+        if !rest.is_empty() {
+            return Err(ParseError::at(
+                s,
+                rest,
+                "unexpected characters after snailfish number",
+            ));
+        }
+        Ok(number)
     }
 
     fn explode(&mut self) -> bool {
@@ -167,22 +196,22 @@ impl SnailfishNumber {
                         return if depth_current >= 4 { Some(idx) } else { None };
                     }
                 }
-                return match find_exploding_node_recursive(nodes, left_idx, depth_current + 1) {
+                match find_exploding_node_recursive(nodes, left_idx, depth_current + 1) {
                     None => find_exploding_node_recursive(nodes, right_idx, depth_current + 1),
                     Some(idx) => Some(idx),
-                };
+                }
             } else {
-                return None;
+                None
             }
         }
 
         let exploding_node_idx = find_exploding_node_recursive(&self.nodes, self.root_idx, 0);
 
-        if let None = exploding_node_idx {
+        if exploding_node_idx.is_none() {
             return false;
         }
 
-        fn find_exploding_node_left_target(nodes: &Vec<Node>, idx: usize) -> Option<(usize, u32)> {
+        fn find_exploding_node_left_target(nodes: &[Node], idx: usize) -> Option<(usize, u32)> {
             if let NodeContent::Bracket(expleft_idx, _) = &nodes[idx].content {
                 if let NodeContent::Number(left_num) = &nodes[*expleft_idx].content {
                     let mut expleft_idx = *expleft_idx;
@@ -197,7 +226,7 @@ impl SnailfishNumber {
             }
             panic!("Error in find_exploding_node_recursive");
         }
-        fn find_exploding_node_right_target(nodes: &Vec<Node>, idx: usize) -> Option<(usize, u32)> {
+        fn find_exploding_node_right_target(nodes: &[Node], idx: usize) -> Option<(usize, u32)> {
             if let NodeContent::Bracket(_, expright_idx) = &nodes[idx].content {
                 if let NodeContent::Number(right_num) = &nodes[*expright_idx].content {
                     let mut expright_idx = *expright_idx;
@@ -242,12 +271,12 @@ impl SnailfishNumber {
                 }
             }
         }
-        if let None = split_idx {
+        if split_idx.is_none() {
             return false;
         }
         let split_idx = split_idx.unwrap();
 
-        fn get_nodes_to_insert(nodes: &Vec<Node>, idx: usize) -> (Node, Node) {
+        fn get_nodes_to_insert(nodes: &[Node], idx: usize) -> (Node, Node) {
             if let NodeContent::Number(num2split) = &nodes[idx].content {
                 let left_num = num2split / 2;
                 let right_num = num2split - left_num;
@@ -273,12 +302,12 @@ impl SnailfishNumber {
         true
     }
 
-    fn reduce(&mut self) -> bool {
+    pub fn reduce(&mut self) -> bool {
         self.explode() || self.split()
     }
 
-    fn magnitude(&self) -> u32 {
-        fn node_magnitude(nodes: &Vec<Node>, idx: usize) -> u32 {
+    pub fn magnitude(&self) -> u32 {
+        fn node_magnitude(nodes: &[Node], idx: usize) -> u32 {
             let node = &nodes[idx];
             match node.content {
                 NodeContent::Number(num) => num,
@@ -292,41 +321,58 @@ impl SnailfishNumber {
     }
 }
 
-pub fn snailfish_math() {
-    let input = read_input(18, false);
+pub struct SnailfishMath;
 
-    let numbers: Vec<SnailfishNumber> = input.lines().map(SnailfishNumber::parse).collect();
+impl Solver for SnailfishMath {
+    type Input = Vec<SnailfishNumber>;
 
-    let mut res = numbers[0].clone();
-    for sn in &numbers {
-        res = res.sum(sn);
-        // res.print();
-        while res.reduce() {
-            // res.print();
-        }
-    }
+    const DAY: u8 = 18;
+    const NAME: &'static str = "snailfish_math";
 
-    res.print();
-
-    println!("magnitude is {}", res.magnitude());
+    fn parse(&self, input: &str) -> Result<Vec<SnailfishNumber>> {
+        parse_lines(Self::DAY, input, SnailfishNumber::parse)
+    }
 
-    let mut summ: SnailfishNumber;
-    let mut max_magnitude: u32 = 0;
-    for first_idx in 0..numbers.len() {
-        for second_idx in 0..numbers.len() {
-            if first_idx == second_idx {
-                continue;
-            }
-            summ = numbers[first_idx].sum(&numbers[second_idx]);
-            while summ.reduce() {
-                // res.print();
+    /// magnitude of the final sum
+    fn part1(&self, numbers: &Vec<SnailfishNumber>) -> Result<Answer> {
+        let mut res = numbers
+            .first()
+            .ok_or_else(|| Error::no_solution(Self::DAY, "there are no numbers to add"))?
+            .clone();
+        for sn in &numbers[1..] {
+            res = res.sum(sn);
+            if enabled!(Level::Trace) {
+                trace!("sum before reduction:\n{}", res.render_tree());
             }
-            let magn = summ.magnitude();
-            if magn > max_magnitude {
-                max_magnitude = magn;
+            while res.reduce() {
+                if enabled!(Level::Trace) {
+                    trace!("after reduction step:\n{}", res.render_tree());
+                }
             }
         }
+        if enabled!(Level::Debug) {
+            debug!("final sum:\n{}", res.render_tree());
+        }
+        Ok(res.magnitude().into())
     }
 
-    println!("max pairwise magnitude: {}", max_magnitude);
+    /// max pairwise magnitude
+    fn part2(&self, numbers: &Vec<SnailfishNumber>) -> Result<Answer> {
+        let mut summ: SnailfishNumber;
+        let mut max_magnitude: u32 = 0;
+        for first_idx in 0..numbers.len() {
+            for second_idx in 0..numbers.len() {
+                if first_idx == second_idx {
+                    continue;
+                }
+                summ = numbers[first_idx].sum(&numbers[second_idx]);
+                while summ.reduce() {}
+                let magn = summ.magnitude();
+                if magn > max_magnitude {
+                    max_magnitude = magn;
+                }
+            }
+        }
+        Ok(max_magnitude.into())
+    }
 }
@@ -1,9 +1,12 @@
-use super::utils::read_input;
+use super::error::{Error, ParseError, Result};
+use super::solver::{Answer, Solver};
+use super::utils::{expect_token, parse_number};
 use itertools::Itertools;
 use std::collections::{HashMap, HashSet};
 
-type RelPos = [i32; 3];
+pub type RelPos = [i32; 3];
 
+#[allow(clippy::needless_range_loop)]
 fn rotated_positions(initial: &RelPos) -> Vec<RelPos> {
     let mut rotations: Vec<RelPos> = Vec::new();
 
@@ -31,7 +34,7 @@ fn rotated_positions(initial: &RelPos) -> Vec<RelPos> {
 }
 
 #[derive(Debug, Clone)]
-struct ScannerData {
+pub struct ScannerData {
     beacons: HashSet<RelPos>,
     len_to_closest_pos: HashMap<RelPos, Option<f32>>,
 }
@@ -43,9 +46,9 @@ impl ScannerData {
             let mut ltcp: Option<f32> = None;
             for b2 in &beacons {
                 if b2[0] > b[0] && b2[1] > b[1] && b2[2] > b[2] {
-                    let len = ((2 as f32).powf((b2[0] - b[0]) as f32)
-                        + (2 as f32).powf((b2[1] - b[1]) as f32)
-                        + (2 as f32).powf((b2[2] - b[2]) as f32))
+                    let len = (2_f32.powf((b2[0] - b[0]) as f32)
+                        + 2_f32.powf((b2[1] - b[1]) as f32)
+                        + 2_f32.powf((b2[2] - b[2]) as f32))
                     .sqrt();
                     if let Some(cur_len) = ltcp {
                         if cur_len > len {
@@ -56,7 +59,7 @@ impl ScannerData {
                     }
                 }
             }
-            ltcps.insert(b.clone(), ltcp);
+            ltcps.insert(*b, ltcp);
         }
         ScannerData {
             beacons,
@@ -64,6 +67,7 @@ impl ScannerData {
         }
     }
 
+    #[allow(dead_code)]
     fn print(&self) {
         println!("\nscanner data:");
         for b in &self.beacons {
@@ -74,24 +78,32 @@ impl ScannerData {
         }
     }
 
-    fn parse(s: &String) -> Vec<ScannerData> {
+    pub fn parse(s: &str) -> Result<Vec<ScannerData>, ParseError> {
         let mut res: Vec<ScannerData> = Vec::new();
         let mut beacons: HashSet<RelPos> = HashSet::new();
         for line in s.lines() {
             if !line.contains(",") {
-                if beacons.len() > 0 {
+                if !beacons.is_empty() {
                     res.push(ScannerData::new(beacons));
                     beacons = HashSet::new();
                 }
             } else {
-                let pos: Vec<i32> = line.split(',').map(|ss| ss.parse().unwrap()).collect();
-                beacons.insert([pos[0], pos[1], pos[2]]);
+                let mut coords = line.split(',');
+                let mut pos: RelPos = [0, 0, 0];
+                for (i, what) in ["x", "y", "z"].iter().enumerate() {
+                    let coord_str = expect_token(s, coords.next(), what)?;
+                    pos[i] = parse_number(s, coord_str, what)?;
+                }
+                if let Some(extra) = coords.next() {
+                    return Err(ParseError::at(s, extra, "position must have 3 coordinates"));
+                }
+                beacons.insert(pos);
             }
         }
-        if beacons.len() > 0 {
+        if !beacons.is_empty() {
             res.push(ScannerData::new(beacons));
         }
-        res
+        Ok(res)
     }
 
     fn rotated(&self) -> Vec<ScannerData> {
@@ -104,10 +116,7 @@ impl ScannerData {
                 beacons_rotated[i].insert(rb);
             }
         }
-        beacons_rotated
-            .into_iter()
-            .map(|b| ScannerData::new(b))
-            .collect()
+        beacons_rotated.into_iter().map(ScannerData::new).collect()
     }
 
     fn match_and_merge_with_rotations(&self, other: &ScannerData) -> Option<(ScannerData, RelPos)> {
@@ -125,7 +134,7 @@ impl ScannerData {
     fn match_and_merge(&self, other: &ScannerData) -> Option<(ScannerData, RelPos)> {
         for b1 in &self.beacons {
             let ltcp1 = self.len_to_closest_pos.get(b1).unwrap();
-            if let None = ltcp1 {
+            if ltcp1.is_none() {
                 continue;
             }
             let ltcp1 = ltcp1.unwrap();
@@ -134,7 +143,7 @@ impl ScannerData {
                 // println!("{:?}", other.len_to_closest_pos);
 
                 let ltcp2 = other.len_to_closest_pos.get(b2).unwrap();
-                if let None = ltcp2 {
+                if ltcp2.is_none() {
                     continue;
                 }
                 let ltcp2 = ltcp2.unwrap();
@@ -144,7 +153,8 @@ impl ScannerData {
 
                 // aligning b2 with b1
                 let offset: RelPos = [b1[0] - b2[0], b1[1] - b2[1], b1[2] - b2[2]];
-                let offsetted_other_beacons: HashSet<RelPos> = (&other.beacons)
+                let offsetted_other_beacons: HashSet<RelPos> = other
+                    .beacons
                     .iter()
                     .map(|pos| [pos[0] + offset[0], pos[1] + offset[1], pos[2] + offset[2]])
                     .collect();
@@ -154,7 +164,7 @@ impl ScannerData {
                         ScannerData::new(
                             offsetted_other_beacons
                                 .union(&self.beacons)
-                                .map(|&v| v)
+                                .copied()
                                 .collect(),
                         ),
                         offset,
@@ -166,45 +176,84 @@ impl ScannerData {
     }
 }
 
-fn manhattan_distance(p1: &RelPos, p2: &RelPos) -> i32 {
+pub fn manhattan_distance(p1: &RelPos, p2: &RelPos) -> i32 {
     (p1[0] - p2[0]).abs() + (p1[1] - p2[1]).abs() + (p1[2] - p2[2]).abs()
 }
 
-pub fn beacons() {
-    let input = read_input(19, false);
-
-    let scanners = ScannerData::parse(&input);
+/// All scanners merged into the frame of the first one
+struct BeaconMap {
+    total: ScannerData,
+    scanner_offsets: Vec<RelPos>,
+}
 
-    let mut total: ScannerData = scanners[0].clone();
-    let mut merged_indices: Vec<usize> = vec![0];
-    let mut scanner_offsets: Vec<RelPos> = Vec::new();
+impl BeaconMap {
+    /// None if some scanners don't overlap with the rest
+    fn assemble(scanners: &[ScannerData]) -> Option<BeaconMap> {
+        let mut total: ScannerData = scanners.first()?.clone();
+        let mut merged_indices: Vec<usize> = vec![0];
+        let mut scanner_offsets: Vec<RelPos> = Vec::new();
 
-    loop {
-        for (i, s) in scanners.iter().enumerate() {
-            if !merged_indices.contains(&i) {
-                if let Some((new_total, scanner_offset)) = total.match_and_merge_with_rotations(s) {
-                    scanner_offsets.push(scanner_offset);
-                    new_total.print();
-                    total = new_total;
-                    merged_indices.push(i);
-                    break;
+        loop {
+            let merged_before = merged_indices.len();
+            for (i, s) in scanners.iter().enumerate() {
+                if !merged_indices.contains(&i) {
+                    if let Some((new_total, scanner_offset)) =
+                        total.match_and_merge_with_rotations(s)
+                    {
+                        scanner_offsets.push(scanner_offset);
+                        // new_total.print();
+                        total = new_total;
+                        merged_indices.push(i);
+                        break;
+                    }
                 }
             }
+            if merged_indices.len() == scanners.len() {
+                break;
+            }
+            if merged_indices.len() == merged_before {
+                return None;
+            }
         }
-        if merged_indices.len() == scanners.len() {
-            break;
-        }
+        Some(BeaconMap {
+            total,
+            scanner_offsets,
+        })
     }
+}
 
-    println!("{}", total.beacons.len());
-    println!("{:?}", scanner_offsets);
-    println!(
-        "{}",
-        &scanner_offsets
+pub struct Beacons;
+
+impl Solver for Beacons {
+    type Input = Vec<ScannerData>;
+
+    const DAY: u8 = 19;
+    const NAME: &'static str = "beacons";
+
+    fn parse(&self, input: &str) -> Result<Vec<ScannerData>> {
+        ScannerData::parse(input).map_err(|e| e.in_input(Self::DAY, input, input))
+    }
+
+    /// total number of beacons
+    fn part1(&self, scanners: &Vec<ScannerData>) -> Result<Answer> {
+        let map = BeaconMap::assemble(scanners).ok_or_else(|| {
+            Error::no_solution(Self::DAY, "scanners can't be assembled into one map")
+        })?;
+        Ok(map.total.beacons.len().into())
+    }
+
+    /// largest distance between any two scanners
+    fn part2(&self, scanners: &Vec<ScannerData>) -> Result<Answer> {
+        let map = BeaconMap::assemble(scanners).ok_or_else(|| {
+            Error::no_solution(Self::DAY, "scanners can't be assembled into one map")
+        })?;
+        Ok(map
+            .scanner_offsets
             .iter()
-            .cartesian_product(&scanner_offsets)
+            .cartesian_product(&map.scanner_offsets)
             .map(|(o1, o2)| manhattan_distance(o1, o2))
             .max()
-            .unwrap()
-    )
+            .unwrap_or(0)
+            .into())
+    }
 }
@@ -1,16 +1,33 @@
-use super::utils::read_input;
+use super::error::{Error, ParseError, Result};
+use super::solver::{Answer, Solver};
 
-type Algorithm = [bool; 512];
+pub type Algorithm = [bool; 512];
 
 const MASK: [[usize; 3]; 3] = [[256, 128, 64], [32, 16, 8], [4, 2, 1]];
 
-fn ch2bool(ch: char) -> bool {
+fn ch2bool(ch: char) -> Option<bool> {
     match ch {
-        '#' => true,
-        _ => false,
+        '#' => Some(true),
+        '.' => Some(false),
+        _ => None,
     }
 }
 
+/// pixels of a single line, `line` must be a slice of `text`
+fn parse_pixels(text: &str, line: &str) -> Result<Vec<bool>, ParseError> {
+    line.char_indices()
+        .map(|(idx, ch)| {
+            ch2bool(ch).ok_or_else(|| {
+                ParseError::at(
+                    text,
+                    &line[idx..idx + ch.len_utf8()],
+                    format!("pixel must be '#' or '.', not '{}'", ch),
+                )
+            })
+        })
+        .collect()
+}
+
 fn bool2ch(b: bool) -> char {
     match b {
         true => '#',
@@ -18,6 +35,7 @@ fn bool2ch(b: bool) -> char {
     }
 }
 
+#[allow(dead_code)]
 fn print_algo(a: &Algorithm) {
     for b in a {
         print!("{}", bool2ch(*b));
@@ -25,29 +43,39 @@ fn print_algo(a: &Algorithm) {
     println!();
 }
 
-struct Image {
+#[derive(Clone)]
+pub struct Image {
     center: Vec<Vec<bool>>,
     padding: bool,
 }
 
 impl Image {
-    fn parse(s: &String) -> Image {
+    pub fn parse(s: &str) -> Result<Image, ParseError> {
         let mut img: Vec<Vec<bool>> = Vec::new();
         for line in s.lines().skip(2) {
-            let mut row: Vec<bool> = Vec::new();
-            for ch in line.chars() {
-                row.push(ch2bool(ch))
+            let row = parse_pixels(s, line)?;
+            if let Some(first_row) = img.first() {
+                if row.len() != first_row.len() {
+                    return Err(ParseError::at(
+                        s,
+                        line,
+                        "image rows must have the same length",
+                    ));
+                }
             }
             img.push(row);
         }
-        Image {
+        if img.is_empty() || img[0].is_empty() {
+            return Err(ParseError::at_end(s, "image is empty"));
+        }
+        Ok(Image {
             center: img,
             padding: false,
-        }
+        })
     }
 
     fn hw(&self) -> (usize, usize) {
-        (self.center.len(), self.center.iter().next().unwrap().len())
+        (self.center.len(), self.center.first().unwrap().len())
     }
 
     fn get(&self, i: i32, j: i32) -> bool {
@@ -61,7 +89,7 @@ impl Image {
         }
     }
 
-    fn enhanced(&self, algorithm: &Algorithm) -> Image {
+    pub fn enhanced(&self, algorithm: &Algorithm) -> Image {
         let (h, w) = self.hw();
         let h = h as i32;
         let w = w as i32;
@@ -95,7 +123,7 @@ impl Image {
         }
     }
 
-    fn count_lit_pixels(&self) -> Option<usize> {
+    pub fn count_lit_pixels(&self) -> Option<usize> {
         // None means infinite
         if self.padding {
             return None;
@@ -111,6 +139,7 @@ impl Image {
         Some(counter)
     }
 
+    #[allow(dead_code)]
     fn display(&self) {
         let pad_len: usize = 2;
         let (_, w) = self.hw();
@@ -133,32 +162,60 @@ impl Image {
     }
 }
 
-pub fn image_enhancement() {
-    let input = read_input(20, false);
-
-    let algorithm: Algorithm = input
-        .lines()
-        .next()
-        .unwrap()
-        .chars()
-        .map(|ch| ch == '#')
-        .collect::<Vec<bool>>()
-        .try_into()
-        .unwrap();
-
-    let mut image = Image::parse(&input);
+pub struct TrenchMap {
+    algorithm: Algorithm,
+    image: Image,
+}
 
-    print_algo(&algorithm);
+impl TrenchMap {
+    /// None if infinitely many pixels are lit
+    pub fn lit_pixels_after(&self, steps: usize) -> Option<usize> {
+        let mut image = self.image.clone();
+        for _ in 0..steps {
+            image = image.enhanced(&self.algorithm)
+        }
+        // image.display();
+        image.count_lit_pixels()
+    }
+}
 
-    image.display();
-    println!();
-    for _ in 0..50 {
-        image = image.enhanced(&algorithm)
+pub struct ImageEnhancement;
+
+impl Solver for ImageEnhancement {
+    type Input = TrenchMap;
+
+    const DAY: u8 = 20;
+    const NAME: &'static str = "image_enhancement";
+
+    fn parse(&self, input: &str) -> Result<TrenchMap> {
+        let in_input = |e: ParseError| e.in_input(Self::DAY, input, input);
+        let algorithm_line = input.lines().next().unwrap_or("");
+        let algorithm: Algorithm = parse_pixels(input, algorithm_line)
+            .map_err(in_input)?
+            .try_into()
+            .map_err(|_| {
+                in_input(ParseError::at(
+                    input,
+                    algorithm_line,
+                    "algorithm must have exactly 512 pixels",
+                ))
+            })?;
+
+        Ok(TrenchMap {
+            algorithm,
+            image: Image::parse(input).map_err(in_input)?,
+        })
     }
 
-    image.display();
+    fn part1(&self, map: &TrenchMap) -> Result<Answer> {
+        map.lit_pixels_after(2)
+            .map(Answer::from)
+            .ok_or_else(|| Error::no_solution(Self::DAY, "infinitely many pixels are lit"))
+    }
 
-    if let Some(count) = image.count_lit_pixels() {
-        println!("pixels lit: {}", count);
+    fn part2(&self, map: &TrenchMap) -> Result<Answer> {
+        map.lit_pixels_after(50)
+            .map(Answer::from)
+            .ok_or_else(|| Error::no_solution(Self::DAY, "infinitely many pixels are lit"))
     }
 }
@@ -1,9 +1,13 @@
+use super::error::{ParseError, Result};
+use super::solver::{Answer, Solver};
+use super::utils::{expect_token, parse_lines, parse_number};
+
 const MOVES: [u8; 7] = [3, 4, 5, 6, 7, 8, 9];
 const MOVE_UNIVERSES: [u64; 7] = [1, 3, 6, 7, 6, 3, 1];
 
 const END_SCORE: u8 = 21;
 
-fn whowins(
+pub fn whowins(
     idx1: u8,
     idx2: u8,
     score1: u8,
@@ -46,15 +50,72 @@ fn whowins(
     (universes_win1, universes_win2)
 }
 
-pub fn dirac_die() {
-    let pos1 = 10;
-    let pos2 = 2;
-
-    let (un1, un2) = whowins(pos1 - 1, pos2 - 1, 0, 0, 1, true);
-    println!(
-        "1 wins in {}, 2 wins in {} (total of {})",
-        un1,
-        un2,
-        un1 + un2
-    );
+const DETERMINISTIC_END_SCORE: u32 = 1000;
+
+pub fn deterministic_game(pos1: u8, pos2: u8) -> u32 {
+    let mut positions: [u32; 2] = [pos1 as u32 - 1, pos2 as u32 - 1];
+    let mut scores: [u32; 2] = [0, 0];
+    let mut rolls: u32 = 0;
+    let mut player: usize = 0;
+    loop {
+        let mut moved: u32 = 0;
+        for _ in 0..3 {
+            moved += rolls % 100 + 1;
+            rolls += 1;
+        }
+        positions[player] = (positions[player] + moved) % 10;
+        scores[player] += positions[player] + 1;
+        if scores[player] >= DETERMINISTIC_END_SCORE {
+            return scores[1 - player] * rolls;
+        }
+        player = 1 - player;
+    }
+}
+
+pub struct StartingPositions {
+    pos1: u8,
+    pos2: u8,
+}
+
+pub struct DiracDie;
+
+impl Solver for DiracDie {
+    type Input = StartingPositions;
+
+    const DAY: u8 = 21;
+    const NAME: &'static str = "dirac_die";
+
+    fn parse(&self, input: &str) -> Result<StartingPositions> {
+        let positions: Vec<u8> = parse_lines(Self::DAY, input, |line| {
+            let pos_str = expect_token(line, line.split(": ").nth(1), "starting position")?.trim();
+            let pos: u8 = parse_number(line, pos_str, "starting position")?;
+            if !(1..=10).contains(&pos) {
+                return Err(ParseError::at(
+                    line,
+                    pos_str,
+                    "starting position must be from 1 to 10",
+                ));
+            }
+            Ok(pos)
+        })?;
+        match positions[..] {
+            [pos1, pos2] => Ok(StartingPositions { pos1, pos2 }),
+            _ => Err(ParseError::at_end(
+                input,
+                "expected starting positions for exactly 2 players",
+            )
+            .in_input(Self::DAY, input, input)),
+        }
+    }
+
+    /// losing score times the number of die rolls with the deterministic die
+    fn part1(&self, start: &StartingPositions) -> Result<Answer> {
+        Ok(deterministic_game(start.pos1, start.pos2).into())
+    }
+
+    /// number of universes in which the player that wins more often wins
+    fn part2(&self, start: &StartingPositions) -> Result<Answer> {
+        let (un1, un2) = whowins(start.pos1 - 1, start.pos2 - 1, 0, 0, 1, true);
+        Ok(un1.max(un2).into())
+    }
 }
@@ -1,39 +1,39 @@
-use super::utils::read_input;
+use super::error::{ParseError, Result};
+use super::solver::{Answer, Solver};
+use super::utils::{expect_token, parse_lines, parse_number};
 use bit_set::BitSet;
 use itertools::Itertools;
 
-fn parse_cuboid(s: &str) -> (bool, [i32; 6]) {
+pub fn parse_cuboid(s: &str) -> Result<(bool, [i32; 6]), ParseError> {
     let mut s_iter = s.split(" ");
-    let polarity: bool = match s_iter.next().unwrap() {
+    let polarity_str = expect_token(s, s_iter.next(), "polarity")?;
+    let polarity: bool = match polarity_str {
         "on" => true,
         "off" => false,
-        _ => panic!("Can't parse polarity from {}", s),
+        _ => {
+            return Err(ParseError::at(
+                s,
+                polarity_str,
+                format!("polarity must be 'on' or 'off', not '{}'", polarity_str),
+            ))
+        }
     };
-    fn parse_bounds(s: &str) -> [i32; 2] {
-        let s: String = String::from(&s[2..]);
-        let mut bounds_iter = s.split("..");
-        [
-            bounds_iter
-                .next()
-                .unwrap()
-                .parse()
-                .expect("Can't parse lower bound"),
-            bounds_iter
-                .next()
-                .unwrap()
-                .parse()
-                .expect("Can't parse upper bound"),
-        ]
-    }
 
-    let coords_str = String::from(s_iter.next().unwrap());
+    let coords_str = expect_token(s, s_iter.next(), "coordinates")?;
     let mut coords: [i32; 6] = [0, 0, 0, 0, 0, 0];
-    for (i, bounds_str) in coords_str.split(",").enumerate() {
-        let bounds = parse_bounds(bounds_str);
-        coords[2 * i] = bounds[0];
-        coords[2 * i + 1] = bounds[1];
+    let mut bounds_strs = coords_str.split(",");
+    for (i, axis) in ["x", "y", "z"].iter().enumerate() {
+        let bounds_str = expect_token(s, bounds_strs.next(), &format!("{} bounds", axis))?;
+        let range_str = bounds_str
+            .strip_prefix(axis)
+            .and_then(|rest| rest.strip_prefix('='))
+            .ok_or_else(|| ParseError::at(s, bounds_str, format!("expected '{}=' here", axis)))?;
+        let mut bounds_iter = range_str.split("..");
+        for (j, what) in ["lower bound", "upper bound"].iter().enumerate() {
+            coords[2 * i + j] = parse_number(s, expect_token(s, bounds_iter.next(), what)?, what)?;
+        }
     }
-    (polarity, coords)
+    Ok((polarity, coords))
 }
 
 #[derive(Debug)]
@@ -43,7 +43,7 @@ struct CuboidBound {
     opening: bool,
 }
 
-fn to_bound_sequence(cuboids: &Vec<[i32; 6]>, dim: usize) -> Vec<CuboidBound> {
+fn to_bound_sequence(cuboids: &[[i32; 6]], dim: usize) -> Vec<CuboidBound> {
     let get_lu = |cub: &[i32; 6]| (cub[2 * dim], cub[2 * dim + 1]);
     let mut cbs: Vec<CuboidBound> = Vec::new();
     for (i, c) in cuboids.iter().enumerate() {
@@ -63,13 +63,13 @@ fn to_bound_sequence(cuboids: &Vec<[i32; 6]>, dim: usize) -> Vec<CuboidBound> {
     cbs
 }
 
-#[allow(dead_code)]
-fn clamp_cuboid(c: &[i32; 6], max_abs: i32) -> [i32; 6] {
+/// cuboids outside of the region collapse into empty ones
+pub fn clamp_cuboid(c: &[i32; 6], max_abs: i32) -> [i32; 6] {
     let clamp = |v: i32, add: i32| {
         if v > max_abs {
             max_abs + add
         } else if v < -max_abs {
-            -max_abs + add
+            -max_abs - 1 + add
         } else {
             v
         }
@@ -83,21 +83,15 @@ fn clamp_cuboid(c: &[i32; 6], max_abs: i32) -> [i32; 6] {
     res
 }
 
-pub fn reactor_cubes() {
-    let input = read_input(22, false);
-
-    let cubspecs: Vec<(bool, [i32; 6])> = input.lines().map(|l| parse_cuboid(l)).collect();
-
-    let cuboids: Vec<[i32; 6]> = cubspecs.iter().map(|c| c.1).collect();
-
-    // for pt1
-    // let cuboids: Vec<[i32; 6]> = cuboids.iter().map(|c| clamp_cuboid(c, 50)).collect();
-
-    let polarities: Vec<bool> = cubspecs.iter().map(|c| c.0).collect();
+pub struct RebootSteps {
+    cuboids: Vec<[i32; 6]>,
+    polarities: Vec<bool>,
+}
 
-    let x_bounds = to_bound_sequence(&cuboids, 0);
-    let y_bounds = to_bound_sequence(&cuboids, 1);
-    let z_bounds = to_bound_sequence(&cuboids, 2);
+pub fn count_on_cubes(cuboids: &[[i32; 6]], polarities: &[bool]) -> u64 {
+    let x_bounds = to_bound_sequence(cuboids, 0);
+    let y_bounds = to_bound_sequence(cuboids, 1);
+    let z_bounds = to_bound_sequence(cuboids, 2);
 
     let mut open_along_x: BitSet = BitSet::new();
     let mut open_along_y: BitSet = BitSet::new();
@@ -140,6 +134,32 @@ pub fn reactor_cubes() {
             }
         }
     }
+    total_on
+}
 
-    println!("{}", total_on);
+pub struct ReactorCubes;
+
+impl Solver for ReactorCubes {
+    type Input = RebootSteps;
+
+    const DAY: u8 = 22;
+    const NAME: &'static str = "reactor_cubes";
+
+    fn parse(&self, input: &str) -> Result<RebootSteps> {
+        let cubspecs: Vec<(bool, [i32; 6])> = parse_lines(Self::DAY, input, parse_cuboid)?;
+        Ok(RebootSteps {
+            cuboids: cubspecs.iter().map(|c| c.1).collect(),
+            polarities: cubspecs.iter().map(|c| c.0).collect(),
+        })
+    }
+
+    /// cubes on in the initialization region
+    fn part1(&self, steps: &RebootSteps) -> Result<Answer> {
+        let cuboids: Vec<[i32; 6]> = steps.cuboids.iter().map(|c| clamp_cuboid(c, 50)).collect();
+        Ok(count_on_cubes(&cuboids, &steps.polarities).into())
+    }
+
+    fn part2(&self, steps: &RebootSteps) -> Result<Answer> {
+        Ok(count_on_cubes(&steps.cuboids, &steps.polarities).into())
+    }
 }
@@ -1,14 +1,16 @@
+use super::error::{Error, ParseError, Result};
+use super::solver::{Answer, Solver};
+use super::trace::{debug, enabled, info, trace, Level};
 use priority_queue::PriorityQueue;
 use std::collections::{HashMap, HashSet};
+use std::fmt;
 
-const ROOM_LEN: usize = 4;
+pub const ROOM_LEN: usize = 4;
 const HALLWAY_LEN: usize = 7; // excluding 4 cells above the rooms
-const ROOM_N: usize = 4;
-
-const DEBUG: bool = false;
+pub const ROOM_N: usize = 4;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
-enum Amphipod {
+pub enum Amphipod {
     A,
     B,
     C,
@@ -16,16 +18,16 @@ enum Amphipod {
 }
 
 impl Amphipod {
-    fn parse(ch: char) -> Amphipod {
+    pub fn parse(ch: char) -> Option<Amphipod> {
         match ch {
-            'A' => Amphipod::A,
-            'B' => Amphipod::B,
-            'C' => Amphipod::C,
-            'D' => Amphipod::D,
-            _ => panic!("Can't parse amphipod from {}", ch),
+            'A' => Some(Amphipod::A),
+            'B' => Some(Amphipod::B),
+            'C' => Some(Amphipod::C),
+            'D' => Some(Amphipod::D),
+            _ => None,
         }
     }
-    fn step_cost(&self) -> usize {
+    pub fn step_cost(&self) -> usize {
         match self {
             Amphipod::A => 1,
             Amphipod::B => 10,
@@ -33,7 +35,7 @@ impl Amphipod {
             Amphipod::D => 1000,
         }
     }
-    fn target_room(&self) -> usize {
+    pub fn target_room(&self) -> usize {
         match self {
             Amphipod::A => 0,
             Amphipod::B => 1,
@@ -54,7 +56,7 @@ impl Amphipod {
 type Room = [Option<Amphipod>; ROOM_LEN];
 
 #[derive(Debug, Clone, Hash, PartialEq, Eq)]
-struct State {
+pub struct State {
     rooms: [Room; ROOM_N],
     hallway: [Option<Amphipod>; HALLWAY_LEN],
 }
@@ -76,8 +78,8 @@ fn room_idx_to_x(i: usize) -> usize {
     2 + i * 2
 }
 
-impl State {
-    fn display(&self) {
+impl fmt::Display for State {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         let mut hallway_full: Vec<Option<Amphipod>> = vec![None; HALLWAY_LEN + ROOM_N];
         for hw in 0..HALLWAY_LEN {
             if let Some(a) = self.hallway[hw] {
@@ -86,12 +88,12 @@ impl State {
         }
         for oa in hallway_full {
             match oa {
-                None => print!("."),
-                Some(a) => print!("{}", a.aschar()),
+                None => write!(f, ".")?,
+                Some(a) => write!(f, "{}", a.aschar())?,
             }
         }
-        println!();
         for room_depth in 0..ROOM_LEN {
+            writeln!(f)?;
             let mut rooms_x: Vec<Option<Amphipod>> = vec![None; HALLWAY_LEN + ROOM_N];
             for r_i in 0..ROOM_N {
                 if let Some(a) = self.rooms[r_i][room_depth] {
@@ -100,19 +102,27 @@ impl State {
             }
             for oa in rooms_x {
                 match oa {
-                    None => print!(" "),
-                    Some(a) => print!("{}", a.aschar()),
+                    None => write!(f, " ")?,
+                    Some(a) => write!(f, "{}", a.aschar())?,
                 }
             }
-            println!();
         }
+        Ok(())
     }
+}
 
-    fn parse_init(s: &str) -> State {
+impl State {
+    /// rooms are filled top to bottom from the rows, missing rows at the bottom are
+    /// filled with amphipods already in their places
+    #[allow(clippy::needless_range_loop)]
+    pub fn from_rows(rows: &Vec<[Amphipod; ROOM_N]>) -> State {
         let mut rooms: [Room; ROOM_N] = Default::default();
-        for (i_room, room_s) in s.split(' ').enumerate() {
-            for (i_ch, ch) in room_s.chars().enumerate() {
-                rooms[i_room][i_ch] = Some(Amphipod::parse(ch));
+        for i_room in 0..ROOM_N {
+            for depth in 0..ROOM_LEN {
+                rooms[i_room][depth] = Some(match rows.get(depth) {
+                    Some(row) => row[i_room],
+                    None => [Amphipod::A, Amphipod::B, Amphipod::C, Amphipod::D][i_room],
+                });
             }
         }
         State {
@@ -121,7 +131,7 @@ impl State {
         }
     }
 
-    fn is_end(&self) -> bool {
+    pub fn is_end(&self) -> bool {
         fn room_eq(r1: &Room, r2: &Room) -> bool {
             r1.iter().zip(r2.iter()).all(|(a, b)| a == b)
         }
@@ -132,7 +142,7 @@ impl State {
             && room_eq(&self.rooms[3], &[Some(Amphipod::D); ROOM_LEN])
     }
 
-    fn next_states(&self) -> Vec<(usize, State)> {
+    pub fn next_states(&self) -> Vec<(usize, State)> {
         let mut res: Vec<(usize, State)> = Vec::new();
 
         // next states resulting from moving amphipod from hallway to the room
@@ -140,14 +150,12 @@ impl State {
             if let Some(amph_to_move) = self.hallway[hw] {
                 let target_room = amph_to_move.target_room();
 
-                if DEBUG {
-                    println!(
-                        "{}: hallway {} -> room {}",
-                        amph_to_move.aschar(),
-                        hw,
-                        target_room
-                    );
-                }
+                trace!(
+                    "{}: hallway {} -> room {}",
+                    amph_to_move.aschar(),
+                    hw,
+                    target_room
+                );
 
                 // checking if amphipod can enter their room
                 let mut amphs_in_target: usize = 0;
@@ -160,12 +168,12 @@ impl State {
                         }
                     }
                 }
-                if DEBUG {
-                    println!(
-                        "\tin room {}: {} total amphipods, {} of them match",
-                        target_room, amphs_in_target, matching_amphs_in_target
-                    );
-                }
+                trace!(
+                    "\tin room {}: {} total amphipods, {} of them match",
+                    target_room,
+                    amphs_in_target,
+                    matching_amphs_in_target
+                );
                 if amphs_in_target > matching_amphs_in_target {
                     // not moving there, rule 2
                     continue;
@@ -178,10 +186,8 @@ impl State {
                 } else {
                     hw_next_to_room..hw
                 };
-                if !self.hallway[between_range].iter().all(|hw| hw == &None) {
-                    if DEBUG {
-                        println!("\tmove is blocked!")
-                    }
+                if !self.hallway[between_range].iter().all(|hw| hw.is_none()) {
+                    trace!("\tmove is blocked!");
                     // not moving the path is blocked
                     continue;
                 }
@@ -191,11 +197,7 @@ impl State {
                 // move cost
                 let from_x = hallway_idx_to_x(hw);
                 let to_x = room_idx_to_x(target_room);
-                let horizontal_move_cost = if to_x > from_x {
-                    to_x - from_x
-                } else {
-                    from_x - to_x
-                };
+                let horizontal_move_cost = to_x.abs_diff(from_x);
                 let move_cost =
                     (horizontal_move_cost + target_depth_in_room + 1) * amph_to_move.step_cost();
 
@@ -213,29 +215,26 @@ impl State {
                     false
                 }
             }) {
-                if DEBUG {
-                    println!("room {} if already OK", i_room);
-                }
+                trace!("room {} is already OK", i_room);
                 continue;
             }
             let come_out = self.rooms[i_room]
                 .iter()
                 .enumerate()
-                .filter(|&(_, oa)| oa != &None)
-                .next();
-            if come_out == None {
+                .find(|&(_, oa)| oa.is_some());
+            if come_out.is_none() {
                 continue;
             }
             let (coming_out_depth, coming_out_amph) = come_out.unwrap();
             // looking for placed where it could go
             let mut target_hws: Vec<usize> = Vec::new();
             let mut target_hw = i_room + 2; // right from room
-            while target_hw < HALLWAY_LEN && self.hallway[target_hw] == None {
+            while target_hw < HALLWAY_LEN && self.hallway[target_hw].is_none() {
                 target_hws.push(target_hw);
                 target_hw += 1;
             }
             let mut target_hw = i_room + 1; // left from room
-            while self.hallway[target_hw] == None {
+            while self.hallway[target_hw].is_none() {
                 target_hws.push(target_hw);
                 if target_hw == 0 {
                     break;
@@ -243,14 +242,12 @@ impl State {
                 target_hw -= 1;
             }
 
-            if DEBUG {
-                println!(
-                    "{} from room {} comes to hallway: {:?}",
-                    coming_out_amph.unwrap().aschar(),
-                    i_room,
-                    target_hws,
-                );
-            }
+            trace!(
+                "{} from room {} comes to hallway: {:?}",
+                coming_out_amph.unwrap().aschar(),
+                i_room,
+                target_hws,
+            );
 
             for target_hw in target_hws {
                 let mut new_state = self.clone();
@@ -260,11 +257,7 @@ impl State {
                 // move cost
                 let from_x = room_idx_to_x(i_room);
                 let to_x = hallway_idx_to_x(target_hw);
-                let horizontal_move_cost = if to_x > from_x {
-                    to_x - from_x
-                } else {
-                    from_x - to_x
-                };
+                let horizontal_move_cost = to_x.abs_diff(from_x);
                 let move_cost = (horizontal_move_cost + coming_out_depth + 1)
                     * coming_out_amph.unwrap().step_cost();
 
@@ -276,9 +269,8 @@ impl State {
     }
 }
 
-const DISPLAY: bool = false;
-
-fn dijkstra(state: State) -> i32 {
+/// None if the amphipods can't be organized at all
+pub fn dijkstra(state: State) -> Option<i32> {
     let mut pq: PriorityQueue<State, i32> = PriorityQueue::new();
     let mut visited: HashSet<State> = HashSet::new();
     let mut previous: HashMap<State, State> = HashMap::new();
@@ -300,33 +292,118 @@ fn dijkstra(state: State) -> i32 {
             pq.push(new_state, priority);
         }
         visited.insert(now_at.clone());
-        let next_with_cost = pq.pop().unwrap();
+        let next_with_cost = pq.pop()?;
         now_at = next_with_cost.0;
         if now_at.is_end() {
             break;
         }
         neg_total_cost = next_with_cost.1;
-        if DISPLAY {
-            println!("\n{}", -neg_total_cost);
-            now_at.display();
+        trace!("expanding state with cost {}\n{}", -neg_total_cost, now_at);
+    }
+
+    info!("organized after visiting {} states", visited.len());
+    if enabled!(Level::Debug) {
+        debug!("path from the end:");
+        loop {
+            debug!("\n{}", now_at);
+            if let Some(state) = previous.get(&now_at) {
+                now_at = state.clone();
+            } else {
+                break;
+            }
         }
     }
 
-    loop {
-        println!();
-        now_at.display();
-        if let Some(state) = previous.get(&now_at) {
-            now_at = state.clone();
-        } else {
-            break;
+    Some(-neg_total_cost)
+}
+
+/// rows on the folded diagram, unfolding adds the rest
+const FOLDED_ROWS: usize = ROOM_LEN - 2;
+
+/// Amphipods in the rooms as seen on the diagram, top to bottom
+pub struct Burrow {
+    pub rows: Vec<[Amphipod; ROOM_N]>,
+}
+
+impl Burrow {
+    pub fn parse(s: &str) -> Result<Burrow, ParseError> {
+        let mut rows: Vec<[Amphipod; ROOM_N]> = Vec::new();
+        for line in s.lines() {
+            let mut amphipods: Vec<Amphipod> = Vec::new();
+            for (idx, ch) in line.char_indices() {
+                if !ch.is_ascii_alphabetic() {
+                    continue;
+                }
+                match Amphipod::parse(ch) {
+                    Some(a) => amphipods.push(a),
+                    None => {
+                        return Err(ParseError::at(
+                            line,
+                            &line[idx..idx + ch.len_utf8()],
+                            format!("unknown amphipod '{}'", ch),
+                        )
+                        .within(s, line))
+                    }
+                }
+            }
+            if amphipods.is_empty() {
+                continue;
+            }
+            if rows.len() == FOLDED_ROWS {
+                return Err(
+                    ParseError::at(line, line, "too many rows in the rooms").within(s, line)
+                );
+            }
+            rows.push(amphipods.try_into().map_err(|_| {
+                ParseError::at(
+                    line,
+                    line,
+                    format!("row must have an amphipod in each of {} rooms", ROOM_N),
+                )
+                .within(s, line)
+            })?);
+        }
+        if rows.len() < FOLDED_ROWS {
+            return Err(ParseError::at_end(
+                s,
+                format!("rooms must have {} rows of amphipods", FOLDED_ROWS),
+            ));
         }
+        Ok(Burrow { rows })
     }
 
-    return -neg_total_cost;
+    /// two more rows appear in the middle when the diagram is unfolded
+    pub fn unfolded(&self) -> Burrow {
+        use Amphipod::*;
+        let mut rows = self.rows.clone();
+        rows.insert(1, [D, B, A, C]);
+        rows.insert(1, [D, C, B, A]);
+        Burrow { rows }
+    }
 }
 
-pub fn amphipod_rooms() {
-    let init = "DDDC ACBA CBAB DACB"; // left to right, top to bottom in each room
-    let state = State::parse_init(init);
-    println!("the most efficient path takes {}", dijkstra(state));
+pub struct AmphipodRooms;
+
+impl Solver for AmphipodRooms {
+    type Input = Burrow;
+
+    const DAY: u8 = 23;
+    const NAME: &'static str = "amphipod_rooms";
+
+    fn parse(&self, input: &str) -> Result<Burrow> {
+        Burrow::parse(input).map_err(|e| e.in_input(Self::DAY, input, input))
+    }
+
+    /// the least energy required to organize the amphipods
+    fn part1(&self, burrow: &Burrow) -> Result<Answer> {
+        dijkstra(State::from_rows(&burrow.rows))
+            .map(Answer::from)
+            .ok_or_else(|| Error::no_solution(Self::DAY, "amphipods can't be organized"))
+    }
+
+    fn part2(&self, burrow: &Burrow) -> Result<Answer> {
+        dijkstra(State::from_rows(&burrow.unfolded().rows))
+            .map(Answer::from)
+            .ok_or_else(|| Error::no_solution(Self::DAY, "amphipods can't be organized"))
+    }
 }
@@ -1,13 +1,33 @@
-use super::utils::read_input;
-use itertools::Itertools;
-use memoize::memoize;
-use std::collections::HashMap;
-use std::{lazy::SyncLazy, sync::Mutex};
+pub mod alu;
+pub mod analysis;
+pub mod compile;
+pub mod debugger;
+pub mod disasm;
+pub mod fuzz;
+pub mod search;
+pub mod symbolic;
+pub mod word;
 
-type Int = i64;
+use super::error::{Error, ParseError, Result};
+use super::solver::{Answer, Solver};
+use super::trace::{debug, enabled, info, Level};
+use super::utils::{expect_token, parse_lines, parse_number};
+use alu::Alu;
+use analysis::Analysis;
+use compile::Compiled;
+use disasm::Disassembly;
+use search::{
+    carried_register, compile_blocks, count_valid, par_first_valid, SearchError, ValidNumbers,
+};
+use std::fmt;
+use std::thread;
+use symbolic::Bounds;
+use word::{Overflow, Word};
+
+pub type Int = i64;
 
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
-enum Op {
+pub enum Op {
     Add,
     Mul,
     Div,
@@ -16,7 +36,7 @@ enum Op {
 }
 
 impl Op {
-    fn parse(s: &str) -> Option<Op> {
+    pub fn parse(s: &str) -> Option<Op> {
         match s {
             "add" => Some(Op::Add),
             "mul" => Some(Op::Mul),
@@ -27,25 +47,24 @@ impl Op {
         }
     }
 
-    fn result(&self, n1: &Int, n2: &Int) -> Int {
+    pub fn name(&self) -> &'static str {
         match self {
-            Op::Add => n1 + n2,
-            Op::Mul => n1 * n2,
-            Op::Div => n1 / n2,
-            Op::Mod => n1 % n2,
-            Op::Eql => {
-                if n1 == n2 {
-                    1
-                } else {
-                    0
-                }
-            }
+            Op::Add => "add",
+            Op::Mul => "mul",
+            Op::Div => "div",
+            Op::Mod => "mod",
+            Op::Eql => "eql",
         }
     }
+
+    /// None on division by zero or overflow
+    pub fn result(&self, n1: &Int, n2: &Int) -> Option<Int> {
+        Int::checked(*self, *n1, *n2)
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
-enum Var {
+pub enum Var {
     X,
     Y,
     Z,
@@ -53,7 +72,7 @@ enum Var {
 }
 
 impl Var {
-    fn parse(s: &str) -> Option<Var> {
+    pub fn parse(s: &str) -> Option<Var> {
         match s {
             "x" => Some(Var::X),
             "y" => Some(Var::Y),
@@ -62,160 +81,350 @@ impl Var {
             &_ => None,
         }
     }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Var::X => "x",
+            Var::Y => "y",
+            Var::Z => "z",
+            Var::W => "w",
+        }
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
-enum Value {
+pub enum Value {
     Variable(Var),
     Number(Int),
 }
 
-impl Value {
-    fn as_string(&self) -> String {
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
-            Value::Number(n) => n.to_string(),
-            Value::Variable(var) => format!("{:?}", var),
+            Value::Number(n) => write!(f, "{}", n),
+            Value::Variable(var) => write!(f, "{}", var.name()),
         }
     }
 }
 
-type CodeBlock = Vec<(Op, Value, Value)>;
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Instruction {
+    Inp(Var),
+    Op(Op, Var, Value),
+}
 
-fn parse_code_blocks(input: &String) -> Vec<CodeBlock> {
-    fn parse_expression(s: &str) -> Option<(Op, Value, Value)> {
-        let mut tokens = s.split(' ');
-        let op = Op::parse(tokens.next()?)?;
-        let value1_str = tokens.next()?;
-        let value1 = if let Some(var) = Var::parse(value1_str) {
-            Value::Variable(var)
-        } else {
-            Value::Number(value1_str.parse().expect("Can't parse numberic value"))
-        };
-        let value2_str = tokens.next().unwrap();
-        let value2 = if let Some(var) = Var::parse(value2_str) {
-            Value::Variable(var)
-        } else {
-            Value::Number(value2_str.parse().expect("Can't parse numberic value"))
-        };
-        return Some((op, value1, value2));
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Instruction::Inp(var) => write!(f, "inp {}", var.name()),
+            Instruction::Op(op, var, value) => write!(f, "{} {} {}", op.name(), var.name(), value),
+        }
     }
+}
 
-    let mut blocks: Vec<CodeBlock> = Vec::new();
-    let mut current_block: CodeBlock = Vec::new();
+pub type Program = Vec<Instruction>;
 
-    for line in input.lines() {
-        if line.starts_with("inp") {
-            if current_block.len() > 0 {
-                blocks.push(current_block);
-                current_block = Vec::new();
-            }
-            continue;
-        }
-        if let Some((o, v1, v2)) = parse_expression(line) {
-            current_block.push((o, v1, v2));
-        } else {
-            println!("unable to parse line {}", line);
+/// Instructions from one inp up to the next one
+pub type CodeBlock = [Instruction];
+
+fn parse_var(s: &str, token: &str, what: &str) -> Result<Var, ParseError> {
+    Var::parse(token).ok_or_else(|| {
+        ParseError::at(
+            s,
+            token,
+            format!("{} must be a variable, not '{}'", what, token),
+        )
+    })
+}
+
+fn parse_instruction(s: &str) -> Result<Instruction, ParseError> {
+    let mut tokens = s.split(' ');
+    let op_str = expect_token(s, tokens.next(), "instruction")?;
+    if op_str == "inp" {
+        let var_str = expect_token(s, tokens.next(), "input variable")?;
+        return Ok(Instruction::Inp(parse_var(s, var_str, "input operand")?));
+    }
+    let op = Op::parse(op_str)
+        .ok_or_else(|| ParseError::at(s, op_str, format!("unknown instruction '{}'", op_str)))?;
+    let var = parse_var(
+        s,
+        expect_token(s, tokens.next(), "first operand")?,
+        "first operand",
+    )?;
+    let value_str = expect_token(s, tokens.next(), "second operand")?;
+    let value = match Var::parse(value_str) {
+        Some(var) => Value::Variable(var),
+        None => Value::Number(parse_number(s, value_str, "second operand")?),
+    };
+    Ok(Instruction::Op(op, var, value))
+}
+
+pub fn parse_program(input: &str) -> Result<Program> {
+    parse_lines(ArithmeticLogicUnit::DAY, input, parse_instruction)
+}
+
+/// Splits the program before each inp, instructions before the first inp make a block of their own
+pub fn code_blocks(program: &[Instruction]) -> Vec<&CodeBlock> {
+    let mut blocks: Vec<&CodeBlock> = Vec::new();
+    let mut start = 0;
+    for (idx, instruction) in program.iter().enumerate() {
+        if matches!(instruction, Instruction::Inp(_)) && idx > start {
+            blocks.push(&program[start..idx]);
+            start = idx;
         }
     }
-    if current_block.len() > 0 {
-        blocks.push(current_block);
+    if start < program.len() {
+        blocks.push(&program[start..]);
     }
-
     blocks
 }
 
-static CODE_BLOCKS: SyncLazy<Mutex<Vec<CodeBlock>>> = SyncLazy::new(|| Mutex::new(vec![]));
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Order {
+    Ascending,
+    Descending,
+}
 
-const DEBUG_EXECUTE: bool = false;
+impl Order {
+    pub fn digits(&self) -> [u8; 9] {
+        match self {
+            Order::Ascending => [1, 2, 3, 4, 5, 6, 7, 8, 9],
+            Order::Descending => [9, 8, 7, 6, 5, 4, 3, 2, 1],
+        }
+    }
+}
 
-// #[memoize]
-fn execute_code_block(code_block_idx: usize, z_init: Int, input: u8) -> Int {
-    let mut var_values: HashMap<Var, Int> = HashMap::new();
-    var_values.insert(Var::X, 0);
-    var_values.insert(Var::Y, 0);
-    var_values.insert(Var::Z, z_init);
-    var_values.insert(Var::W, input as Int);
+/// None if the number doesn't fit in 64 bits
+pub fn to_number(digits: &[u8]) -> Option<u64> {
+    digits
+        .iter()
+        .try_fold(0u64, |acc, d| acc.checked_mul(10)?.checked_add(*d as u64))
+}
 
-    if DEBUG_EXECUTE {
-        println!(
-            "\nexecuting code block\nx = {}, y = {}, z = {}, input = {}",
-            var_values.get(&Var::X).unwrap(),
-            var_values.get(&Var::Y).unwrap(),
-            var_values.get(&Var::Z).unwrap(),
-            var_values.get(&Var::W).unwrap()
-        )
+/// The digits as text, for numbers of any length
+fn show(digits: &[u8]) -> String {
+    digits.iter().map(|d| char::from(b'0' + d)).collect()
+}
+
+/// true if MONAD accepts the number
+pub fn is_valid(program: &[Instruction], digits: &[u8]) -> bool {
+    accepts(program, digits, Overflow::default())
+}
+
+fn accepts(program: &[Instruction], digits: &[u8], overflow: Overflow) -> bool {
+    Alu::new(program)
+        .with_overflow(overflow)
+        .run(digits.iter().map(|d| *d as Int))
+        .is_ok_and(|registers| registers[Var::Z] == 0)
+}
+
+/// Valid model numbers of a program, derived by the analysis when the program follows
+/// the MONAD template and searched for block by block otherwise
+pub struct ModelNumbers {
+    /// the whole program, numbers found by the search are checked on it
+    program: Program,
+    overflow: Overflow,
+    blocks: Vec<Compiled>,
+    analysis: Option<Analysis>,
+    /// threads searching for the max and min when the analysis fails
+    threads: usize,
+}
+
+impl ModelNumbers {
+    /// Overflows are errors
+    pub fn new(program: &[Instruction]) -> Result<ModelNumbers> {
+        ModelNumbers::with_overflow(program, Overflow::default())
     }
 
-    let code_blocks = CODE_BLOCKS.lock().unwrap();
+    /// The search runs the blocks with the given overflow, the analysis assumes there is none
+    pub fn with_overflow(program: &[Instruction], overflow: Overflow) -> Result<ModelNumbers> {
+        let blocks = code_blocks(program);
+        if blocks.is_empty() {
+            return Err(Error::no_solution(
+                ArithmeticLogicUnit::DAY,
+                "program has no code blocks",
+            ));
+        }
+        if blocks
+            .iter()
+            .any(|block| !matches!(block[0], Instruction::Inp(_)))
+        {
+            return Err(Error::no_solution(
+                ArithmeticLogicUnit::DAY,
+                "program must start with inp, each block reads a single digit",
+            ));
+        }
+        let analysis = match Analysis::new(&blocks) {
+            Ok(analysis) => {
+                // a cheap sanity check that the program really does what the analysis assumes
+                let extremes = [analysis.max_model_number(), analysis.min_model_number()];
+                match extremes
+                    .iter()
+                    .flatten()
+                    .find(|d| !accepts(program, d, overflow))
+                {
+                    Some(digits) => {
+                        info!(
+                            "analysis gave an invalid number {}, searching instead",
+                            show(digits)
+                        );
+                        None
+                    }
+                    None => Some(analysis),
+                }
+            }
+            Err(e) => {
+                info!("can't analyze the program, searching instead: {}", e);
+                if enabled!(Level::Debug) {
+                    let z =
+                        &symbolic::evaluate_with_overflow(program, Bounds::DIGIT, overflow)[Var::Z];
+                    debug!("z after the program:\n{}", z.formula("z").trim_end());
+                    debug!(
+                        "blocks:\n{}",
+                        Disassembly::new(program).to_string().trim_end()
+                    );
+                }
+                None
+            }
+        };
+        if analysis.is_none() {
+            // the first block starts from zeros like the search does
+            let carried =
+                blocks.iter().enumerate().skip(1).find_map(|(idx, block)| {
+                    carried_register(block).map(|(pc, var)| (idx, pc, var))
+                });
+            if let Some((idx, pc, var)) = carried {
+                return Err(Error::no_solution(
+                    ArithmeticLogicUnit::DAY,
+                    format!(
+                        "block {}, instruction {}: reads {} before writing it, \
+                         the search only carries z from block to block",
+                        idx,
+                        pc,
+                        var.name()
+                    ),
+                ));
+            }
+        }
+        Ok(ModelNumbers {
+            program: program.to_vec(),
+            overflow,
+            blocks: compile_blocks(&blocks, overflow),
+            analysis,
+            threads: thread::available_parallelism().map_or(1, |n| n.get()),
+        })
+    }
 
-    let code_block = code_blocks.get(code_block_idx).unwrap();
+    /// Number of search threads, all available cores by default
+    pub fn with_threads(mut self, threads: usize) -> ModelNumbers {
+        self.threads = threads.max(1);
+        self
+    }
 
-    for (op, v1, v2) in code_block {
-        if DEBUG_EXECUTE {
-            println!("{:?} {} {}", op, v1.as_string(), v2.as_string());
+    /// Valid numbers in the given order, computed lazily, the search ends after an error
+    pub fn iter(&self, order: Order) -> Box<dyn Iterator<Item = Result<Vec<u8>>> + '_> {
+        match &self.analysis {
+            Some(analysis) => Box::new(analysis.numbers(order).map(Ok)),
+            None => Box::new(ValidNumbers::new(&self.blocks, order).map(|number| {
+                number
+                    .map_err(search_error)
+                    .and_then(|number| self.checked(number))
+            })),
         }
-        if let Value::Variable(var1) = v1 {
-            let n1 = var_values.get(var1).unwrap();
-            let n2 = match v2 {
-                Value::Number(n) => n,
-                Value::Variable(var2) => var_values.get(var2).unwrap(),
-            };
-            // set_var(*var1, op.result(&value1, &value2));
-            let result = op.result(&n1, &n2);
-            var_values.insert(*var1, result);
-        } else {
-            panic!();
+    }
+
+    /// Number found by the search if the whole program accepts it too
+    fn checked(&self, number: Vec<u8>) -> Result<Vec<u8>> {
+        if accepts(&self.program, &number, self.overflow) {
+            return Ok(number);
         }
-        if DEBUG_EXECUTE {
-            println!(
-                "x = {}, y = {}, z = {}",
-                var_values.get(&Var::X).unwrap(),
-                var_values.get(&Var::Y).unwrap(),
-                var_values.get(&Var::Z).unwrap(),
-            )
+        Err(Error::no_solution(
+            ArithmeticLogicUnit::DAY,
+            format!("search found {} but the program rejects it", show(&number)),
+        ))
+    }
+
+    fn first(&self, order: Order) -> Result<Option<Vec<u8>>> {
+        if let Some(analysis) = &self.analysis {
+            return Ok(analysis.numbers(order).next());
         }
+        let first = if self.threads == 1 {
+            let mut numbers = ValidNumbers::new(&self.blocks, order);
+            let first = numbers.next().transpose();
+            info!(
+                "{:?} search visited {} dead (digit, z) states",
+                order,
+                numbers.dead_states()
+            );
+            first
+        } else {
+            let (first, dead_states) = par_first_valid(&self.blocks, order, self.threads);
+            info!(
+                "{:?} search on {} threads visited {} dead (digit, z) states",
+                order, self.threads, dead_states
+            );
+            first
+        };
+        first
+            .map_err(search_error)?
+            .map(|number| self.checked(number))
+            .transpose()
     }
 
-    *var_values.get(&Var::Z).unwrap()
-}
+    pub fn max(&self) -> Result<Option<Vec<u8>>> {
+        self.first(Order::Descending)
+    }
 
-#[memoize]
-fn recurse(digit_idx: usize, z_value: Int) -> Option<Vec<u8>> {
-    let code_blocks_total = CODE_BLOCKS.lock().unwrap().len();
-    for digit_value in 1..=9 {
-        let next_z_value = execute_code_block(digit_idx, z_value, digit_value);
-        if digit_idx == code_blocks_total - 1 {
-            if next_z_value == 0 {
-                return Some(vec![digit_value]);
-            }
-        } else if let Some(mut valid_digits) = recurse(digit_idx + 1, next_z_value) {
-            let mut res: Vec<u8> = Vec::new();
-            res.push(digit_value);
-            res.append(&mut valid_digits);
-            return Some(res);
+    pub fn min(&self) -> Result<Option<Vec<u8>>> {
+        self.first(Order::Ascending)
+    }
+
+    pub fn count(&self) -> Result<u64> {
+        match &self.analysis {
+            Some(analysis) => Ok(analysis.count()),
+            None => count_valid(&self.blocks).map_err(search_error),
         }
     }
-    None
 }
 
-fn find_valid_number() -> String {
-    match recurse(0, 0) {
-        None => "None".to_string(),
-        Some(vec) => vec.iter().map(|d| d.to_string()).join(""),
+fn search_error(e: SearchError) -> Error {
+    Error::no_solution(ArithmeticLogicUnit::DAY, format!("search failed, {}", e))
+}
+
+fn answer(digits: Option<Vec<u8>>) -> Result<Answer> {
+    match digits {
+        None => Err(Error::no_solution(
+            ArithmeticLogicUnit::DAY,
+            "no model number is valid",
+        )),
+        Some(digits) => to_number(&digits).map(Answer::from).ok_or_else(|| {
+            Error::no_solution(
+                ArithmeticLogicUnit::DAY,
+                format!("model number {} doesn't fit in 64 bits", show(&digits)),
+            )
+        }),
     }
 }
 
-pub fn arithmetic_logic_unit() {
-    let input = read_input(24, false);
+pub struct ArithmeticLogicUnit;
+
+impl Solver for ArithmeticLogicUnit {
+    type Input = Program;
 
-    let blocks = parse_code_blocks(&input);
+    const DAY: u8 = 24;
+    const NAME: &'static str = "arithmetic_logic_unit";
 
-    // TEMP
-    // let blocks = Vec::from_iter(blocks.into_iter().take(6));
+    fn parse(&self, input: &str) -> Result<Program> {
+        parse_program(input)
+    }
 
-    for b in blocks {
-        CODE_BLOCKS.lock().unwrap().push(b);
+    /// the largest model number accepted by MONAD
+    fn part1(&self, program: &Program) -> Result<Answer> {
+        answer(ModelNumbers::new(program)?.max()?)
     }
 
-    println!("\n{}", find_valid_number());
+    /// the smallest model number accepted by MONAD
+    fn part2(&self, program: &Program) -> Result<Answer> {
+        answer(ModelNumbers::new(program)?.min()?)
+    }
 }
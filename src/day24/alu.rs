@@ -0,0 +1,212 @@
+//! ALU virtual machine running whole programs
+//!
+//! Registers are i64 unless asked otherwise, see `Word` for the other widths and `Overflow` for
+//! what happens when a result doesn't fit.
+
+use super::word::{Overflow, Word};
+use super::{Instruction, Int, Op, Value, Var};
+use crate::trace::trace;
+use std::fmt;
+use std::ops::Index;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Registers<W: Word = Int> {
+    values: [W; 4],
+}
+
+impl Registers {
+    pub const VARS: [Var; 4] = [Var::W, Var::X, Var::Y, Var::Z];
+
+    pub(super) fn slot(var: Var) -> usize {
+        match var {
+            Var::W => 0,
+            Var::X => 1,
+            Var::Y => 2,
+            Var::Z => 3,
+        }
+    }
+
+    pub fn value(&self, value: &Value) -> Int {
+        match value {
+            Value::Number(n) => *n,
+            Value::Variable(var) => self[*var],
+        }
+    }
+}
+
+impl<W: Word> Registers<W> {
+    pub(super) fn from_slots(values: [W; 4]) -> Registers<W> {
+        Registers { values }
+    }
+
+    pub(super) fn slots(&self) -> [W; 4] {
+        self.values
+    }
+
+    pub fn set(&mut self, var: Var, value: W) {
+        self.values[Registers::slot(var)] = value;
+    }
+
+    pub fn with(mut self, var: Var, value: W) -> Registers<W> {
+        self.set(var, value);
+        self
+    }
+}
+
+impl<W: Word> Index<Var> for Registers<W> {
+    type Output = W;
+
+    fn index(&self, var: Var) -> &W {
+        &self.values[Registers::slot(var)]
+    }
+}
+
+impl<W: Word> fmt::Display for Registers<W> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "w = {}, x = {}, y = {}, z = {}",
+            self[Var::W],
+            self[Var::X],
+            self[Var::Y],
+            self[Var::Z]
+        )
+    }
+}
+
+/// The ALU crashes on these, `pc` is the index of the failing instruction
+///
+/// Overflow means that the result or the constant operand doesn't fit into a register.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AluError {
+    DivisionByZero { pc: usize, op: Op },
+    InputExhausted { pc: usize },
+    Overflow { pc: usize, op: Op },
+}
+
+impl fmt::Display for AluError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AluError::DivisionByZero { pc, op } => {
+                write!(f, "instruction {}: {} by zero", pc, op.name())
+            }
+            AluError::InputExhausted { pc } => {
+                write!(f, "instruction {}: inp reads past the end of input", pc)
+            }
+            AluError::Overflow { pc, op } => {
+                write!(f, "instruction {}: {} overflows", pc, op.name())
+            }
+        }
+    }
+}
+
+impl std::error::Error for AluError {}
+
+pub struct Alu<'a, W: Word = Int> {
+    program: &'a [Instruction],
+    registers: Registers<W>,
+    pc: usize,
+    overflow: Overflow,
+}
+
+impl<'a, W: Word> Alu<'a, W> {
+    /// all registers start at zero
+    pub fn new(program: &'a [Instruction]) -> Alu<'a, W> {
+        Alu::with_registers(program, Registers::default())
+    }
+
+    /// Overflows are errors
+    pub fn with_registers(program: &'a [Instruction], registers: Registers<W>) -> Alu<'a, W> {
+        Alu {
+            program,
+            registers,
+            pc: 0,
+            overflow: Overflow::default(),
+        }
+    }
+
+    pub fn with_overflow(mut self, overflow: Overflow) -> Alu<'a, W> {
+        self.overflow = overflow;
+        self
+    }
+
+    pub fn registers(&self) -> Registers<W> {
+        self.registers
+    }
+
+    /// index of the next instruction to execute
+    pub fn pc(&self) -> usize {
+        self.pc
+    }
+
+    pub fn is_halted(&self) -> bool {
+        self.pc >= self.program.len()
+    }
+
+    /// Executes a single instruction, does nothing once the program has halted
+    pub fn step<I: Iterator<Item = W>>(&mut self, input: &mut I) -> Result<(), AluError> {
+        let instruction = match self.program.get(self.pc) {
+            Some(instruction) => instruction,
+            None => return Ok(()),
+        };
+        match instruction {
+            Instruction::Inp(var) => {
+                let value = input
+                    .next()
+                    .ok_or(AluError::InputExhausted { pc: self.pc })?;
+                self.registers.set(*var, value);
+            }
+            Instruction::Op(op, var, value) => {
+                let (pc, op) = (self.pc, *op);
+                let n1 = self.registers[*var];
+                let n2 = match value {
+                    Value::Number(n) => self.overflow.convert(*n),
+                    Value::Variable(src) => Some(self.registers[*src]),
+                }
+                .ok_or(AluError::Overflow { pc, op })?;
+                if n2 == W::default() && matches!(op, Op::Div | Op::Mod) {
+                    return Err(AluError::DivisionByZero { pc, op });
+                }
+                let res = self
+                    .overflow
+                    .apply(op, n1, n2)
+                    .ok_or(AluError::Overflow { pc, op })?;
+                self.registers.set(*var, res);
+            }
+        }
+        trace!(
+            "{:4} {:<12} {}",
+            self.pc,
+            instruction.to_string(),
+            self.registers
+        );
+        self.pc += 1;
+        Ok(())
+    }
+
+    /// Runs the program to the end, unused input is left alone
+    pub fn run<I: IntoIterator<Item = W>>(&mut self, input: I) -> Result<Registers<W>, AluError> {
+        let mut input = input.into_iter();
+        while !self.is_halted() {
+            self.step(&mut input)?;
+        }
+        Ok(self.registers)
+    }
+}
+
+/// Final registers of the program started with all zeros
+pub fn run<I: IntoIterator<Item = Int>>(
+    program: &[Instruction],
+    input: I,
+) -> Result<Registers, AluError> {
+    Alu::new(program).run(input)
+}
+
+/// Same for registers of another width or with another overflow policy
+pub fn run_with<W: Word, I: IntoIterator<Item = W>>(
+    program: &[Instruction],
+    input: I,
+    overflow: Overflow,
+) -> Result<Registers<W>, AluError> {
+    Alu::new(program).with_overflow(overflow).run(input)
+}
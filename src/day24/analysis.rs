@@ -0,0 +1,263 @@
+//! Static analysis of MONAD-style programs
+//!
+//! Every block of such a program treats z as a stack of base-26 digits and looks like this,
+//! with only the divisor, the check and the offset changing between blocks:
+//!
+//! ```text
+//! inp w
+//! mul x 0; add x z; mod x 26    x = top of the stack
+//! div z <divisor>               1 keeps the top, 26 pops it
+//! add x <check>
+//! eql x w; eql x 0              x = 1 unless w == top + check
+//! mul y 0; add y 25; mul y x; add y 1; mul z y
+//! mul y 0; add y w; add y <offset>; mul y x; add z y
+//!                               if x == 1, push w + offset
+//! ```
+//!
+//! Blocks with divisor 1 and a check above 9 always push. Blocks with divisor 26 pop and must
+//! match, otherwise they push instead and z can't get back to zero. So each popping block pairs
+//! up with the block that pushed, and their digits are tied by `later = earlier + diff`.
+
+use super::{CodeBlock, Instruction, Int, Op, Order, Value, Var};
+use itertools::Itertools;
+use std::fmt;
+use std::ops::RangeInclusive;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BlockParams {
+    pub divisor: Int,
+    pub check: Int,
+    pub offset: Int,
+}
+
+impl BlockParams {
+    pub fn is_push(&self) -> bool {
+        self.divisor == 1
+    }
+}
+
+/// Digit `later` must be equal to digit `earlier` plus `diff`, indices are 0-based
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Constraint {
+    pub earlier: usize,
+    pub later: usize,
+    pub diff: Int,
+}
+
+impl Constraint {
+    /// digit pairs satisfying the constraint
+    pub fn choices(&self) -> Int {
+        (9 - self.diff.abs()).max(0)
+    }
+
+    /// values of the earlier digit that keep the later one within 1..=9
+    pub fn earlier_digits(&self) -> RangeInclusive<Int> {
+        1.max(1 - self.diff)..=9.min(9 - self.diff)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AnalysisError {
+    /// the block doesn't follow the template, `line` is the first differing instruction in it
+    UnknownShape { block: usize, line: usize },
+    /// parameters outside of the range the stack reasoning holds for
+    UnsupportedParams { block: usize, params: BlockParams },
+    /// a block pops from an empty stack
+    EmptyStack { block: usize },
+    /// pushed values that no block pops
+    Unbalanced { unmatched: usize },
+}
+
+impl fmt::Display for AnalysisError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AnalysisError::UnknownShape { block, line } => write!(
+                f,
+                "block {} doesn't look like a MONAD block at instruction {}",
+                block, line
+            ),
+            AnalysisError::UnsupportedParams { block, params } => write!(
+                f,
+                "block {} has unsupported parameters: div {}, check {}, offset {}",
+                block, params.divisor, params.check, params.offset
+            ),
+            AnalysisError::EmptyStack { block } => {
+                write!(f, "block {} pops from an empty stack", block)
+            }
+            AnalysisError::Unbalanced { unmatched } => {
+                write!(f, "{} pushed values are never popped", unmatched)
+            }
+        }
+    }
+}
+
+impl std::error::Error for AnalysisError {}
+
+/// Parameter slots of the block template
+#[derive(Clone, Copy)]
+enum Slot {
+    Divisor,
+    Check,
+    Offset,
+}
+
+enum Operand {
+    Var(Var),
+    Number(Int),
+    Param(Slot),
+}
+
+const TEMPLATE: [(Op, Var, Operand); 17] = [
+    (Op::Mul, Var::X, Operand::Number(0)),
+    (Op::Add, Var::X, Operand::Var(Var::Z)),
+    (Op::Mod, Var::X, Operand::Number(26)),
+    (Op::Div, Var::Z, Operand::Param(Slot::Divisor)),
+    (Op::Add, Var::X, Operand::Param(Slot::Check)),
+    (Op::Eql, Var::X, Operand::Var(Var::W)),
+    (Op::Eql, Var::X, Operand::Number(0)),
+    (Op::Mul, Var::Y, Operand::Number(0)),
+    (Op::Add, Var::Y, Operand::Number(25)),
+    (Op::Mul, Var::Y, Operand::Var(Var::X)),
+    (Op::Add, Var::Y, Operand::Number(1)),
+    (Op::Mul, Var::Z, Operand::Var(Var::Y)),
+    (Op::Mul, Var::Y, Operand::Number(0)),
+    (Op::Add, Var::Y, Operand::Var(Var::W)),
+    (Op::Add, Var::Y, Operand::Param(Slot::Offset)),
+    (Op::Mul, Var::Y, Operand::Var(Var::X)),
+    (Op::Add, Var::Z, Operand::Var(Var::Y)),
+];
+
+/// Parameters of a block following the template, `idx` is only used in errors
+pub fn block_params(idx: usize, block: &CodeBlock) -> Result<BlockParams, AnalysisError> {
+    let unknown_shape = |line: usize| AnalysisError::UnknownShape { block: idx, line };
+    if block.first() != Some(&Instruction::Inp(Var::W)) {
+        return Err(unknown_shape(0));
+    }
+    let mut params = BlockParams {
+        divisor: 0,
+        check: 0,
+        offset: 0,
+    };
+    for (line, (instruction, (op, var, operand))) in
+        block[1..].iter().zip(TEMPLATE.iter()).enumerate()
+    {
+        let (actual_op, actual_var, actual_value) = match instruction {
+            Instruction::Op(op, var, value) => (op, var, value),
+            Instruction::Inp(_) => return Err(unknown_shape(line + 1)),
+        };
+        if actual_op != op || actual_var != var {
+            return Err(unknown_shape(line + 1));
+        }
+        match (operand, actual_value) {
+            (Operand::Var(v), Value::Variable(actual)) if v == actual => {}
+            (Operand::Number(n), Value::Number(actual)) if n == actual => {}
+            (Operand::Param(slot), Value::Number(n)) => match slot {
+                Slot::Divisor => params.divisor = *n,
+                Slot::Check => params.check = *n,
+                Slot::Offset => params.offset = *n,
+            },
+            _ => return Err(unknown_shape(line + 1)),
+        }
+    }
+    if block.len() != TEMPLATE.len() + 1 {
+        return Err(unknown_shape(block.len().min(TEMPLATE.len() + 1)));
+    }
+
+    // pushed values must fit a base-26 digit and be non-zero, pushing blocks must never match
+    let supported = (0..=16).contains(&params.offset)
+        && match params.divisor {
+            1 => params.check > 9,
+            26 => true,
+            _ => false,
+        };
+    if !supported {
+        return Err(AnalysisError::UnsupportedParams { block: idx, params });
+    }
+    Ok(params)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Analysis {
+    pub params: Vec<BlockParams>,
+    pub constraints: Vec<Constraint>,
+}
+
+impl Analysis {
+    pub fn new(blocks: &[&CodeBlock]) -> Result<Analysis, AnalysisError> {
+        let params = blocks
+            .iter()
+            .enumerate()
+            .map(|(idx, block)| block_params(idx, block))
+            .collect::<Result<Vec<BlockParams>, AnalysisError>>()?;
+
+        let mut stack: Vec<usize> = Vec::new();
+        let mut constraints: Vec<Constraint> = Vec::new();
+        for (idx, p) in params.iter().enumerate() {
+            if p.is_push() {
+                stack.push(idx);
+                continue;
+            }
+            let earlier = stack
+                .pop()
+                .ok_or(AnalysisError::EmptyStack { block: idx })?;
+            constraints.push(Constraint {
+                earlier,
+                later: idx,
+                diff: params[earlier].offset + p.check,
+            });
+        }
+        if !stack.is_empty() {
+            return Err(AnalysisError::Unbalanced {
+                unmatched: stack.len(),
+            });
+        }
+        constraints.sort_by_key(|c| c.earlier);
+        Ok(Analysis {
+            params,
+            constraints,
+        })
+    }
+
+    pub fn digits(&self) -> usize {
+        self.params.len()
+    }
+
+    /// product of the choices of every digit pair
+    pub fn count(&self) -> u64 {
+        self.constraints
+            .iter()
+            .map(|c| c.choices() as u64)
+            .product()
+    }
+
+    /// Valid numbers in lexicographic order, which is the order of the earlier digits of each pair
+    pub fn numbers(&self, order: Order) -> impl Iterator<Item = Vec<u8>> + '_ {
+        self.constraints
+            .iter()
+            .map(|c| {
+                let mut earlier: Vec<Int> = c.earlier_digits().collect();
+                if order == Order::Descending {
+                    earlier.reverse();
+                }
+                earlier
+            })
+            .multi_cartesian_product()
+            .map(move |earlier| {
+                let mut digits = vec![0; self.digits()];
+                for (c, d) in self.constraints.iter().zip(earlier) {
+                    digits[c.earlier] = d as u8;
+                    digits[c.later] = (d + c.diff) as u8;
+                }
+                digits
+            })
+    }
+
+    /// None if some pair of digits can't satisfy its constraint
+    pub fn max_model_number(&self) -> Option<Vec<u8>> {
+        self.numbers(Order::Descending).next()
+    }
+
+    pub fn min_model_number(&self) -> Option<Vec<u8>> {
+        self.numbers(Order::Ascending).next()
+    }
+}
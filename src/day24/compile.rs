@@ -0,0 +1,282 @@
+//! Compiler from ALU programs to a register-array bytecode
+//!
+//! Registers whose values are known at compile time are folded away: `mul x 0` becomes a plain
+//! store, `add x 0`, `mul x 1` and `div x 1` disappear, operations on known values are computed
+//! right away and `add x z` on a zeroed x becomes a copy. Stores that nothing reads are dropped
+//! unless they can fail. Overflows are handled like in the ALU, by default they are errors.
+
+use super::alu::{AluError, Registers};
+use super::word::Overflow;
+use super::{Instruction, Int, Op, Value, Var};
+use std::fmt;
+
+/// register slot, see `Registers`
+type Reg = usize;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Code {
+    Inp(Reg),
+    Set(Reg, Int),
+    Copy(Reg, Reg),
+    Imm(Op, Reg, Int),
+    Reg(Op, Reg, Reg),
+}
+
+impl Code {
+    /// Division by zero and checked overflow fail at runtime, so such code is kept even if the
+    /// result is never read
+    fn can_fail(&self, overflow: Overflow) -> bool {
+        let checked = overflow == Overflow::Checked;
+        match self {
+            Code::Imm(op, _, n) => match op {
+                Op::Add => checked && *n != 0,
+                Op::Mul => checked && !matches!(n, 0 | 1),
+                // MIN / -1 overflows
+                Op::Div | Op::Mod => *n == 0 || (checked && *n == -1),
+                Op::Eql => false,
+            },
+            Code::Reg(op, _, _) => match op {
+                Op::Div | Op::Mod => true,
+                Op::Eql => false,
+                _ => checked,
+            },
+            _ => false,
+        }
+    }
+}
+
+/// What is known about the registers before the program starts, None stands for any value
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Known([Option<Int>; 4]);
+
+impl Known {
+    pub fn nothing() -> Known {
+        Known([None; 4])
+    }
+
+    pub fn zeros() -> Known {
+        Known([Some(0); 4])
+    }
+
+    pub fn with(mut self, var: Var, value: Option<Int>) -> Known {
+        self.0[Registers::slot(var)] = value;
+        self
+    }
+}
+
+/// Same result or error as the ALU with the given overflow
+fn apply(op: Op, n1: Int, n2: Int, pc: usize, overflow: Overflow) -> Result<Int, AluError> {
+    if n2 == 0 && matches!(op, Op::Div | Op::Mod) {
+        return Err(AluError::DivisionByZero { pc, op });
+    }
+    overflow
+        .apply(op, n1, n2)
+        .ok_or(AluError::Overflow { pc, op })
+}
+
+#[derive(Debug, Clone)]
+pub struct Compiled {
+    code: Vec<Code>,
+    /// index of the source instruction for each code, errors point there
+    source: Vec<usize>,
+    start: Known,
+    overflow: Overflow,
+}
+
+impl Compiled {
+    /// Running the result from registers that don't match `start` gives wrong results,
+    /// overflows are errors
+    pub fn new(program: &[Instruction], start: Known) -> Compiled {
+        Compiled::with_overflow(program, start, Overflow::default())
+    }
+
+    /// Constants are folded with the same overflow as the code runs with
+    pub fn with_overflow(program: &[Instruction], start: Known, overflow: Overflow) -> Compiled {
+        let mut code: Vec<(Code, usize)> = Vec::new();
+        let mut known = start.0;
+        for (pc, instruction) in program.iter().enumerate() {
+            let mut emit = |c: Code| code.push((c, pc));
+            let (op, dst, value) = match instruction {
+                Instruction::Inp(var) => {
+                    let dst = Registers::slot(*var);
+                    emit(Code::Inp(dst));
+                    known[dst] = None;
+                    continue;
+                }
+                Instruction::Op(op, var, value) => (*op, Registers::slot(*var), value),
+            };
+            let (src, src_value) = match value {
+                Value::Number(n) => (None, Some(*n)),
+                Value::Variable(var) => {
+                    let src = Registers::slot(*var);
+                    (Some(src), known[src])
+                }
+            };
+            known[dst] = match (known[dst], src_value, src) {
+                // both known, computed right away unless it fails at runtime
+                (Some(n1), Some(n2), _) => match apply(op, n1, n2, pc, overflow) {
+                    Ok(res) => {
+                        if known[dst] != Some(res) {
+                            emit(Code::Set(dst, res));
+                        }
+                        Some(res)
+                    }
+                    Err(_) => {
+                        emit(Code::Imm(op, dst, n2));
+                        None
+                    }
+                },
+                (_, Some(n), _) => match (op, n) {
+                    (Op::Add, 0) | (Op::Mul, 1) | (Op::Div, 1) => known[dst],
+                    (Op::Mul, 0) => {
+                        emit(Code::Set(dst, 0));
+                        Some(0)
+                    }
+                    _ => {
+                        emit(Code::Imm(op, dst, n));
+                        None
+                    }
+                },
+                (Some(n), None, Some(src)) => match (op, n) {
+                    (Op::Add, 0) | (Op::Mul, 1) => {
+                        emit(Code::Copy(dst, src));
+                        None
+                    }
+                    (Op::Mul, 0) => Some(0),
+                    _ => {
+                        emit(Code::Reg(op, dst, src));
+                        None
+                    }
+                },
+                (None, None, Some(src)) if src == dst && op == Op::Eql => {
+                    emit(Code::Set(dst, 1));
+                    Some(1)
+                }
+                (None, None, Some(src)) => {
+                    emit(Code::Reg(op, dst, src));
+                    None
+                }
+                (_, None, None) => unreachable!("numbers are always known"),
+            };
+        }
+
+        let code = eliminate_dead_stores(code, overflow);
+        Compiled {
+            source: code.iter().map(|(_, pc)| *pc).collect(),
+            code: code.into_iter().map(|(c, _)| c).collect(),
+            start,
+            overflow,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.code.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.code.is_empty()
+    }
+
+    pub fn run<I: IntoIterator<Item = Int>>(
+        &self,
+        registers: Registers,
+        input: I,
+    ) -> Result<Registers, AluError> {
+        let mut r = registers.slots();
+        debug_assert!(r
+            .iter()
+            .zip(self.start.0)
+            .all(|(value, known)| known.is_none_or(|k| k == *value)));
+        let mut input = input.into_iter();
+        for (idx, code) in self.code.iter().enumerate() {
+            match *code {
+                Code::Inp(dst) => {
+                    r[dst] = input.next().ok_or(AluError::InputExhausted {
+                        pc: self.source[idx],
+                    })?
+                }
+                Code::Set(dst, n) => r[dst] = n,
+                Code::Copy(dst, src) => r[dst] = r[src],
+                Code::Imm(op, dst, n) => {
+                    r[dst] = apply(op, r[dst], n, self.source[idx], self.overflow)?
+                }
+                Code::Reg(op, dst, src) => {
+                    r[dst] = apply(op, r[dst], r[src], self.source[idx], self.overflow)?
+                }
+            }
+        }
+        Ok(Registers::from_slots(r))
+    }
+}
+
+/// Backward liveness pass, every register is read at the end of the program
+fn eliminate_dead_stores(code: Vec<(Code, usize)>, overflow: Overflow) -> Vec<(Code, usize)> {
+    let mut live = [true; 4];
+    let mut res: Vec<(Code, usize)> = Vec::with_capacity(code.len());
+    for (c, pc) in code.into_iter().rev() {
+        let keep = match c {
+            Code::Inp(dst) => {
+                live[dst] = false;
+                true
+            }
+            Code::Set(dst, _) => std::mem::replace(&mut live[dst], false),
+            Code::Copy(dst, src) => {
+                let keep = std::mem::replace(&mut live[dst], false);
+                live[src] |= keep;
+                keep
+            }
+            Code::Imm(_, dst, _) => {
+                let keep = live[dst] || c.can_fail(overflow);
+                live[dst] |= keep;
+                keep
+            }
+            Code::Reg(_, dst, src) => {
+                let keep = live[dst] || c.can_fail(overflow);
+                live[dst] |= keep;
+                live[src] |= keep;
+                keep
+            }
+        };
+        if keep {
+            res.push((c, pc));
+        }
+    }
+    res.reverse();
+    res
+}
+
+/// Whole program started from zeroed registers, as `alu::run` does
+pub fn compile(program: &[Instruction]) -> Compiled {
+    Compiled::new(program, Known::zeros())
+}
+
+fn reg_name(reg: Reg) -> &'static str {
+    Registers::VARS[reg].name()
+}
+
+impl fmt::Display for Compiled {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (code, pc) in self.code.iter().zip(&self.source) {
+            match code {
+                Code::Inp(dst) => write!(f, "{:4}  inp {}", pc, reg_name(*dst))?,
+                Code::Set(dst, n) => write!(f, "{:4}  set {} {}", pc, reg_name(*dst), n)?,
+                Code::Copy(dst, src) => {
+                    write!(f, "{:4}  cpy {} {}", pc, reg_name(*dst), reg_name(*src))?
+                }
+                Code::Imm(op, dst, n) => {
+                    write!(f, "{:4}  {} {} {}", pc, op.name(), reg_name(*dst), n)?
+                }
+                Code::Reg(op, dst, src) => write!(
+                    f,
+                    "{:4}  {} {} {}",
+                    pc,
+                    op.name(),
+                    reg_name(*dst),
+                    reg_name(*src)
+                )?,
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
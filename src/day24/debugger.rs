@@ -0,0 +1,320 @@
+//! Step debugger over the ALU interpreter
+//!
+//! Breakpoints stop either before the instruction at some index or right after a register
+//! condition becomes true. The same operations are available as one-line commands, so a session
+//! can be scripted:
+//!
+//! ```text
+//! break z == 0
+//! break 18
+//! next
+//! step 3
+//! continue
+//! regs
+//! ```
+
+use super::alu::{Alu, AluError, Registers};
+use super::word::Overflow;
+use super::{Instruction, Int, Var};
+use crate::error::ParseError;
+use crate::utils::{expect_token, parse_number};
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Cmp {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+impl Cmp {
+    pub fn parse(s: &str) -> Option<Cmp> {
+        match s {
+            "==" => Some(Cmp::Eq),
+            "!=" => Some(Cmp::Ne),
+            "<" => Some(Cmp::Lt),
+            "<=" => Some(Cmp::Le),
+            ">" => Some(Cmp::Gt),
+            ">=" => Some(Cmp::Ge),
+            &_ => None,
+        }
+    }
+
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            Cmp::Eq => "==",
+            Cmp::Ne => "!=",
+            Cmp::Lt => "<",
+            Cmp::Le => "<=",
+            Cmp::Gt => ">",
+            Cmp::Ge => ">=",
+        }
+    }
+
+    pub fn holds(&self, n1: Int, n2: Int) -> bool {
+        match self {
+            Cmp::Eq => n1 == n2,
+            Cmp::Ne => n1 != n2,
+            Cmp::Lt => n1 < n2,
+            Cmp::Le => n1 <= n2,
+            Cmp::Gt => n1 > n2,
+            Cmp::Ge => n1 >= n2,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Breakpoint {
+    /// before executing the instruction with this index
+    Pc(usize),
+    /// after the instruction that makes the condition true
+    When(Var, Cmp, Int),
+}
+
+impl Breakpoint {
+    fn condition(&self, registers: &Registers) -> bool {
+        match self {
+            Breakpoint::Pc(_) => false,
+            Breakpoint::When(var, cmp, n) => cmp.holds(registers[*var], *n),
+        }
+    }
+}
+
+impl fmt::Display for Breakpoint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Breakpoint::Pc(pc) => write!(f, "at {}", pc),
+            Breakpoint::When(var, cmp, n) => {
+                write!(f, "when {} {} {}", var.name(), cmp.symbol(), n)
+            }
+        }
+    }
+}
+
+/// Why the debugger gave control back
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Stop {
+    /// single steps are done
+    Stepped,
+    /// the next instruction is inp
+    Input,
+    /// breakpoint by its id
+    Breakpoint(usize),
+    Halted,
+    /// the failing instruction is the next one, stepping again fails again
+    Failed(AluError),
+}
+
+impl fmt::Display for Stop {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Stop::Stepped => write!(f, "stepped"),
+            Stop::Input => write!(f, "waiting for input"),
+            Stop::Breakpoint(id) => write!(f, "breakpoint {}", id),
+            Stop::Halted => write!(f, "halted"),
+            Stop::Failed(e) => write!(f, "failed, {}", e),
+        }
+    }
+}
+
+pub struct Debugger<'a, I: Iterator<Item = Int>> {
+    program: &'a [Instruction],
+    alu: Alu<'a>,
+    input: I,
+    /// removed breakpoints leave a hole so that ids stay put
+    breakpoints: Vec<Option<Breakpoint>>,
+}
+
+impl<'a, I: Iterator<Item = Int>> Debugger<'a, I> {
+    /// all registers start at zero, overflows are errors
+    pub fn new<T: IntoIterator<IntoIter = I>>(
+        program: &'a [Instruction],
+        input: T,
+    ) -> Debugger<'a, I> {
+        Debugger {
+            program,
+            alu: Alu::new(program),
+            input: input.into_iter(),
+            breakpoints: Vec::new(),
+        }
+    }
+
+    pub fn with_overflow(mut self, overflow: Overflow) -> Debugger<'a, I> {
+        self.alu = self.alu.with_overflow(overflow);
+        self
+    }
+
+    pub fn registers(&self) -> Registers {
+        self.alu.registers()
+    }
+
+    /// index of the next instruction to execute
+    pub fn pc(&self) -> usize {
+        self.alu.pc()
+    }
+
+    /// the next instruction to execute, None once the program has halted
+    pub fn instruction(&self) -> Option<&'a Instruction> {
+        self.program.get(self.alu.pc())
+    }
+
+    /// Returns the id of the new breakpoint
+    pub fn add_breakpoint(&mut self, breakpoint: Breakpoint) -> usize {
+        self.breakpoints.push(Some(breakpoint));
+        self.breakpoints.len() - 1
+    }
+
+    /// false if there is no such breakpoint
+    pub fn remove_breakpoint(&mut self, id: usize) -> bool {
+        self.breakpoints
+            .get_mut(id)
+            .and_then(|breakpoint| breakpoint.take())
+            .is_some()
+    }
+
+    pub fn breakpoints(&self) -> impl Iterator<Item = (usize, Breakpoint)> + '_ {
+        self.breakpoints
+            .iter()
+            .enumerate()
+            .filter_map(|(id, breakpoint)| breakpoint.map(|b| (id, b)))
+    }
+
+    /// One instruction, None if nothing stopped it
+    fn advance(&mut self) -> Option<Stop> {
+        if self.alu.is_halted() {
+            return Some(Stop::Halted);
+        }
+        let before = self.alu.registers();
+        if let Err(e) = self.alu.step(&mut self.input) {
+            return Some(Stop::Failed(e));
+        }
+        let after = self.alu.registers();
+        let pc = self.alu.pc();
+        let hit = self.breakpoints().find(|(_, breakpoint)| match breakpoint {
+            Breakpoint::Pc(at) => *at == pc,
+            _ => breakpoint.condition(&after) && !breakpoint.condition(&before),
+        });
+        match hit {
+            Some((id, _)) => Some(Stop::Breakpoint(id)),
+            None if self.alu.is_halted() => Some(Stop::Halted),
+            None => None,
+        }
+    }
+
+    /// Executes up to `n` instructions
+    pub fn step(&mut self, n: usize) -> Stop {
+        for _ in 0..n {
+            if let Some(stop) = self.advance() {
+                return stop;
+            }
+        }
+        Stop::Stepped
+    }
+
+    /// Runs until the next instruction is inp
+    pub fn next_input(&mut self) -> Stop {
+        loop {
+            if let Some(stop) = self.advance() {
+                return stop;
+            }
+            if matches!(self.instruction(), Some(Instruction::Inp(_))) {
+                return Stop::Input;
+            }
+        }
+    }
+
+    /// Runs until a breakpoint, an error or the end of the program
+    pub fn resume(&mut self) -> Stop {
+        loop {
+            if let Some(stop) = self.advance() {
+                return stop;
+            }
+        }
+    }
+
+    /// The next instruction and the registers
+    pub fn dump(&self) -> String {
+        let next = match self.instruction() {
+            Some(instruction) => instruction.to_string(),
+            None => "end".to_string(),
+        };
+        format!("{:4} {:<12} {}", self.pc(), next, self.registers())
+    }
+
+    /// Runs a single command, returns what it has to say
+    ///
+    /// `step [N]`, `next`, `continue`, `break PC`, `break VAR CMP N`, `delete ID`, `breakpoints`
+    /// and `regs`, the first letter is enough for all but `breakpoints`
+    pub fn command(&mut self, line: &str) -> Result<String, ParseError> {
+        let mut tokens = line.split_whitespace();
+        let name = match tokens.next() {
+            Some(name) => name,
+            None => return Ok(String::new()),
+        };
+        let stop = match name {
+            "s" | "step" => {
+                let n = match tokens.next() {
+                    Some(token) => parse_number(line, token, "number of steps")?,
+                    None => 1,
+                };
+                self.step(n)
+            }
+            "n" | "next" => self.next_input(),
+            "c" | "continue" => self.resume(),
+            "r" | "regs" => return Ok(format!("{}\n", self.dump())),
+            "b" | "break" => {
+                let first = expect_token(line, tokens.next(), "breakpoint")?;
+                let breakpoint = match Var::parse(first) {
+                    Some(var) => {
+                        let token = expect_token(line, tokens.next(), "comparison")?;
+                        let cmp = Cmp::parse(token).ok_or_else(|| {
+                            ParseError::at(line, token, format!("unknown comparison '{}'", token))
+                        })?;
+                        let token = expect_token(line, tokens.next(), "value")?;
+                        Breakpoint::When(var, cmp, parse_number(line, token, "value")?)
+                    }
+                    None => Breakpoint::Pc(parse_number(line, first, "instruction index")?),
+                };
+                let id = self.add_breakpoint(breakpoint);
+                return Ok(format!("breakpoint {} {}\n", id, breakpoint));
+            }
+            "d" | "delete" => {
+                let token = expect_token(line, tokens.next(), "breakpoint id")?;
+                let id = parse_number(line, token, "breakpoint id")?;
+                if !self.remove_breakpoint(id) {
+                    return Err(ParseError::at(line, token, "no such breakpoint"));
+                }
+                return Ok(String::new());
+            }
+            "breakpoints" => {
+                return Ok(self
+                    .breakpoints()
+                    .map(|(id, breakpoint)| format!("breakpoint {} {}\n", id, breakpoint))
+                    .collect())
+            }
+            _ => {
+                return Err(ParseError::at(
+                    line,
+                    name,
+                    format!("unknown command '{}'", name),
+                ))
+            }
+        };
+        Ok(format!("{}\n{}\n", stop, self.dump()))
+    }
+
+    /// Commands one per line, `#` starts a comment
+    pub fn run_script(&mut self, script: &str) -> Result<String, ParseError> {
+        let mut output = String::new();
+        for line in script.lines() {
+            let command = line.split('#').next().unwrap();
+            output += &self
+                .command(command)
+                .map_err(|e| e.within(script, command))?;
+        }
+        Ok(output)
+    }
+}
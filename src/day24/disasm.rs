@@ -0,0 +1,116 @@
+//! Side by side listing of the code blocks of a program
+//!
+//! Blocks are aligned by the position of an instruction within its block, rows where the blocks
+//! don't agree are marked with `*` in the margin. In colored output the cells that differ from
+//! the most common instruction of their row are highlighted as well.
+
+use super::{code_blocks, CodeBlock, Instruction};
+use std::fmt;
+
+const HIGHLIGHT: &str = "\x1b[1;33m";
+const RESET: &str = "\x1b[0m";
+
+pub struct Disassembly<'a> {
+    blocks: Vec<&'a CodeBlock>,
+    /// blocks printed next to each other, the rest goes below
+    columns: usize,
+    color: bool,
+}
+
+impl<'a> Disassembly<'a> {
+    pub fn new(program: &'a [Instruction]) -> Disassembly<'a> {
+        let blocks = code_blocks(program);
+        Disassembly {
+            columns: blocks.len().max(1),
+            blocks,
+            color: false,
+        }
+    }
+
+    pub fn columns(mut self, columns: usize) -> Disassembly<'a> {
+        self.columns = columns.max(1);
+        self
+    }
+
+    /// ANSI escapes around differing cells
+    pub fn colored(mut self, color: bool) -> Disassembly<'a> {
+        self.color = color;
+        self
+    }
+
+    fn rows(&self) -> usize {
+        self.blocks
+            .iter()
+            .map(|block| block.len())
+            .max()
+            .unwrap_or(0)
+    }
+
+    fn cell(&self, block: usize, row: usize) -> Option<&'a Instruction> {
+        self.blocks[block].get(row)
+    }
+
+    /// the instruction most blocks have in this row, the earliest one on ties
+    fn common(&self, row: usize) -> Option<&'a Instruction> {
+        let cells: Vec<Option<&Instruction>> =
+            (0..self.blocks.len()).map(|b| self.cell(b, row)).collect();
+        let mut best = (0, None);
+        for cell in &cells {
+            let count = cells.iter().filter(|other| *other == cell).count();
+            if count > best.0 {
+                best = (count, *cell);
+            }
+        }
+        best.1
+    }
+
+    /// Rows where some block has a different instruction or none at all
+    pub fn differing_rows(&self) -> Vec<usize> {
+        (0..self.rows())
+            .filter(|row| (1..self.blocks.len()).any(|b| self.cell(b, *row) != self.cell(0, *row)))
+            .collect()
+    }
+}
+
+impl<'a> fmt::Display for Disassembly<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let width = self
+            .blocks
+            .iter()
+            .flat_map(|block| block.iter())
+            // one extra space so that the widest cells don't run into each other
+            .map(|instruction| instruction.to_string().len() + 1)
+            .max()
+            .unwrap_or(0)
+            .max("block 00".len());
+        let differing = self.differing_rows();
+        let first_blocks: Vec<usize> = (0..self.blocks.len()).step_by(self.columns).collect();
+        for (group, first) in first_blocks.iter().enumerate() {
+            if group > 0 {
+                writeln!(f)?;
+            }
+            let blocks = *first..(first + self.columns).min(self.blocks.len());
+            let mut header = format!("{:>5} |", "");
+            for b in blocks.clone() {
+                header += &format!(" {:<width$}", format!("block {}", b), width = width);
+            }
+            writeln!(f, "{}", header.trim_end())?;
+            for row in 0..self.rows() {
+                let marker = if differing.contains(&row) { '*' } else { ' ' };
+                let common = self.common(row);
+                let mut line = format!("{}{:>4} |", marker, row);
+                for b in blocks.clone() {
+                    let cell = self.cell(b, row);
+                    let text = cell.map(|i| i.to_string()).unwrap_or_default();
+                    if self.color && cell.is_some() && cell != common {
+                        line += &format!(" {}{:<width$}{}", HIGHLIGHT, text, RESET, width = width);
+                    } else {
+                        line += &format!(" {:<width$}", text, width = width);
+                    }
+                }
+                writeln!(f, "{}", line.trim_end())?;
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,391 @@
+//! Random ALU programs and a differential check of the ways to run them
+//!
+//! Every backend runs each prefix of a program on the same input, the first prefix whose
+//! results don't agree points at the instruction that one of the backends gets wrong.
+//! Generated programs keep their values small, bounds from the symbolic evaluator rule out
+//! overflows, so that any disagreement is a real bug. Backends that only run some programs,
+//! like the search that carries nothing but z between blocks, sit out the others.
+
+use super::alu::{self, AluError, Registers};
+use super::compile::{compile, Compiled, Known};
+use super::search::{carried_register, compile_blocks, execute_code_block};
+use super::symbolic::{self, Bounds, Expr};
+use super::word::Overflow;
+use super::{code_blocks, CodeBlock, Instruction, Int, Op, Program, Value, Var};
+use crate::utils::Rng;
+use std::fmt;
+use std::iter;
+
+impl Rng {
+    pub fn within(&mut self, bounds: Bounds) -> Int {
+        // zero for the full range of Int, any number will do then
+        let span = bounds.max.abs_diff(bounds.min).wrapping_add(1);
+        let offset = match span {
+            0 => self.next_u64(),
+            _ => self.next_u64() % span,
+        };
+        bounds.min.wrapping_add_unsigned(offset)
+    }
+}
+
+/// results of generated programs stay within this
+const LIMIT: Int = 1 << 40;
+
+/// attempts to find an instruction that keeps the values within `LIMIT`
+const ATTEMPTS: usize = 20;
+
+pub struct Generator {
+    /// number of inp instructions
+    pub inputs: usize,
+    /// number of instructions, inp included
+    pub len: usize,
+    pub digits: Bounds,
+    pub constants: Bounds,
+    /// each block writes w, x and y before reading them, as the search expects
+    pub reset: bool,
+}
+
+impl Generator {
+    pub fn new(inputs: usize, len: usize) -> Generator {
+        Generator {
+            inputs,
+            len: len.max(inputs),
+            digits: Bounds::DIGIT,
+            constants: Bounds::new(-30, 30),
+            reset: false,
+        }
+    }
+
+    pub fn input(&self, rng: &mut Rng) -> Vec<Int> {
+        (0..self.inputs).map(|_| rng.within(self.digits)).collect()
+    }
+
+    /// Starts with inp unless there are no inputs at all
+    pub fn program(&self, rng: &mut Rng) -> Program {
+        let zero = Expr::constant(0);
+        let mut values = [zero.clone(), zero.clone(), zero.clone(), zero];
+        let mut written = [false, false, false, true];
+        let mut inputs_left = self.inputs;
+        let mut program = Vec::with_capacity(self.len);
+        for idx in 0..self.len {
+            let var = Registers::VARS[rng.below(4)];
+            let slot = Registers::slot(var);
+            if inputs_left > 0 && (idx == 0 || rng.below(self.len - idx) < inputs_left) {
+                values[slot] = Expr::digit(self.inputs - inputs_left, self.digits);
+                inputs_left -= 1;
+                written = [false, false, false, true];
+                written[slot] = true;
+                program.push(Instruction::Inp(var));
+                continue;
+            }
+            if self.reset && !written[slot] {
+                values[slot] = Expr::constant(0);
+                written[slot] = true;
+                program.push(Instruction::Op(Op::Mul, var, Value::Number(0)));
+                continue;
+            }
+            let candidate = (0..ATTEMPTS).find_map(|_| {
+                let op = [Op::Add, Op::Mul, Op::Div, Op::Mod, Op::Eql][rng.below(5)];
+                let src = (rng.below(2) == 0)
+                    .then(|| rng.below(4))
+                    .filter(|src| !self.reset || written[*src]);
+                let (value, operand) = match src {
+                    Some(src) => (Value::Variable(Registers::VARS[src]), values[src].clone()),
+                    None => {
+                        let n = rng.within(self.constants);
+                        (Value::Number(n), Expr::constant(n))
+                    }
+                };
+                // about one division per program that can fail, most runs should get to the end
+                let risky = matches!(op, Op::Div | Op::Mod) && operand.bounds().contains(0);
+                if risky && rng.below(self.len) > 0 {
+                    return None;
+                }
+                let res = Expr::apply(op, &values[slot], &operand);
+                let bounds = res.bounds();
+                (bounds.min > -LIMIT && bounds.max < LIMIT).then_some((op, value, res))
+            });
+            let (op, value, res) =
+                candidate.unwrap_or((Op::Mul, Value::Number(0), Expr::constant(0)));
+            values[slot] = res;
+            program.push(Instruction::Op(op, var, value));
+        }
+        program
+    }
+}
+
+/// A way to run programs that should agree with the interpreter
+pub trait Backend {
+    fn name(&self) -> &str;
+
+    /// Whether the backend can run the program on this input at all
+    fn supports(&self, _program: &[Instruction], _input: &[Int]) -> bool {
+        true
+    }
+
+    fn run(&self, program: &[Instruction], input: &[Int]) -> Result<Registers, AluError>;
+}
+
+pub struct Interpreter;
+
+impl Backend for Interpreter {
+    fn name(&self) -> &str {
+        "interpreter"
+    }
+
+    fn run(&self, program: &[Instruction], input: &[Int]) -> Result<Registers, AluError> {
+        alu::run(program, input.iter().copied())
+    }
+}
+
+/// Compiled for registers known to start at zero, the most folding
+pub struct Compiler;
+
+impl Backend for Compiler {
+    fn name(&self) -> &str {
+        "compiled"
+    }
+
+    fn run(&self, program: &[Instruction], input: &[Int]) -> Result<Registers, AluError> {
+        compile(program).run(Registers::default(), input.iter().copied())
+    }
+}
+
+/// Compiled assuming nothing about the registers
+pub struct UnfoldedCompiler;
+
+impl Backend for UnfoldedCompiler {
+    fn name(&self) -> &str {
+        "compiled unfolded"
+    }
+
+    fn run(&self, program: &[Instruction], input: &[Int]) -> Result<Registers, AluError> {
+        Compiled::new(program, Known::nothing()).run(Registers::default(), input.iter().copied())
+    }
+}
+
+/// Expressions evaluated on the input, the digits must lie within the bounds
+pub struct Symbolic(pub Bounds);
+
+impl Backend for Symbolic {
+    fn name(&self) -> &str {
+        "symbolic"
+    }
+
+    fn run(&self, program: &[Instruction], input: &[Int]) -> Result<Registers, AluError> {
+        symbolic::evaluate(program, self.0).run(input)
+    }
+}
+
+/// Block by block the way the search runs them, only z is carried from one block to the next
+pub struct SearchExecutor;
+
+impl Backend for SearchExecutor {
+    fn name(&self) -> &str {
+        "search"
+    }
+
+    /// Programs that start with inp and don't need w, x or y from the block before, the last
+    /// block has to write again the ones written before it as they'd be zero otherwise
+    fn supports(&self, program: &[Instruction], input: &[Int]) -> bool {
+        let blocks = code_blocks(program);
+        let Some((last, before)) = blocks.split_last() else {
+            return false;
+        };
+        let written = |block: &CodeBlock| {
+            let mut written = [false; 4];
+            for instruction in block {
+                let (Instruction::Inp(var) | Instruction::Op(_, var, _)) = instruction;
+                written[Registers::slot(*var)] = true;
+            }
+            written
+        };
+        let last = written(last);
+        matches!(program[0], Instruction::Inp(_))
+            && blocks.iter().all(|block| carried_register(block).is_none())
+            && before.iter().all(|block| {
+                let before = written(block);
+                Registers::VARS[..3]
+                    .iter()
+                    .all(|var| last[Registers::slot(*var)] || !before[Registers::slot(*var)])
+            })
+            && input.iter().all(|d| u8::try_from(*d).is_ok())
+    }
+
+    fn run(&self, program: &[Instruction], input: &[Int]) -> Result<Registers, AluError> {
+        let blocks = code_blocks(program);
+        let compiled = compile_blocks(&blocks, Overflow::default());
+        let mut z = 0;
+        let mut start = 0;
+        for (idx, block) in compiled.iter().enumerate() {
+            let digit = *input
+                .get(idx)
+                .ok_or(AluError::InputExhausted { pc: start })?;
+            if idx + 1 < compiled.len() {
+                let next =
+                    execute_code_block(block, z, digit as u8).map_err(|e| shifted(e, start))?;
+                if let Some(next) = next {
+                    z = next;
+                    start += blocks[idx].len();
+                    continue;
+                }
+            }
+            // the last block for all of the registers, a failed one again for its error
+            return block
+                .run(Registers::default().with(Var::Z, z), iter::once(digit))
+                .map_err(|e| shifted(e, start));
+        }
+        Ok(Registers::default())
+    }
+}
+
+/// The error of a block that starts at `start` with the pc of the whole program
+fn shifted(error: AluError, start: usize) -> AluError {
+    match error {
+        AluError::DivisionByZero { pc, op } => AluError::DivisionByZero { pc: pc + start, op },
+        AluError::InputExhausted { pc } => AluError::InputExhausted { pc: pc + start },
+        AluError::Overflow { pc, op } => AluError::Overflow { pc: pc + start, op },
+    }
+}
+
+/// The interpreter on i32 registers, for programs whose values fit into them when the digits
+/// lie within the bounds
+pub struct Narrow(pub Bounds);
+
+impl Narrow {
+    const BOUNDS: Bounds = Bounds {
+        min: i32::MIN as Int,
+        max: i32::MAX as Int,
+    };
+
+    fn fits(bounds: Bounds) -> bool {
+        Narrow::BOUNDS.contains(bounds.min) && Narrow::BOUNDS.contains(bounds.max)
+    }
+}
+
+impl Backend for Narrow {
+    fn name(&self) -> &str {
+        "interpreter i32"
+    }
+
+    /// Bounds of every value along the way, not just the final ones, an overflow anywhere
+    /// would stop the run
+    fn supports(&self, program: &[Instruction], input: &[Int]) -> bool {
+        if !input.iter().all(|d| self.0.contains(*d)) || !Narrow::fits(self.0) {
+            return false;
+        }
+        let zero = Expr::constant(0);
+        let mut values = [zero.clone(), zero.clone(), zero.clone(), zero];
+        let mut inputs = 0;
+        program.iter().all(|instruction| {
+            let var = match instruction {
+                Instruction::Inp(var) => {
+                    values[Registers::slot(*var)] = Expr::digit(inputs, self.0);
+                    inputs += 1;
+                    var
+                }
+                Instruction::Op(op, var, value) => {
+                    let operand = match value {
+                        Value::Number(n) => Expr::constant(*n),
+                        Value::Variable(src) => values[Registers::slot(*src)].clone(),
+                    };
+                    if !Narrow::fits(operand.bounds()) {
+                        return false;
+                    }
+                    let slot = Registers::slot(*var);
+                    values[slot] = Expr::apply(*op, &values[slot], &operand);
+                    var
+                }
+            };
+            Narrow::fits(values[Registers::slot(*var)].bounds())
+        })
+    }
+
+    fn run(&self, program: &[Instruction], input: &[Int]) -> Result<Registers, AluError> {
+        let registers = alu::run_with::<i32, _>(
+            program,
+            input.iter().map(|d| *d as i32),
+            Overflow::default(),
+        )?;
+        Ok(Registers::from_slots(registers.slots().map(Int::from)))
+    }
+}
+
+/// Every backend there is, symbolic evaluation and narrow registers assuming the given digits
+pub fn backends(digits: Bounds) -> Vec<Box<dyn Backend>> {
+    vec![
+        Box::new(Interpreter),
+        Box::new(Compiler),
+        Box::new(UnfoldedCompiler),
+        Box::new(Symbolic(digits)),
+        Box::new(SearchExecutor),
+        Box::new(Narrow(digits)),
+    ]
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Divergence {
+    /// index of the first instruction after which the backends disagree
+    pub pc: usize,
+    pub instruction: Instruction,
+    /// backend names with their results
+    pub results: Vec<(String, Result<Registers, AluError>)>,
+}
+
+impl fmt::Display for Divergence {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(
+            f,
+            "backends disagree after {}: {}",
+            self.pc, self.instruction
+        )?;
+        for (name, result) in &self.results {
+            match result {
+                Ok(registers) => writeln!(f, "    {:<20} {}", name, registers)?,
+                Err(e) => writeln!(f, "    {:<20} {}", name, e)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The first instruction the backends disagree on, None if they agree on the whole program
+///
+/// Backends that don't support a prefix of the program are left out for that prefix.
+pub fn differential(
+    backends: &[Box<dyn Backend>],
+    program: &[Instruction],
+    input: &[Int],
+) -> Option<Divergence> {
+    (1..=program.len()).find_map(|len| {
+        let prefix = &program[..len];
+        let results: Vec<(String, Result<Registers, AluError>)> = backends
+            .iter()
+            .filter(|backend| backend.supports(prefix, input))
+            .map(|backend| (backend.name().to_string(), backend.run(prefix, input)))
+            .collect();
+        if results.windows(2).all(|pair| pair[0].1 == pair[1].1) {
+            return None;
+        }
+        Some(Divergence {
+            pc: len - 1,
+            instruction: program[len - 1].clone(),
+            results,
+        })
+    })
+}
+
+/// Random programs until the backends disagree on one, returns it with the input and divergence
+pub fn fuzz(
+    backends: &[Box<dyn Backend>],
+    generator: &Generator,
+    seed: u64,
+    cases: usize,
+) -> Option<(Program, Vec<Int>, Divergence)> {
+    let mut rng = Rng::new(seed);
+    (0..cases).find_map(|_| {
+        let program = generator.program(&mut rng);
+        let input = generator.input(&mut rng);
+        differential(backends, &program, &input).map(|divergence| (program, input, divergence))
+    })
+}
@@ -0,0 +1,405 @@
+//! Search over digits running one code block per digit on the ALU
+//!
+//! Registers other than z are expected to be reset by each block, as they are in MONAD, so
+//! (digit index, z before the block) is all the state there is. `carried_register` finds blocks
+//! that don't reset them. A block crashing on a division by zero only rules out the digit, an
+//! overflow stops the search as the answer would depend on it.
+
+use super::alu::{AluError, Registers};
+use super::compile::{Compiled, Known};
+use super::word::Overflow;
+use super::{CodeBlock, Instruction, Int, Op, Order, Value, Var};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt;
+use std::iter;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+
+/// First register other than z the block reads before writing it, with the index of the
+/// reading instruction
+///
+/// The search starts each block with w, x and y at zero, which only holds for blocks where this
+/// is None. `mul v 0` counts as a write, MONAD resets its registers that way.
+pub fn carried_register(block: &CodeBlock) -> Option<(usize, Var)> {
+    let mut written = [false; 4];
+    written[Registers::slot(Var::Z)] = true;
+    for (pc, instruction) in block.iter().enumerate() {
+        let (dst, reads) = match instruction {
+            Instruction::Inp(var) => (*var, None),
+            Instruction::Op(Op::Mul, var, Value::Number(0)) => (*var, None),
+            Instruction::Op(_, var, Value::Number(_)) => (*var, Some([*var, *var])),
+            Instruction::Op(_, var, Value::Variable(src)) => (*var, Some([*var, *src])),
+        };
+        if let Some(var) = reads
+            .into_iter()
+            .flatten()
+            .find(|var| !written[Registers::slot(*var)])
+        {
+            return Some((pc, var));
+        }
+        written[Registers::slot(dst)] = true;
+    }
+    None
+}
+
+/// Each block compiled for zeroed w, x and y and any z
+pub fn compile_blocks(blocks: &[&CodeBlock], overflow: Overflow) -> Vec<Compiled> {
+    let start = Known::zeros().with(Var::Z, None);
+    blocks
+        .iter()
+        .map(|block| Compiled::with_overflow(block, start, overflow))
+        .collect()
+}
+
+/// The block with this index overflowed, `error` points into the block
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SearchError {
+    pub block: usize,
+    pub error: AluError,
+}
+
+impl fmt::Display for SearchError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "block {}, {}", self.block, self.error)
+    }
+}
+
+impl std::error::Error for SearchError {}
+
+/// z after running the block with the digit as its only input, None if the ALU fails on it
+/// in any other way than an overflow
+pub fn execute_code_block(
+    block: &Compiled,
+    z_init: Int,
+    digit: u8,
+) -> Result<Option<Int>, AluError> {
+    match block.run(
+        Registers::default().with(Var::Z, z_init),
+        iter::once(digit as Int),
+    ) {
+        Ok(registers) => Ok(Some(registers[Var::Z])),
+        Err(e @ AluError::Overflow { .. }) => Err(e),
+        Err(_) => Ok(None),
+    }
+}
+
+fn execute(blocks: &[Compiled], idx: usize, z: Int, digit: u8) -> Result<Option<Int>, SearchError> {
+    execute_code_block(&blocks[idx], z, digit).map_err(|error| SearchError { block: idx, error })
+}
+
+struct Frame {
+    /// z before the block of this frame's digit
+    z: Int,
+    /// position of the next digit to try in the search order
+    next: usize,
+    /// some valid number has been found below this frame
+    found: bool,
+}
+
+/// Lazy depth-first enumeration of valid model numbers in the given order, ends after an error
+pub struct ValidNumbers<'a> {
+    blocks: &'a [Compiled],
+    digits: [u8; 9],
+    /// states that can't lead to z = 0 at the end
+    dead_states: HashSet<(usize, Int)>,
+    stack: Vec<Frame>,
+    prefix: Vec<u8>,
+}
+
+impl<'a> ValidNumbers<'a> {
+    pub fn new(blocks: &'a [Compiled], order: Order) -> ValidNumbers<'a> {
+        let stack = if blocks.is_empty() {
+            Vec::new()
+        } else {
+            vec![Frame {
+                z: 0,
+                next: 0,
+                found: false,
+            }]
+        };
+        ValidNumbers {
+            blocks,
+            digits: order.digits(),
+            dead_states: HashSet::new(),
+            stack,
+            prefix: Vec::new(),
+        }
+    }
+
+    pub fn dead_states(&self) -> usize {
+        self.dead_states.len()
+    }
+}
+
+impl<'a> Iterator for ValidNumbers<'a> {
+    type Item = Result<Vec<u8>, SearchError>;
+
+    fn next(&mut self) -> Option<Result<Vec<u8>, SearchError>> {
+        while !self.stack.is_empty() {
+            let idx = self.stack.len() - 1;
+            let frame = &mut self.stack[idx];
+            if frame.next == self.digits.len() {
+                let frame = self.stack.pop().unwrap();
+                if frame.found {
+                    if let Some(parent) = self.stack.last_mut() {
+                        parent.found = true;
+                    }
+                } else {
+                    self.dead_states.insert((idx, frame.z));
+                }
+                self.prefix.pop();
+                continue;
+            }
+            let digit = self.digits[frame.next];
+            frame.next += 1;
+            let next_z = match execute(self.blocks, idx, frame.z, digit) {
+                Ok(Some(z)) => z,
+                Ok(None) => continue,
+                Err(e) => {
+                    self.stack.clear();
+                    return Some(Err(e));
+                }
+            };
+            if idx == self.blocks.len() - 1 {
+                if next_z == 0 {
+                    frame.found = true;
+                    let mut number = self.prefix.clone();
+                    number.push(digit);
+                    return Some(Ok(number));
+                }
+            } else if !self.dead_states.contains(&(idx + 1, next_z)) {
+                self.prefix.push(digit);
+                self.stack.push(Frame {
+                    z: next_z,
+                    next: 0,
+                    found: false,
+                });
+            }
+        }
+        None
+    }
+}
+
+/// States that can't lead to z = 0, shared between threads
+///
+/// Split into shards so that threads rarely wait on each other's lookups.
+pub struct DeadStates {
+    shards: Vec<Mutex<HashSet<(usize, Int)>>>,
+}
+
+impl DeadStates {
+    const SHARDS: usize = 64;
+
+    pub fn new() -> DeadStates {
+        DeadStates {
+            shards: (0..DeadStates::SHARDS)
+                .map(|_| Mutex::new(HashSet::new()))
+                .collect(),
+        }
+    }
+
+    fn shard(&self, state: &(usize, Int)) -> &Mutex<HashSet<(usize, Int)>> {
+        let hash = (state.1 as u64 ^ state.0 as u64).wrapping_mul(0x9e37_79b9_7f4a_7c15);
+        &self.shards[(hash >> 58) as usize % DeadStates::SHARDS]
+    }
+
+    pub fn contains(&self, state: &(usize, Int)) -> bool {
+        self.shard(state).lock().unwrap().contains(state)
+    }
+
+    pub fn insert(&self, state: (usize, Int)) {
+        self.shard(&state).lock().unwrap().insert(state);
+    }
+
+    pub fn len(&self) -> usize {
+        self.shards
+            .iter()
+            .map(|shard| shard.lock().unwrap().len())
+            .sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl Default for DeadStates {
+    fn default() -> DeadStates {
+        DeadStates::new()
+    }
+}
+
+enum Outcome {
+    Found,
+    Dead,
+    Failed(SearchError),
+    /// a task earlier in the search order has found a number, nothing is known about the state
+    Cancelled,
+}
+
+/// Depth-first search for the first valid number below the state, its digits are pushed to `number`
+fn search_from(
+    blocks: &[Compiled],
+    digits: &[u8; 9],
+    (idx, z): (usize, Int),
+    dead_states: &DeadStates,
+    cancelled: &dyn Fn() -> bool,
+    number: &mut Vec<u8>,
+) -> Outcome {
+    if idx == blocks.len() {
+        return if z == 0 {
+            Outcome::Found
+        } else {
+            Outcome::Dead
+        };
+    }
+    if dead_states.contains(&(idx, z)) {
+        return Outcome::Dead;
+    }
+    if cancelled() {
+        return Outcome::Cancelled;
+    }
+    for digit in digits {
+        let next_z = match execute(blocks, idx, z, *digit) {
+            Ok(Some(z)) => z,
+            Ok(None) => continue,
+            Err(e) => return Outcome::Failed(e),
+        };
+        number.push(*digit);
+        match search_from(
+            blocks,
+            digits,
+            (idx + 1, next_z),
+            dead_states,
+            cancelled,
+            number,
+        ) {
+            Outcome::Dead => {
+                number.pop();
+            }
+            outcome => return outcome,
+        }
+    }
+    dead_states.insert((idx, z));
+    Outcome::Dead
+}
+
+/// digits so far and z after them
+type Task = (Vec<u8>, Result<Int, SearchError>);
+
+/// States after the first `depth` digits in the search order, with the digits leading there
+///
+/// A prefix that fails is kept in its place, so that the error comes up in the search order.
+fn prefixes(blocks: &[Compiled], digits: &[u8; 9], depth: usize) -> Vec<Task> {
+    let mut tasks: Vec<Task> = vec![(Vec::new(), Ok(0))];
+    for idx in 0..depth {
+        tasks = tasks
+            .into_iter()
+            .flat_map(|(prefix, z)| -> Vec<Task> {
+                let z = match z {
+                    Ok(z) => z,
+                    Err(e) => return vec![(prefix, Err(e))],
+                };
+                digits
+                    .iter()
+                    .filter_map(|digit| {
+                        let next_z = execute(blocks, idx, z, *digit).transpose()?;
+                        let mut prefix = prefix.clone();
+                        prefix.push(*digit);
+                        Some((prefix, next_z))
+                    })
+                    .collect()
+            })
+            .collect();
+    }
+    tasks
+}
+
+/// First valid number in the given order, searched on `threads` threads
+///
+/// The first digits are split into tasks taken by the threads in the search order, all threads
+/// share the dead states. Once some task finds a number or fails the later ones are abandoned,
+/// the earlier ones still have to finish as they may hold a number that comes first. Returns the
+/// number of dead states along with the result.
+pub fn par_first_valid(
+    blocks: &[Compiled],
+    order: Order,
+    threads: usize,
+) -> (Result<Option<Vec<u8>>, SearchError>, usize) {
+    if blocks.is_empty() {
+        return (Ok(None), 0);
+    }
+    let digits = order.digits();
+    // a couple of digits give enough tasks to keep the threads busy till the end
+    let tasks = prefixes(blocks, &digits, (blocks.len() - 1).min(2));
+    let dead_states = DeadStates::new();
+    let next_task = AtomicUsize::new(0);
+    // index of the earliest task that has found a number or failed
+    let best = AtomicUsize::new(usize::MAX);
+    let found: Mutex<BTreeMap<usize, Result<Vec<u8>, SearchError>>> = Mutex::new(BTreeMap::new());
+
+    thread::scope(|scope| {
+        for _ in 0..threads.max(1) {
+            scope.spawn(|| loop {
+                let task = next_task.fetch_add(1, Ordering::Relaxed);
+                if task >= tasks.len() || task > best.load(Ordering::Relaxed) {
+                    break;
+                }
+                let (prefix, z) = &tasks[task];
+                let cancelled = || best.load(Ordering::Relaxed) < task;
+                let mut number = prefix.clone();
+                let outcome = match z {
+                    Ok(z) => search_from(
+                        blocks,
+                        &digits,
+                        (prefix.len(), *z),
+                        &dead_states,
+                        &cancelled,
+                        &mut number,
+                    ),
+                    Err(e) => Outcome::Failed(e.clone()),
+                };
+                let result = match outcome {
+                    Outcome::Found => Ok(number),
+                    Outcome::Failed(e) => Err(e),
+                    Outcome::Dead | Outcome::Cancelled => continue,
+                };
+                best.fetch_min(task, Ordering::Relaxed);
+                found.lock().unwrap().insert(task, result);
+            });
+        }
+    });
+
+    let first = found.into_inner().unwrap().into_values().next().transpose();
+    (first, dead_states.len())
+}
+
+/// Number of valid model numbers, memoized on the state
+pub fn count_valid(blocks: &[Compiled]) -> Result<u64, SearchError> {
+    fn count(
+        blocks: &[Compiled],
+        idx: usize,
+        z: Int,
+        memo: &mut HashMap<(usize, Int), u64>,
+    ) -> Result<u64, SearchError> {
+        if idx == blocks.len() {
+            return Ok((z == 0) as u64);
+        }
+        if let Some(n) = memo.get(&(idx, z)) {
+            return Ok(*n);
+        }
+        let mut n = 0;
+        for digit in 1..=9 {
+            if let Some(next_z) = execute(blocks, idx, z, digit)? {
+                n += count(blocks, idx + 1, next_z, memo)?;
+            }
+        }
+        memo.insert((idx, z), n);
+        Ok(n)
+    }
+    if blocks.is_empty() {
+        return Ok(0);
+    }
+    count(blocks, 0, 0, &mut HashMap::new())
+}
@@ -0,0 +1,470 @@
+//! Symbolic evaluation of ALU programs
+//!
+//! Every register is tracked as an expression over the input digits, each node knowing the
+//! interval its value lies in. The bounds drive simplification: `eql` of operands with disjoint
+//! ranges is 0, `mod` of a value already below the modulus is the value itself and so on.
+//! For MONAD this folds the push blocks to `z * 26 + d + offset` and leaves `eql` only in pops.
+//! Each operation keeps the overflow it was evaluated with, wrapping ones that may overflow
+//! can be anything.
+
+use super::alu::{AluError, Registers};
+use super::word::Overflow;
+use super::{Instruction, Int, Op, Value, Var};
+use std::collections::HashMap;
+use std::fmt::{self, Write};
+use std::ops::Index;
+use std::rc::Rc;
+
+/// Inclusive range of values
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Bounds {
+    pub min: Int,
+    pub max: Int,
+}
+
+impl Bounds {
+    pub const ANY: Bounds = Bounds {
+        min: Int::MIN,
+        max: Int::MAX,
+    };
+    pub const DIGIT: Bounds = Bounds { min: 1, max: 9 };
+    const BOOL: Bounds = Bounds { min: 0, max: 1 };
+
+    pub fn new(min: Int, max: Int) -> Bounds {
+        Bounds { min, max }
+    }
+
+    pub fn exact(n: Int) -> Bounds {
+        Bounds { min: n, max: n }
+    }
+
+    pub fn contains(&self, n: Int) -> bool {
+        self.min <= n && n <= self.max
+    }
+
+    fn is_disjoint(&self, other: &Bounds) -> bool {
+        self.max < other.min || other.max < self.min
+    }
+
+    /// smallest range containing all of the values
+    fn spanning(values: &[Int]) -> Bounds {
+        Bounds {
+            min: *values.iter().min().unwrap(),
+            max: *values.iter().max().unwrap(),
+        }
+    }
+
+    fn of(op: Op, b1: Bounds, b2: Bounds) -> Bounds {
+        match op {
+            Op::Add => Bounds::new(b1.min.saturating_add(b2.min), b1.max.saturating_add(b2.max)),
+            Op::Mul => Bounds::spanning(&[
+                b1.min.saturating_mul(b2.min),
+                b1.min.saturating_mul(b2.max),
+                b1.max.saturating_mul(b2.min),
+                b1.max.saturating_mul(b2.max),
+            ]),
+            // truncating division is monotone in both operands while the divisor keeps its sign
+            Op::Div if b2.min > 0 || b2.max < 0 => Bounds::spanning(&[
+                b1.min.saturating_div(b2.min),
+                b1.min.saturating_div(b2.max),
+                b1.max.saturating_div(b2.min),
+                b1.max.saturating_div(b2.max),
+            ]),
+            // the quotient is never further from zero than the dividend
+            Op::Div => {
+                let limit = b1.min.saturating_abs().max(b1.max.saturating_abs());
+                Bounds::new(-limit, limit)
+            }
+            // the remainder keeps the sign of the dividend and is smaller than the divisor
+            Op::Mod => {
+                let limit = b2.min.saturating_abs().max(b2.max.saturating_abs()) - 1;
+                Bounds::new(b1.min.max(-limit).min(0), b1.max.min(limit).max(0))
+            }
+            Op::Eql => Bounds::BOOL,
+        }
+    }
+
+    /// Some operands within the bounds give a result that doesn't fit into `Int`
+    fn may_overflow(op: Op, b1: Bounds, b2: Bounds) -> bool {
+        match op {
+            Op::Add => b1.min.checked_add(b2.min).is_none() || b1.max.checked_add(b2.max).is_none(),
+            Op::Mul => [b1.min, b1.max].iter().any(|n1| {
+                [b2.min, b2.max]
+                    .iter()
+                    .any(|n2| n1.checked_mul(*n2).is_none())
+            }),
+            Op::Div | Op::Mod => b1.contains(Int::MIN) && b2.contains(-1),
+            Op::Eql => false,
+        }
+    }
+}
+
+impl fmt::Display for Bounds {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "[{}, {}]", self.min, self.max)
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum Node {
+    Const(Int),
+    /// input digit by its 0-based position
+    Digit(usize),
+    Op(Op, Overflow, Expr, Expr),
+}
+
+/// Immutable expression, clones share the tree
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Expr(Rc<(Node, Bounds)>);
+
+impl Expr {
+    fn new(node: Node, bounds: Bounds) -> Expr {
+        Expr(Rc::new((node, bounds)))
+    }
+
+    pub fn constant(n: Int) -> Expr {
+        Expr::new(Node::Const(n), Bounds::exact(n))
+    }
+
+    pub fn digit(idx: usize, bounds: Bounds) -> Expr {
+        Expr::new(Node::Digit(idx), bounds)
+    }
+
+    fn node(&self) -> &Node {
+        &self.0 .0
+    }
+
+    pub fn bounds(&self) -> Bounds {
+        self.0 .1
+    }
+
+    pub fn as_const(&self) -> Option<Int> {
+        match self.node() {
+            Node::Const(n) => Some(*n),
+            _ if self.bounds().min == self.bounds().max => Some(self.bounds().min),
+            _ => None,
+        }
+    }
+
+    /// `op e1 e2` simplified as far as the operands and their bounds allow, overflows are errors
+    pub fn apply(op: Op, e1: &Expr, e2: &Expr) -> Expr {
+        Expr::apply_with_overflow(op, e1, e2, Overflow::default())
+    }
+
+    pub fn apply_with_overflow(op: Op, e1: &Expr, e2: &Expr, overflow: Overflow) -> Expr {
+        let (b1, b2) = (e1.bounds(), e2.bounds());
+        match (op, e1.as_const(), e2.as_const()) {
+            // division by zero is left for the runtime to fail on
+            (Op::Div | Op::Mod, _, Some(0)) => {}
+            // checked overflows stay symbolic too
+            (_, Some(n1), Some(n2)) => {
+                if let Some(n) = overflow.apply(op, n1, n2) {
+                    return Expr::constant(n);
+                }
+            }
+            (Op::Add, Some(0), _) | (Op::Mul, Some(1), _) => return e2.clone(),
+            (Op::Add, _, Some(0)) | (Op::Mul, _, Some(1)) | (Op::Div, _, Some(1)) => {
+                return e1.clone()
+            }
+            (Op::Mul, Some(0), _) | (Op::Mul, _, Some(0)) => return Expr::constant(0),
+            _ => {}
+        }
+        match op {
+            Op::Eql if b1.is_disjoint(&b2) => return Expr::constant(0),
+            Op::Eql if e1 == e2 => return Expr::constant(1),
+            // non-negative and below the modulus
+            Op::Mod if b1.min >= 0 && (b2.min > b1.max || b2.max.saturating_neg() > b1.max) => {
+                return e1.clone()
+            }
+            // |dividend| below |divisor| everywhere
+            Op::Div
+                if (b2.min > 0 || b2.max < 0)
+                    && b1.min.saturating_abs().max(b1.max.saturating_abs())
+                        < b2.min.saturating_abs().min(b2.max.saturating_abs()) =>
+            {
+                return Expr::constant(0)
+            }
+            _ => {}
+        }
+        if let Node::Op(Op::Add, inner_overflow, inner1, inner2) = e1.node() {
+            // (a + c1) + c2 = a + (c1 + c2), saturation doesn't regroup like that
+            if let (Some(c1), Some(c2), Op::Add) = (inner2.as_const(), e2.as_const(), op) {
+                if *inner_overflow == overflow && overflow != Overflow::Saturating {
+                    if let Some(c) = overflow.apply(Op::Add, c1, c2) {
+                        return Expr::apply_with_overflow(
+                            Op::Add,
+                            inner1,
+                            &Expr::constant(c),
+                            overflow,
+                        );
+                    }
+                }
+            }
+        }
+        let bounds = if overflow == Overflow::Wrapping && Bounds::may_overflow(op, b1, b2) {
+            Bounds::ANY
+        } else {
+            Bounds::of(op, b1, b2)
+        };
+        Expr::new(Node::Op(op, overflow, e1.clone(), e2.clone()), bounds)
+    }
+
+    /// Value for the given digits, None if some division by zero or overflow happens on the way
+    pub fn eval(&self, digits: &[Int]) -> Option<Int> {
+        fn eval_memo(
+            e: &Expr,
+            digits: &[Int],
+            memo: &mut HashMap<*const (Node, Bounds), Option<Int>>,
+        ) -> Option<Int> {
+            if let Some(n) = memo.get(&Rc::as_ptr(&e.0)) {
+                return *n;
+            }
+            let res = match e.node() {
+                Node::Const(n) => Some(*n),
+                Node::Digit(idx) => digits.get(*idx).copied(),
+                Node::Op(op, overflow, e1, e2) => {
+                    let n1 = eval_memo(e1, digits, memo)?;
+                    let n2 = eval_memo(e2, digits, memo)?;
+                    if n2 == 0 && matches!(op, Op::Div | Op::Mod) {
+                        None
+                    } else {
+                        overflow.apply(*op, n1, n2)
+                    }
+                }
+            };
+            memo.insert(Rc::as_ptr(&e.0), res);
+            res
+        }
+        eval_memo(self, digits, &mut HashMap::new())
+    }
+
+    /// Subexpressions used more than once are written out as t0, t1, ... before `name = ...`
+    pub fn formula(&self, name: &str) -> String {
+        let mut uses: HashMap<*const (Node, Bounds), usize> = HashMap::new();
+        count_uses(self, &mut uses);
+        let mut printer = Printer {
+            uses,
+            names: HashMap::new(),
+            lines: String::new(),
+        };
+        let body = printer.write(self, 0);
+        let _ = writeln!(printer.lines, "{} = {}", name, body);
+        printer.lines
+    }
+}
+
+fn count_uses(e: &Expr, uses: &mut HashMap<*const (Node, Bounds), usize>) {
+    let count = uses.entry(Rc::as_ptr(&e.0)).or_insert(0);
+    *count += 1;
+    if *count > 1 {
+        return;
+    }
+    if let Node::Op(_, _, e1, e2) = e.node() {
+        count_uses(e1, uses);
+        count_uses(e2, uses);
+    }
+}
+
+fn precedence(op: Op) -> u8 {
+    match op {
+        Op::Eql => 0,
+        Op::Add => 1,
+        Op::Mul | Op::Div | Op::Mod => 2,
+    }
+}
+
+struct Printer {
+    uses: HashMap<*const (Node, Bounds), usize>,
+    names: HashMap<*const (Node, Bounds), String>,
+    lines: String,
+}
+
+impl Printer {
+    /// text of the expression, parenthesized if its operator binds weaker than `min_precedence`
+    fn write(&mut self, e: &Expr, min_precedence: u8) -> String {
+        let key = Rc::as_ptr(&e.0);
+        if let Some(name) = self.names.get(&key) {
+            return name.clone();
+        }
+        let (op, e1, e2) = match e.node() {
+            Node::Const(n) => return n.to_string(),
+            Node::Digit(idx) => return format!("d{}", idx),
+            Node::Op(op, _, e1, e2) => (*op, e1, e2),
+        };
+        let text = match op {
+            Op::Eql => format!("[{} == {}]", self.write(e1, 0), self.write(e2, 0)),
+            _ => {
+                let symbol = match op {
+                    Op::Add => "+",
+                    Op::Mul => "*",
+                    Op::Div => "/",
+                    _ => "%",
+                };
+                let p = precedence(op);
+                // a - (b + c) isn't a thing here, but a / (b * c) is
+                let left = self.write(e1, p);
+                let right = self.write(e2, if op == Op::Add { p } else { p + 1 });
+                format!("{} {} {}", left, symbol, right)
+            }
+        };
+        if self.uses.get(&key).is_some_and(|n| *n > 1) {
+            let name = format!("t{}", self.names.len());
+            let _ = writeln!(self.lines, "{} = {}", name, text);
+            self.names.insert(key, name.clone());
+            return name;
+        }
+        if precedence(op) < min_precedence && op != Op::Eql {
+            format!("({})", text)
+        } else {
+            text
+        }
+    }
+}
+
+impl fmt::Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        // no use counts, so shared subexpressions are written out in full every time
+        let mut printer = Printer {
+            uses: HashMap::new(),
+            names: HashMap::new(),
+            lines: String::new(),
+        };
+        write!(f, "{}", printer.write(self, 0))
+    }
+}
+
+/// Something that makes the ALU fail, in program order
+#[derive(Debug, Clone)]
+enum Check {
+    /// inp reading the digit with this index
+    Input { pc: usize, idx: usize },
+    /// div or mod by a value that can be zero
+    Divisor { pc: usize, op: Op, divisor: Expr },
+    /// checked operation whose result may not fit
+    Overflow {
+        pc: usize,
+        op: Op,
+        operands: (Expr, Expr),
+    },
+}
+
+/// Register expressions after running a program
+#[derive(Debug, Clone)]
+pub struct SymbolicRegisters {
+    values: [Expr; 4],
+    /// number of digits read
+    pub inputs: usize,
+    /// the expressions only hold if none of these fail
+    checks: Vec<Check>,
+}
+
+impl SymbolicRegisters {
+    fn slot(var: Var) -> usize {
+        match var {
+            Var::W => 0,
+            Var::X => 1,
+            Var::Y => 2,
+            Var::Z => 3,
+        }
+    }
+
+    /// Registers for the given digits, failing where the ALU would
+    ///
+    /// Unlike `Expr::eval` of a register this also catches divisions by zero and overflows whose
+    /// results are thrown away later.
+    pub fn run(&self, digits: &[Int]) -> Result<Registers, AluError> {
+        for check in &self.checks {
+            match check {
+                Check::Input { pc, idx } if *idx >= digits.len() => {
+                    return Err(AluError::InputExhausted { pc: *pc })
+                }
+                Check::Divisor { pc, op, divisor } if divisor.eval(digits) == Some(0) => {
+                    return Err(AluError::DivisionByZero { pc: *pc, op: *op })
+                }
+                // earlier failures of the operands are caught by earlier checks
+                Check::Overflow {
+                    pc,
+                    op,
+                    operands: (e1, e2),
+                } if matches!((e1.eval(digits), e2.eval(digits)), (Some(n1), Some(n2))
+                    if n2 != 0 && op.result(&n1, &n2).is_none()) =>
+                {
+                    return Err(AluError::Overflow { pc: *pc, op: *op })
+                }
+                _ => {}
+            }
+        }
+        let mut values = [0; 4];
+        for (value, expr) in values.iter_mut().zip(&self.values) {
+            *value = expr.eval(digits).expect("failures are checked above");
+        }
+        Ok(Registers::from_slots(values))
+    }
+}
+
+impl Index<Var> for SymbolicRegisters {
+    type Output = Expr;
+
+    fn index(&self, var: Var) -> &Expr {
+        &self.values[SymbolicRegisters::slot(var)]
+    }
+}
+
+/// Runs the program from zeroed registers, every inp reads the next digit within `digit_bounds`
+///
+/// Overflows are errors, as in the ALU by default.
+pub fn evaluate(program: &[Instruction], digit_bounds: Bounds) -> SymbolicRegisters {
+    evaluate_with_overflow(program, digit_bounds, Overflow::default())
+}
+
+pub fn evaluate_with_overflow(
+    program: &[Instruction],
+    digit_bounds: Bounds,
+    overflow: Overflow,
+) -> SymbolicRegisters {
+    let zero = Expr::constant(0);
+    let mut registers = SymbolicRegisters {
+        values: [zero.clone(), zero.clone(), zero.clone(), zero],
+        inputs: 0,
+        checks: Vec::new(),
+    };
+    for (pc, instruction) in program.iter().enumerate() {
+        let (var, expr) = match instruction {
+            Instruction::Inp(var) => {
+                let idx = registers.inputs;
+                registers.inputs += 1;
+                registers.checks.push(Check::Input { pc, idx });
+                (*var, Expr::digit(idx, digit_bounds))
+            }
+            Instruction::Op(op, var, value) => {
+                let operand = match value {
+                    Value::Number(n) => Expr::constant(*n),
+                    Value::Variable(v) => registers[*v].clone(),
+                };
+                if matches!(op, Op::Div | Op::Mod) && operand.bounds().contains(0) {
+                    registers.checks.push(Check::Divisor {
+                        pc,
+                        op: *op,
+                        divisor: operand.clone(),
+                    });
+                }
+                let current = &registers[*var];
+                if overflow == Overflow::Checked
+                    && Bounds::may_overflow(*op, current.bounds(), operand.bounds())
+                {
+                    registers.checks.push(Check::Overflow {
+                        pc,
+                        op: *op,
+                        operands: (current.clone(), operand.clone()),
+                    });
+                }
+                (
+                    *var,
+                    Expr::apply_with_overflow(*op, &registers[*var], &operand, overflow),
+                )
+            }
+        };
+        registers.values[SymbolicRegisters::slot(var)] = expr;
+    }
+    registers
+}
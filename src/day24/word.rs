@@ -0,0 +1,106 @@
+//! Integer types the ALU can run on and what happens when they overflow
+
+use super::{Int, Op};
+use std::fmt;
+use std::hash::Hash;
+
+/// What the ALU does with results that don't fit into its registers
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Overflow {
+    /// two's complement wrap around, what the hardware does
+    Wrapping,
+    /// the program fails with `AluError::Overflow`
+    #[default]
+    Checked,
+    /// clamped to the smallest or largest value
+    Saturating,
+}
+
+impl Overflow {
+    /// None if the result overflows and that is an error, the divisor must not be zero
+    pub fn apply<W: Word>(&self, op: Op, n1: W, n2: W) -> Option<W> {
+        match self {
+            Overflow::Wrapping => Some(W::wrapping(op, n1, n2)),
+            Overflow::Checked => W::checked(op, n1, n2),
+            Overflow::Saturating => Some(W::saturating(op, n1, n2)),
+        }
+    }
+
+    /// Program constant as a register value, None if it doesn't fit and that is an error
+    pub fn convert<W: Word>(&self, n: Int) -> Option<W> {
+        match self {
+            Overflow::Wrapping => Some(W::wrap(n)),
+            Overflow::Checked => W::from_int(n),
+            Overflow::Saturating => Some(W::saturate(n)),
+        }
+    }
+}
+
+/// Signed integer of some width, i64 is the one the puzzle asks for
+pub trait Word:
+    Copy + Default + Eq + Ord + Hash + fmt::Debug + fmt::Display + Send + Sync + 'static
+{
+    fn from_int(n: Int) -> Option<Self>;
+
+    /// keeps the lowest bits
+    fn wrap(n: Int) -> Self;
+
+    fn saturate(n: Int) -> Self;
+
+    fn checked(op: Op, n1: Self, n2: Self) -> Option<Self>;
+
+    fn wrapping(op: Op, n1: Self, n2: Self) -> Self;
+
+    fn saturating(op: Op, n1: Self, n2: Self) -> Self;
+}
+
+macro_rules! impl_word {
+    ($($t:ty),*) => {$(
+        impl Word for $t {
+            fn from_int(n: Int) -> Option<$t> {
+                <$t>::try_from(n).ok()
+            }
+
+            fn wrap(n: Int) -> $t {
+                n as $t
+            }
+
+            fn saturate(n: Int) -> $t {
+                <$t>::try_from(n).unwrap_or(if n < 0 { <$t>::MIN } else { <$t>::MAX })
+            }
+
+            fn checked(op: Op, n1: $t, n2: $t) -> Option<$t> {
+                match op {
+                    Op::Add => n1.checked_add(n2),
+                    Op::Mul => n1.checked_mul(n2),
+                    Op::Div => n1.checked_div(n2),
+                    Op::Mod => n1.checked_rem(n2),
+                    Op::Eql => Some((n1 == n2) as $t),
+                }
+            }
+
+            fn wrapping(op: Op, n1: $t, n2: $t) -> $t {
+                match op {
+                    Op::Add => n1.wrapping_add(n2),
+                    Op::Mul => n1.wrapping_mul(n2),
+                    Op::Div => n1.wrapping_div(n2),
+                    Op::Mod => n1.wrapping_rem(n2),
+                    Op::Eql => (n1 == n2) as $t,
+                }
+            }
+
+            fn saturating(op: Op, n1: $t, n2: $t) -> $t {
+                match op {
+                    Op::Add => n1.saturating_add(n2),
+                    Op::Mul => n1.saturating_mul(n2),
+                    Op::Div => n1.saturating_div(n2),
+                    // MIN % -1 is 0 and doesn't overflow in the first place
+                    Op::Mod => n1.wrapping_rem(n2),
+                    Op::Eql => (n1 == n2) as $t,
+                }
+            }
+        }
+    )*};
+}
+
+impl_word!(i8, i16, i32, i64, i128);
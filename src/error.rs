@@ -0,0 +1,155 @@
+use std::fmt;
+use std::io;
+use std::path::PathBuf;
+
+#[derive(Debug)]
+pub enum Error {
+    /// Input file is missing or unreadable
+    Io { path: PathBuf, source: io::Error },
+    /// Input couldn't be downloaded
+    Fetch { day: u8, message: String },
+    /// Malformed puzzle input, line and column are 1-based
+    Parse {
+        day: u8,
+        line: usize,
+        column: usize,
+        message: String,
+    },
+    /// Input is well-formed but there's no answer for it
+    NoSolution { day: u8, message: String },
+}
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+impl Error {
+    pub fn no_solution(day: u8, message: impl Into<String>) -> Error {
+        Error::NoSolution {
+            day,
+            message: message.into(),
+        }
+    }
+
+    pub fn fetch(day: u8, message: impl Into<String>) -> Error {
+        Error::Fetch {
+            day,
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Io { path, source } => write!(f, "can't read {}: {}", path.display(), source),
+            Error::Fetch { day, message } => {
+                write!(f, "can't fetch input for day {}: {}", day, message)
+            }
+            Error::Parse {
+                day,
+                line,
+                column,
+                message,
+            } => write!(
+                f,
+                "day {}, line {}, column {}: {}",
+                day, line, column, message
+            ),
+            Error::NoSolution { day, message } => write!(f, "day {}: {}", day, message),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+/// Error found by a parser that only sees a part of the input, the position is relative to that part
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    line: usize,   // 0-based
+    column: usize, // 0-based
+    message: String,
+}
+
+/// byte offset of a slice within the text it was taken from
+fn offset_in(text: &str, part: &str) -> Option<usize> {
+    let start = text.as_ptr() as usize;
+    let part_start = part.as_ptr() as usize;
+    if part_start >= start && part_start + part.len() <= start + text.len() {
+        Some(part_start - start)
+    } else {
+        None
+    }
+}
+
+/// 0-based line and column of a byte offset
+fn position(text: &str, offset: usize) -> (usize, usize) {
+    let before = &text[..offset];
+    let line = before.matches('\n').count();
+    let line_start = before.rfind('\n').map(|i| i + 1).unwrap_or(0);
+    (line, before[line_start..].chars().count())
+}
+
+impl ParseError {
+    /// Error pointing at `token`, which must be a slice of `text`
+    pub fn at(text: &str, token: &str, message: impl Into<String>) -> ParseError {
+        let (line, column) = match offset_in(text, token) {
+            Some(offset) => position(text, offset),
+            None => (0, 0),
+        };
+        ParseError {
+            line,
+            column,
+            message: message.into(),
+        }
+    }
+
+    /// Error pointing right after the end of `text`, e.g. for missing tokens
+    pub fn at_end(text: &str, message: impl Into<String>) -> ParseError {
+        let (line, column) = position(text, text.len());
+        ParseError {
+            line,
+            column,
+            message: message.into(),
+        }
+    }
+
+    /// Same error relative to a larger text, `part` must be the slice of `text` that was being parsed
+    pub fn within(self, text: &str, part: &str) -> ParseError {
+        let (part_line, part_column) = match offset_in(text, part) {
+            Some(offset) => position(text, offset),
+            None => (0, 0),
+        };
+        ParseError {
+            line: part_line + self.line,
+            column: if self.line == 0 {
+                part_column + self.column
+            } else {
+                self.column
+            },
+            message: self.message,
+        }
+    }
+
+    /// Full error for the day's input, `text` must be the slice of `input` that was being parsed
+    pub fn in_input(self, day: u8, input: &str, text: &str) -> Error {
+        let e = self.within(input, text);
+        Error::Parse {
+            day,
+            line: e.line + 1,
+            column: e.column + 1,
+            message: e.message,
+        }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:{}: {}", self.line + 1, self.column + 1, self.message)
+    }
+}
@@ -0,0 +1,365 @@
+//! Where puzzle inputs come from: files in the data directory, stdin or a download
+//!
+//! Data directory is `data` by default, with `dayNN/input.txt` for puzzle inputs and
+//! `dayNN/input_test.txt` for examples. With a session token missing puzzle inputs are
+//! downloaded from the site and stored in the data directory.
+
+use super::error::{Error, Result};
+use super::trace::info;
+use super::utils::{read_file, read_stdin};
+use std::env;
+use std::fs;
+use std::io::{self, Read, Write};
+use std::net::{Ipv6Addr, TcpStream};
+use std::path::{Path, PathBuf};
+use std::process::{self, Stdio};
+use std::time::Duration;
+
+pub const DATA_DIR_VAR: &str = "AOC_DATA_DIR";
+pub const SESSION_VAR: &str = "AOC_SESSION";
+pub const URL_VAR: &str = "AOC_URL";
+pub const DEFAULT_DATA_DIR: &str = "data";
+pub const DEFAULT_URL: &str = "https://adventofcode.com";
+const YEAR: u16 = 2021;
+const USER_AGENT: &str = "advent-of-code-2021 input fetcher (rust)";
+const TIMEOUT: Duration = Duration::from_secs(30);
+
+pub trait InputSource {
+    fn read(&self, day: u8) -> Result<String>;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InputKind {
+    Puzzle,
+    Example,
+}
+
+impl InputKind {
+    pub fn file_name(&self) -> &'static str {
+        match self {
+            InputKind::Puzzle => "input.txt",
+            InputKind::Example => "input_test.txt",
+        }
+    }
+}
+
+/// --data-dir if given, then AOC_DATA_DIR, then ./data
+pub fn data_dir(arg: Option<&Path>) -> PathBuf {
+    match arg {
+        Some(dir) => dir.to_path_buf(),
+        None => env::var_os(DATA_DIR_VAR)
+            .map(PathBuf::from)
+            .unwrap_or_else(|| PathBuf::from(DEFAULT_DATA_DIR)),
+    }
+}
+
+/// Inputs of one kind stored in the data directory
+#[derive(Debug, Clone)]
+pub struct FileCache {
+    dir: PathBuf,
+    kind: InputKind,
+}
+
+impl FileCache {
+    pub fn new(dir: impl Into<PathBuf>, kind: InputKind) -> FileCache {
+        FileCache {
+            dir: dir.into(),
+            kind,
+        }
+    }
+
+    pub fn path(&self, day: u8) -> PathBuf {
+        self.dir
+            .join(format!("day{:02}", day))
+            .join(self.kind.file_name())
+    }
+
+    pub fn contains(&self, day: u8) -> bool {
+        self.path(day).is_file()
+    }
+
+    /// creates the day directory if needed
+    pub fn store(&self, day: u8, input: &str) -> io::Result<()> {
+        let path = self.path(day);
+        if let Some(day_dir) = path.parent() {
+            fs::create_dir_all(day_dir)?;
+        }
+        fs::write(path, input)
+    }
+}
+
+impl InputSource for FileCache {
+    fn read(&self, day: u8) -> Result<String> {
+        read_file(&self.path(day))
+    }
+}
+
+/// The same file whatever the day is
+#[derive(Debug, Clone)]
+pub struct FileInput(pub PathBuf);
+
+impl InputSource for FileInput {
+    fn read(&self, _day: u8) -> Result<String> {
+        read_file(&self.0)
+    }
+}
+
+pub struct StdinInput;
+
+impl InputSource for StdinInput {
+    fn read(&self, _day: u8) -> Result<String> {
+        read_stdin()
+    }
+}
+
+/// Downloads the puzzle input for a day
+pub trait Fetcher {
+    fn fetch(&self, day: u8) -> Result<String>;
+}
+
+/// Reads inputs from the cache, missing ones are fetched and stored there
+pub struct FetchingCache {
+    cache: FileCache,
+    fetcher: Box<dyn Fetcher>,
+}
+
+impl FetchingCache {
+    pub fn new(cache: FileCache, fetcher: Box<dyn Fetcher>) -> FetchingCache {
+        FetchingCache { cache, fetcher }
+    }
+}
+
+impl InputSource for FetchingCache {
+    fn read(&self, day: u8) -> Result<String> {
+        if self.cache.contains(day) {
+            return self.cache.read(day);
+        }
+        info!("downloading input for day {}", day);
+        let input = self.fetcher.fetch(day)?;
+        // the input is still usable even if it can't be cached
+        match self.cache.store(day, &input) {
+            Ok(()) => info!(
+                "saved input for day {} to {}",
+                day,
+                self.cache.path(day).display()
+            ),
+            Err(e) => info!("can't save {}: {}", self.cache.path(day).display(), e),
+        }
+        Ok(input)
+    }
+}
+
+/// path of the input on the site, e.g. /2021/day/16/input
+pub fn input_url_path(day: u8) -> String {
+    format!("/{}/day/{}/input", YEAR, day)
+}
+
+/// Fetcher for `url`, https goes through curl and plain http is handled by `HttpFetcher`
+pub fn fetcher(url: &str, session: &str) -> Result<Box<dyn Fetcher>, String> {
+    if url.starts_with("https://") {
+        Ok(Box::new(CurlFetcher {
+            url: url.trim_end_matches('/').to_string(),
+            session: session.to_string(),
+        }))
+    } else if url.starts_with("http://") {
+        Ok(Box::new(HttpFetcher::new(url, session)?))
+    } else {
+        Err(format!(
+            "url must start with http:// or https://, not '{}'",
+            url
+        ))
+    }
+}
+
+/// Minimal HTTP/1.1 client, enough for a local server or a caching proxy
+#[derive(Debug, Clone)]
+pub struct HttpFetcher {
+    host: String,
+    port: u16,
+    prefix: String,
+    session: String,
+}
+
+impl HttpFetcher {
+    /// `url` is like "http://localhost:8080", "http://[::1]:8080" or "http://host/some/prefix"
+    pub fn new(url: &str, session: &str) -> Result<HttpFetcher, String> {
+        let rest = url
+            .strip_prefix("http://")
+            .ok_or_else(|| format!("only http:// urls are supported, not '{}'", url))?;
+        let (authority, prefix) = match rest.find('/') {
+            Some(i) => (&rest[..i], rest[i..].trim_end_matches('/')),
+            None => (rest, ""),
+        };
+        let (host, port) = match authority.rsplit_once(':') {
+            // a colon inside the brackets of an IPv6 address doesn't start a port
+            Some((host, port)) if !port.contains(']') => (
+                host,
+                port.parse()
+                    .map_err(|_| format!("bad port '{}' in '{}'", port, url))?,
+            ),
+            _ => (authority, 80),
+        };
+        if host.is_empty() {
+            return Err(format!("no host in '{}'", url));
+        }
+        let valid = match host.strip_prefix('[') {
+            Some(address) => address
+                .strip_suffix(']')
+                .is_some_and(|address| address.parse::<Ipv6Addr>().is_ok()),
+            None => !host.contains([':', ']']),
+        };
+        if !valid {
+            return Err(format!(
+                "bad host '{}' in '{}', IPv6 addresses go in brackets",
+                host, url
+            ));
+        }
+        Ok(HttpFetcher {
+            host: host.to_string(),
+            port,
+            prefix: prefix.to_string(),
+            session: session.to_string(),
+        })
+    }
+
+    fn get(&self, path: &str) -> io::Result<Vec<u8>> {
+        // the Host header keeps the brackets of an IPv6 address, connecting takes it without them
+        let host = self
+            .host
+            .strip_prefix('[')
+            .and_then(|host| host.strip_suffix(']'))
+            .unwrap_or(&self.host);
+        let mut stream = TcpStream::connect((host, self.port))?;
+        stream.set_read_timeout(Some(TIMEOUT))?;
+        stream.set_write_timeout(Some(TIMEOUT))?;
+        write!(
+            stream,
+            "GET {} HTTP/1.1\r\nHost: {}\r\nUser-Agent: {}\r\nCookie: session={}\r\nConnection: close\r\n\r\n",
+            path, self.host, USER_AGENT, self.session
+        )?;
+        let mut response = Vec::new();
+        stream.read_to_end(&mut response)?;
+        Ok(response)
+    }
+}
+
+impl Fetcher for HttpFetcher {
+    fn fetch(&self, day: u8) -> Result<String> {
+        let path = format!("{}{}", self.prefix, input_url_path(day));
+        let response = self.get(&path).map_err(|e| {
+            Error::fetch(
+                day,
+                format!(
+                    "request to {}:{}{} failed: {}",
+                    self.host, self.port, path, e
+                ),
+            )
+        })?;
+        parse_response(&response).map_err(|message| Error::fetch(day, message))
+    }
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack.windows(needle.len()).position(|w| w == needle)
+}
+
+/// body of a 200 response, anything else is an error with the status and the first line of the body
+fn parse_response(response: &[u8]) -> Result<String, String> {
+    let header_end = find(response, b"\r\n\r\n").ok_or("malformed response, no end of headers")?;
+    let head = String::from_utf8_lossy(&response[..header_end]);
+    let mut body = response[header_end + 4..].to_vec();
+
+    let mut lines = head.split("\r\n");
+    let status_line = lines.next().unwrap_or("");
+    let status = status_line.split_whitespace().nth(1).unwrap_or("");
+
+    let mut chunked = false;
+    let mut content_length: Option<usize> = None;
+    for line in lines {
+        if let Some((name, value)) = line.split_once(':') {
+            let value = value.trim();
+            match name.trim().to_ascii_lowercase().as_str() {
+                "transfer-encoding" => chunked = value.eq_ignore_ascii_case("chunked"),
+                "content-length" => content_length = value.parse().ok(),
+                _ => {}
+            }
+        }
+    }
+    if chunked {
+        body = decode_chunked(&body)?;
+    } else if let Some(len) = content_length {
+        body.truncate(len);
+    }
+
+    let body = String::from_utf8(body).map_err(|_| "response is not valid UTF-8")?;
+    if status != "200" {
+        return Err(format!(
+            "server responded with '{}': {}",
+            status_line,
+            body.lines().next().unwrap_or("").trim()
+        ));
+    }
+    Ok(body)
+}
+
+fn decode_chunked(mut body: &[u8]) -> Result<Vec<u8>, String> {
+    let mut res = Vec::new();
+    loop {
+        let line_end = find(body, b"\r\n").ok_or("malformed chunked response")?;
+        let size_str = String::from_utf8_lossy(&body[..line_end]);
+        let size_str = size_str.split(';').next().unwrap_or("").trim();
+        let size = usize::from_str_radix(size_str, 16)
+            .map_err(|_| format!("bad chunk size '{}'", size_str))?;
+        body = &body[line_end + 2..];
+        if size == 0 {
+            return Ok(res);
+        }
+        if body.len() < size {
+            return Err("chunked response is cut short".to_string());
+        }
+        res.extend_from_slice(&body[..size]);
+        body = body[size..].strip_prefix(b"\r\n").unwrap_or(&body[size..]);
+    }
+}
+
+/// Fetches over https with the curl binary, the session is passed through stdin so that
+/// it doesn't show up in the process list
+#[derive(Debug, Clone)]
+pub struct CurlFetcher {
+    url: String,
+    session: String,
+}
+
+impl Fetcher for CurlFetcher {
+    fn fetch(&self, day: u8) -> Result<String> {
+        let url = format!("{}{}", self.url, input_url_path(day));
+        let mut child = process::Command::new("curl")
+            .args(["--silent", "--show-error", "--fail", "--header", "@-"])
+            .args(["--user-agent", USER_AGENT])
+            .arg(&url)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| Error::fetch(day, format!("can't run curl: {}", e)))?;
+        if let Some(mut stdin) = child.stdin.take() {
+            writeln!(stdin, "Cookie: session={}", self.session)
+                .map_err(|e| Error::fetch(day, format!("can't pass the session to curl: {}", e)))?;
+        }
+        let output = child
+            .wait_with_output()
+            .map_err(|e| Error::fetch(day, format!("curl failed: {}", e)))?;
+        if !output.status.success() {
+            return Err(Error::fetch(
+                day,
+                format!(
+                    "{}: {}",
+                    url,
+                    String::from_utf8_lossy(&output.stderr).trim()
+                ),
+            ));
+        }
+        String::from_utf8(output.stdout)
+            .map_err(|_| Error::fetch(day, "response is not valid UTF-8"))
+    }
+}
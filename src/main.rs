@@ -1,68 +1,307 @@
-#![feature(once_cell)]
-mod day01;
-mod day02;
-mod day03;
-mod day04;
-mod day05;
-mod day06;
-mod day07;
-mod day08;
-mod day09;
-mod day10;
-mod day11;
-mod day12;
-mod day13;
-mod day14;
-mod day15;
-mod day16;
-mod day17;
-mod day18;
-mod day19;
-mod day20;
-mod day21;
-mod day22;
-mod day23;
-mod day24;
-mod utils;
+mod cli;
 
+use advent_of_code::bench::{self, Baseline, DayBench};
+use advent_of_code::day24::{self, disasm::Disassembly, ArithmeticLogicUnit};
+use advent_of_code::input::{
+    self, FetchingCache, FileCache, FileInput, InputKind, InputSource, StdinInput,
+};
+use advent_of_code::report::{self, PartReport};
+use advent_of_code::trace::{self, Filter, Level};
+use advent_of_code::{registry, utils, DynSolver, Registry, Result, Solver};
+use cli::{BenchArgs, Command, Days, DisasmArgs, Format, InputArg, RunArgs};
 use std::env;
+use std::fs;
+use std::io::{self, ErrorKind, Write};
+use std::path::Path;
+use std::process;
+use std::time::Duration;
+
+/// puzzle inputs are downloaded only when there's a session token
+fn input_source(
+    input: &InputArg,
+    data_dir: Option<&Path>,
+) -> std::result::Result<Box<dyn InputSource>, String> {
+    let dir = input::data_dir(data_dir);
+    Ok(match input {
+        InputArg::Test => Box::new(FileCache::new(dir, InputKind::Example)),
+        InputArg::Path(path) => Box::new(FileInput(path.clone())),
+        InputArg::Stdin => Box::new(StdinInput),
+        InputArg::Default => {
+            let cache = FileCache::new(dir, InputKind::Puzzle);
+            match env::var(input::SESSION_VAR) {
+                Ok(session) if !session.trim().is_empty() => {
+                    let url =
+                        env::var(input::URL_VAR).unwrap_or_else(|_| input::DEFAULT_URL.to_string());
+                    let fetcher = input::fetcher(&url, session.trim())
+                        .map_err(|e| format!("{}: {}", input::URL_VAR, e))?;
+                    Box::new(FetchingCache::new(cache, fetcher))
+                }
+                _ => Box::new(cache),
+            }
+        }
+    })
+}
+
+/// Solvers for the requested days or None if the day is not registered
+fn select_days<'a>(registry: &'a Registry, days: &Days) -> Option<Vec<&'a dyn DynSolver>> {
+    match days {
+        Days::All => Some(registry.iter().collect()),
+        Days::Latest => Some(registry.iter().last().into_iter().collect()),
+        Days::Single(day) => registry.get(*day).map(|solver| vec![solver]),
+    }
+}
+
+fn run_day(
+    solver: &dyn DynSolver,
+    source: &dyn InputSource,
+    args: &RunArgs,
+) -> Result<Vec<PartReport>> {
+    let input = source.read(solver.day())?;
+    report::solve_parts(solver, &input, args.part.numbers())
+}
+
+/// false if the day has failed
+fn print_day(solver: &dyn DynSolver, reports: &Result<Vec<PartReport>>, format: Format) -> bool {
+    let reports = match (reports, format) {
+        (Ok(reports), _) => reports,
+        (Err(e), Format::Text) => {
+            eprintln!("error: {}", e);
+            return false;
+        }
+        (Err(e), Format::Json) => {
+            println!("{}", report::day_error_json(solver.day(), e));
+            return false;
+        }
+    };
+    if format == Format::Text {
+        println!("day {} ({})", solver.day(), solver.name());
+    }
+    let mut ok = true;
+    for r in reports {
+        match (&r.answer, format) {
+            (_, Format::Json) => println!("{}", r.to_json()),
+            (Ok(answer), Format::Text) => println!("part {}: {}", r.part, answer),
+            (Err(e), Format::Text) => eprintln!("error: {}", e),
+        }
+        ok &= r.answer.is_ok();
+    }
+    ok
+}
+
+/// exit code for the run
+fn run(registry: &Registry, args: &RunArgs) -> i32 {
+    let solvers = match select_days(registry, &args.days) {
+        Some(solvers) => solvers,
+        None => {
+            eprintln!(
+                "error: {} is not yet implemented",
+                describe_days(&args.days)
+            );
+            return 2;
+        }
+    };
+
+    let source = match input_source(&args.input, args.data_dir.as_deref()) {
+        Ok(source) => source,
+        Err(e) => {
+            eprintln!("error: {}", e);
+            return 2;
+        }
+    };
+
+    let mut exit_code = 0;
+    for solver in solvers {
+        if !print_day(solver, &run_day(solver, source.as_ref(), args), args.format) {
+            exit_code = 1;
+        }
+    }
+    exit_code
+}
+
+fn describe_days(days: &Days) -> String {
+    match days {
+        Days::Single(day) => format!("day {}", day),
+        _ => "no day".to_string(),
+    }
+}
+
+fn format_ms(d: Duration) -> String {
+    format!("{:.3} ms", report::elapsed_ms(d))
+}
+
+fn print_bench(b: &DayBench, baseline: Option<&Baseline>) {
+    println!("day {} ({})", b.day, b.name);
+    for (stage, stats) in &b.stats {
+        print!(
+            "  {:6} min {:>12}  median {:>12}  max {:>12}",
+            stage.name(),
+            format_ms(stats.min),
+            format_ms(stats.median),
+            format_ms(stats.max)
+        );
+        if let Some(base) = baseline.and_then(|bl| bl.get(b.day, *stage)) {
+            if !base.is_zero() {
+                print!(
+                    "  vs baseline {:+.1}%",
+                    bench::relative_change(base, stats.median)
+                );
+            }
+        }
+        println!();
+    }
+}
+
+/// exit code for the benchmark
+fn run_bench(registry: &Registry, args: &BenchArgs) -> i32 {
+    let solvers = match select_days(registry, &args.days) {
+        Some(solvers) => solvers,
+        None => {
+            eprintln!(
+                "error: {} is not yet implemented",
+                describe_days(&args.days)
+            );
+            return 2;
+        }
+    };
+    let source = match input_source(&args.input, args.data_dir.as_deref()) {
+        Ok(source) => source,
+        Err(e) => {
+            eprintln!("error: {}", e);
+            return 2;
+        }
+    };
+    let baseline = match &args.baseline {
+        None => None,
+        Some(path) => match utils::read_file(path) {
+            Err(e) => {
+                eprintln!("error: {}", e);
+                return 1;
+            }
+            Ok(text) => match Baseline::parse(&text) {
+                Ok(baseline) => Some(baseline),
+                Err(e) => {
+                    eprintln!("error: {}:{}", path.display(), e);
+                    return 1;
+                }
+            },
+        },
+    };
+
+    let mut exit_code = 0;
+    let mut benches: Vec<DayBench> = Vec::new();
+    for solver in solvers {
+        let input = source.read(solver.day());
+        match input.and_then(|input| bench::bench_day(solver, &input, args.iterations)) {
+            Ok(b) => {
+                print_bench(&b, baseline.as_ref());
+                benches.push(b);
+            }
+            Err(e) => {
+                eprintln!("error: {}", e);
+                exit_code = 1;
+            }
+        }
+    }
+
+    if let Some(baseline) = &baseline {
+        let regressions = baseline.regressions(&benches, args.threshold);
+        for r in &regressions {
+            eprintln!(
+                "regression: day {} {} median {} -> {} ({:+.1}%)",
+                r.day,
+                r.stage.name(),
+                format_ms(r.baseline),
+                format_ms(r.current),
+                r.change()
+            );
+        }
+        if !regressions.is_empty() {
+            exit_code = 1;
+        }
+    }
+    if let Some(path) = &args.save_baseline {
+        if let Err(e) = fs::write(path, Baseline::from_benches(&benches).to_string()) {
+            eprintln!("error: can't write {}: {}", path.display(), e);
+            exit_code = 1;
+        }
+    }
+    exit_code
+}
+
+/// exit code for printing the disassembly
+fn run_disasm(args: &DisasmArgs) -> i32 {
+    let source = match input_source(&args.input, args.data_dir.as_deref()) {
+        Ok(source) => source,
+        Err(e) => {
+            eprintln!("error: {}", e);
+            return 2;
+        }
+    };
+    let program = match source
+        .read(ArithmeticLogicUnit::DAY)
+        .and_then(|input| day24::parse_program(&input))
+    {
+        Ok(program) => program,
+        Err(e) => {
+            eprintln!("error: {}", e);
+            return 1;
+        }
+    };
+    let mut disassembly = Disassembly::new(&program).colored(args.color);
+    if let Some(columns) = args.columns {
+        disassembly = disassembly.columns(columns);
+    }
+    // the disassembly is long enough to be piped into head
+    match write!(io::stdout().lock(), "{}", disassembly) {
+        Ok(()) => 0,
+        Err(e) if e.kind() == ErrorKind::BrokenPipe => 0,
+        Err(e) => {
+            eprintln!("error: can't write the disassembly: {}", e);
+            1
+        }
+    }
+}
+
+/// --log takes precedence over the environment, -v flags raise the level for all days
+fn log_filter(log: Option<String>, verbosity: u8) -> std::result::Result<Filter, String> {
+    let spec = log
+        .or_else(|| env::var(trace::ENV_VAR).ok())
+        .unwrap_or_default();
+    let filter = Filter::parse(&spec)?;
+    Ok(filter.at_least(match verbosity {
+        0 => Level::Off,
+        1 => Level::Info,
+        2 => Level::Debug,
+        _ => Level::Trace,
+    }))
+}
 
 fn main() {
-    let args: Vec<String> = env::args().collect();
-    if args.len() > 1 {
-        let day_to_run: u8 = (&args[1])
-            .parse()
-            .expect("Command line argument must be an integer");
-        match day_to_run {
-            1 => day01::sonar(),
-            2 => day02::submarine_path(),
-            3 => day03::submarine_ratings(),
-            4 => day04::squid(),
-            5 => day05::vent_lines(),
-            6 => day06::lanternfish(),
-            7 => day07::align_crabs(),
-            8 => day08::messed_up_displays(),
-            9 => day09::smoke_basin(),
-            10 => day10::brackets_parsing(),
-            11 => day11::dumbos(),
-            12 => day12::path_in_caves(),
-            13 => day13::folding_origami(),
-            14 => day14::polymers(),
-            15 => day15::chitons(),
-            16 => day16::bits_decoding(),
-            17 => day17::probe_launch(),
-            18 => day18::snailfish_math(),
-            19 => day19::beacons(),
-            20 => day20::image_enhancement(),
-            21 => day21::dirac_die(),
-            22 => day22::reactor_cubes(),
-            23 => day23::amphipod_rooms(),
-            _ => {
-                println!("Day {} is not yet implemented", day_to_run)
+    let args = match cli::parse_args(env::args().skip(1)) {
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("error: {}\n\n{}", e, cli::USAGE);
+            process::exit(2);
+        }
+    };
+    match log_filter(args.log, args.verbosity) {
+        Ok(filter) => trace::set_filter(filter),
+        Err(e) => {
+            eprintln!("error: {}\n\n{}", e, cli::USAGE);
+            process::exit(2);
+        }
+    }
+
+    let registry = registry();
+    match args.command {
+        Command::Help => println!("{}", cli::USAGE),
+        Command::List => {
+            for solver in registry.iter() {
+                println!("{:2} {}", solver.day(), solver.name());
             }
         }
-    } else {
-        day24::arithmetic_logic_unit();
-        // new day will be here...
+        Command::Run(args) => process::exit(run(&registry, &args)),
+        Command::Bench(args) => process::exit(run_bench(&registry, &args)),
+        Command::Disasm(args) => process::exit(run_disasm(&args)),
     }
 }
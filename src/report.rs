@@ -0,0 +1,95 @@
+use super::error::{Error, Result};
+use super::solver::{Answer, DynSolver};
+use std::fmt::Write;
+use std::time::{Duration, Instant};
+
+/// Outcome of a single part of some day
+pub struct PartReport {
+    pub day: u8,
+    pub part: u8,
+    pub answer: Result<Answer>,
+    pub elapsed: Duration,
+}
+
+/// Parses the input once and runs requested parts (1 and/or 2) on it, parsing errors fail the whole day
+pub fn solve_parts(solver: &dyn DynSolver, input: &str, parts: &[u8]) -> Result<Vec<PartReport>> {
+    let parsed = solver.parse(input)?;
+    Ok(parts
+        .iter()
+        .map(|&part| {
+            let start = Instant::now();
+            let answer = match part {
+                1 => solver.part1(parsed.as_ref()),
+                _ => solver.part2(parsed.as_ref()),
+            };
+            PartReport {
+                day: solver.day(),
+                part,
+                answer,
+                elapsed: start.elapsed(),
+            }
+        })
+        .collect())
+}
+
+pub fn json_string(s: &str) -> String {
+    let mut res = String::with_capacity(s.len() + 2);
+    res.push('"');
+    for ch in s.chars() {
+        match ch {
+            '"' => res.push_str("\\\""),
+            '\\' => res.push_str("\\\\"),
+            '\n' => res.push_str("\\n"),
+            '\r' => res.push_str("\\r"),
+            '\t' => res.push_str("\\t"),
+            ch if (ch as u32) < 0x20 => {
+                let _ = write!(res, "\\u{:04x}", ch as u32);
+            }
+            ch => res.push(ch),
+        }
+    }
+    res.push('"');
+    res
+}
+
+fn json_answer(answer: &Answer) -> String {
+    match answer {
+        Answer::Number(n) => n.to_string(),
+        Answer::Unsigned(n) => n.to_string(),
+        Answer::Text(s) => json_string(s),
+    }
+}
+
+pub fn elapsed_ms(elapsed: Duration) -> f64 {
+    elapsed.as_secs_f64() * 1000.0
+}
+
+impl PartReport {
+    /// {"day": .., "part": .., "answer": .., "elapsed_ms": ..}, answer is null and "error" is added on failure
+    pub fn to_json(&self) -> String {
+        let (answer, error) = match &self.answer {
+            Ok(answer) => (json_answer(answer), String::new()),
+            Err(e) => (
+                "null".to_string(),
+                format!(", \"error\": {}", json_string(&e.to_string())),
+            ),
+        };
+        format!(
+            "{{\"day\": {}, \"part\": {}, \"answer\": {}, \"elapsed_ms\": {:.3}{}}}",
+            self.day,
+            self.part,
+            answer,
+            elapsed_ms(self.elapsed),
+            error
+        )
+    }
+}
+
+/// Record for a day that failed before any part was run, e.g. on a parsing error
+pub fn day_error_json(day: u8, error: &Error) -> String {
+    format!(
+        "{{\"day\": {}, \"part\": null, \"answer\": null, \"elapsed_ms\": null, \"error\": {}}}",
+        day,
+        json_string(&error.to_string())
+    )
+}
@@ -0,0 +1,203 @@
+use std::any::Any;
+use std::collections::BTreeMap;
+use std::fmt;
+
+use super::error::Result;
+use super::{
+    day01, day02, day03, day04, day05, day06, day07, day08, day09, day10, day11, day12, day13,
+    day14, day15, day16, day17, day18, day19, day20, day21, day22, day23, day24,
+};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Answer {
+    Number(i64),
+    /// unsigned answer too big for `Number`
+    Unsigned(u64),
+    Text(String),
+}
+
+impl fmt::Display for Answer {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Answer::Number(n) => write!(f, "{}", n),
+            Answer::Unsigned(n) => write!(f, "{}", n),
+            Answer::Text(s) => write!(f, "{}", s),
+        }
+    }
+}
+
+macro_rules! answer_from_int {
+    ($($t:ty),*) => {
+        $(
+            impl From<$t> for Answer {
+                fn from(n: $t) -> Answer {
+                    Answer::Number(i64::from(n))
+                }
+            }
+        )*
+    };
+}
+
+answer_from_int!(u8, u16, u32, i32, i64);
+
+macro_rules! answer_from_unsigned {
+    ($($t:ty),*) => {
+        $(
+            impl From<$t> for Answer {
+                fn from(n: $t) -> Answer {
+                    match i64::try_from(n) {
+                        Ok(n) => Answer::Number(n),
+                        // usize is at most 64 bits wide on every supported target
+                        Err(_) => Answer::Unsigned(n as u64),
+                    }
+                }
+            }
+        )*
+    };
+}
+
+answer_from_unsigned!(u64, usize);
+
+impl From<String> for Answer {
+    fn from(s: String) -> Answer {
+        Answer::Text(s)
+    }
+}
+
+impl From<&str> for Answer {
+    fn from(s: &str) -> Answer {
+        Answer::Text(s.to_string())
+    }
+}
+
+/// Solution for a single day: input is parsed once and then shared by both parts
+pub trait Solver {
+    type Input;
+
+    const DAY: u8;
+    const NAME: &'static str;
+
+    fn parse(&self, input: &str) -> Result<Self::Input>;
+    fn part1(&self, input: &Self::Input) -> Result<Answer>;
+    fn part2(&self, input: &Self::Input) -> Result<Answer>;
+}
+
+/// Object-safe version of Solver, parsed input is passed around as Any
+pub trait DynSolver {
+    fn day(&self) -> u8;
+    fn name(&self) -> &'static str;
+    fn parse(&self, input: &str) -> Result<Box<dyn Any>>;
+    fn part1(&self, input: &dyn Any) -> Result<Answer>;
+    fn part2(&self, input: &dyn Any) -> Result<Answer>;
+
+    fn solve(&self, input: &str) -> Result<(Answer, Answer)> {
+        let parsed = self.parse(input)?;
+        Ok((self.part1(parsed.as_ref())?, self.part2(parsed.as_ref())?))
+    }
+}
+
+impl<S> DynSolver for S
+where
+    S: Solver,
+    S::Input: 'static,
+{
+    fn day(&self) -> u8 {
+        S::DAY
+    }
+
+    fn name(&self) -> &'static str {
+        S::NAME
+    }
+
+    fn parse(&self, input: &str) -> Result<Box<dyn Any>> {
+        Ok(Box::new(Solver::parse(self, input)?))
+    }
+
+    fn part1(&self, input: &dyn Any) -> Result<Answer> {
+        Solver::part1(self, downcast_input::<S>(input))
+    }
+
+    fn part2(&self, input: &dyn Any) -> Result<Answer> {
+        Solver::part2(self, downcast_input::<S>(input))
+    }
+}
+
+fn downcast_input<S: Solver>(input: &dyn Any) -> &S::Input
+where
+    S::Input: 'static,
+{
+    input
+        .downcast_ref::<S::Input>()
+        .expect("Parsed input was produced by another day's solver")
+}
+
+pub struct Registry {
+    solvers: BTreeMap<u8, Box<dyn DynSolver>>,
+}
+
+impl Registry {
+    pub fn new() -> Registry {
+        Registry {
+            solvers: BTreeMap::new(),
+        }
+    }
+
+    pub fn register<S>(&mut self, solver: S)
+    where
+        S: Solver + 'static,
+        S::Input: 'static,
+    {
+        if self.solvers.insert(S::DAY, Box::new(solver)).is_some() {
+            panic!("Day {} is registered twice", S::DAY);
+        }
+    }
+
+    pub fn get(&self, day: u8) -> Option<&dyn DynSolver> {
+        self.solvers.get(&day).map(|s| s.as_ref())
+    }
+
+    pub fn days(&self) -> impl Iterator<Item = u8> + '_ {
+        self.iter().map(|s| s.day())
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &dyn DynSolver> {
+        self.solvers.values().map(|s| s.as_ref())
+    }
+}
+
+impl Default for Registry {
+    fn default() -> Registry {
+        Registry::new()
+    }
+}
+
+/// Registry with all the days solved so far
+pub fn registry() -> Registry {
+    let mut registry = Registry::new();
+    registry.register(day01::Sonar);
+    registry.register(day02::SubmarinePath);
+    registry.register(day03::SubmarineRatings);
+    registry.register(day04::Squid);
+    registry.register(day05::VentLines);
+    registry.register(day06::Lanternfish);
+    registry.register(day07::AlignCrabs);
+    registry.register(day08::MessedUpDisplays);
+    registry.register(day09::SmokeBasin);
+    registry.register(day10::BracketsParsing);
+    registry.register(day11::Dumbos);
+    registry.register(day12::PathInCaves);
+    registry.register(day13::FoldingOrigami);
+    registry.register(day14::Polymers);
+    registry.register(day15::Chitons);
+    registry.register(day16::BitsDecoding);
+    registry.register(day17::ProbeLaunch);
+    registry.register(day18::SnailfishMath);
+    registry.register(day19::Beacons);
+    registry.register(day20::ImageEnhancement);
+    registry.register(day21::DiracDie);
+    registry.register(day22::ReactorCubes);
+    registry.register(day23::AmphipodRooms);
+    registry.register(day24::ArithmeticLogicUnit);
+    // new day will be here...
+    registry
+}
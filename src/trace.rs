@@ -0,0 +1,163 @@
+//! Runtime-configurable debug output, written to stderr
+//!
+//! Filter spec is a comma-separated list of levels, either global ("debug") or per module
+//! ("day16=trace"), e.g. `AOC_LOG=info,day23=debug`. Module names are paths inside the crate,
+//! "day24" also covers "day24::alu" and alike.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::RwLock;
+
+pub const ENV_VAR: &str = "AOC_LOG";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Level {
+    Off,
+    Info,
+    Debug,
+    Trace,
+}
+
+impl Level {
+    pub fn parse(s: &str) -> Option<Level> {
+        match s {
+            "off" => Some(Level::Off),
+            "info" => Some(Level::Info),
+            "debug" => Some(Level::Debug),
+            "trace" => Some(Level::Trace),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Level::Off => "off",
+            Level::Info => "info",
+            Level::Debug => "debug",
+            Level::Trace => "trace",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Filter {
+    default: Level,
+    modules: BTreeMap<String, Level>,
+}
+
+impl Filter {
+    pub const fn off() -> Filter {
+        Filter {
+            default: Level::Off,
+            modules: BTreeMap::new(),
+        }
+    }
+
+    pub fn parse(spec: &str) -> Result<Filter, String> {
+        let mut filter = Filter::off();
+        for item in spec.split(',').map(|s| s.trim()).filter(|s| !s.is_empty()) {
+            let (module, level_str) = match item.split_once('=') {
+                Some((module, level_str)) => (Some(module.trim()), level_str.trim()),
+                None => (None, item),
+            };
+            let level = Level::parse(level_str).ok_or_else(|| {
+                format!(
+                    "unknown log level '{}', expected off, info, debug or trace",
+                    level_str
+                )
+            })?;
+            match module {
+                Some(module) => {
+                    filter.modules.insert(module.to_string(), level);
+                }
+                None => filter.default = level,
+            }
+        }
+        Ok(filter)
+    }
+
+    /// raises the global level, e.g. for repeated -v flags
+    pub fn at_least(mut self, level: Level) -> Filter {
+        self.default = self.default.max(level);
+        self
+    }
+
+    /// the most specific module rule wins
+    pub fn level_for(&self, module: &str) -> Level {
+        self.modules
+            .iter()
+            .filter(|(m, _)| {
+                module == m.as_str()
+                    || (module.starts_with(m.as_str()) && module[m.len()..].starts_with("::"))
+            })
+            .max_by_key(|(m, _)| m.len())
+            .map(|(_, level)| *level)
+            .unwrap_or(self.default)
+    }
+
+    fn max_level(&self) -> Level {
+        self.modules
+            .values()
+            .copied()
+            .fold(self.default, Level::max)
+    }
+}
+
+static FILTER: RwLock<Filter> = RwLock::new(Filter::off());
+// checked first so that disabled tracing in hot loops costs a single atomic load
+static MAX_LEVEL: AtomicU8 = AtomicU8::new(Level::Off as u8);
+
+pub fn set_filter(filter: Filter) {
+    MAX_LEVEL.store(filter.max_level() as u8, Ordering::Relaxed);
+    *FILTER.write().unwrap_or_else(|e| e.into_inner()) = filter;
+}
+
+/// module path without the crate name, e.g. "day16"
+pub fn module_name(module_path: &str) -> &str {
+    module_path
+        .split_once("::")
+        .map(|(_, m)| m)
+        .unwrap_or(module_path)
+}
+
+pub fn is_enabled(module_path: &str, level: Level) -> bool {
+    if level as u8 > MAX_LEVEL.load(Ordering::Relaxed) || level == Level::Off {
+        return false;
+    }
+    let filter = FILTER.read().unwrap_or_else(|e| e.into_inner());
+    level <= filter.level_for(module_name(module_path))
+}
+
+pub fn write(module_path: &str, level: Level, args: fmt::Arguments) {
+    eprintln!("[{} {}] {}", module_name(module_path), level.name(), args);
+}
+
+macro_rules! log {
+    ($level:expr, $($arg:tt)+) => {
+        if $crate::trace::is_enabled(module_path!(), $level) {
+            $crate::trace::write(module_path!(), $level, format_args!($($arg)+));
+        }
+    };
+}
+
+macro_rules! info {
+    ($($arg:tt)+) => { $crate::trace::log!($crate::trace::Level::Info, $($arg)+) };
+}
+
+macro_rules! debug {
+    ($($arg:tt)+) => { $crate::trace::log!($crate::trace::Level::Debug, $($arg)+) };
+}
+
+macro_rules! trace {
+    ($($arg:tt)+) => { $crate::trace::log!($crate::trace::Level::Trace, $($arg)+) };
+}
+
+/// for output that is expensive to prepare
+macro_rules! enabled {
+    ($level:expr) => {
+        $crate::trace::is_enabled(module_path!(), $level)
+    };
+}
+
+pub(crate) use {debug, enabled, info, log, trace};
@@ -1,7 +1,111 @@
+use super::error::{Error, ParseError, Result};
 use std::fs;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
 
-pub fn read_input(day: u8, test: bool) -> String {
-    let filename = if test { "input_test" } else { "input" };
-    fs::read_to_string(format!("data/day{:02}/{}.txt", day, filename))
-        .expect("Can't read input file")
+pub fn read_file(path: &Path) -> Result<String> {
+    fs::read_to_string(path).map_err(|source| Error::Io {
+        path: path.to_path_buf(),
+        source,
+    })
+}
+
+pub fn read_stdin() -> Result<String> {
+    let mut input = String::new();
+    io::stdin()
+        .read_to_string(&mut input)
+        .map_err(|source| Error::Io {
+            path: PathBuf::from("<stdin>"),
+            source,
+        })?;
+    Ok(input)
+}
+
+/// Parses each line of the input separately, errors get the line number
+pub fn parse_lines<T, F>(day: u8, input: &str, parse_line: F) -> Result<Vec<T>>
+where
+    F: Fn(&str) -> Result<T, ParseError>,
+{
+    input
+        .lines()
+        .map(|line| parse_line(line).map_err(|e| e.in_input(day, input, line)))
+        .collect()
+}
+
+/// Parses `token` (a slice of `text`) as a number, `what` is used in error message
+pub fn parse_number<T: FromStr>(text: &str, token: &str, what: &str) -> Result<T, ParseError> {
+    token.parse().map_err(|_| {
+        ParseError::at(
+            text,
+            token,
+            format!("can't parse {} from '{}'", what, token),
+        )
+    })
+}
+
+/// Next token or an error pointing to the end of `text`
+pub fn expect_token<'a>(
+    text: &str,
+    token: Option<&'a str>,
+    what: &str,
+) -> Result<&'a str, ParseError> {
+    token.ok_or_else(|| ParseError::at_end(text, format!("{} is missing", what)))
+}
+
+/// Rectangular grid of single-digit numbers, one row per line
+pub fn parse_digit_grid(text: &str) -> Result<Vec<Vec<u8>>, ParseError> {
+    let mut grid: Vec<Vec<u8>> = Vec::new();
+    for line in text.lines() {
+        let mut row: Vec<u8> = Vec::new();
+        for (idx, ch) in line.char_indices() {
+            match ch.to_digit(10) {
+                Some(d) => row.push(d as u8),
+                None => {
+                    return Err(ParseError::at(
+                        text,
+                        &line[idx..idx + ch.len_utf8()],
+                        format!("expected a digit, found '{}'", ch),
+                    ))
+                }
+            }
+        }
+        if let Some(first_row) = grid.first() {
+            if row.len() != first_row.len() {
+                return Err(ParseError::at(
+                    text,
+                    line,
+                    format!("row has {} digits, expected {}", row.len(), first_row.len()),
+                ));
+            }
+        }
+        grid.push(row);
+    }
+    if grid.is_empty() || grid[0].is_empty() {
+        return Err(ParseError::at_end(text, "grid is empty"));
+    }
+    Ok(grid)
+}
+
+/// xorshift64*, plenty for picking random test cases
+pub struct Rng(u64);
+
+impl Rng {
+    pub fn new(seed: u64) -> Rng {
+        // xorshift never leaves a zero state
+        let state = seed ^ 0x9e37_79b9_7f4a_7c15;
+        Rng(if state == 0 { 1 } else { state })
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        self.0.wrapping_mul(0x2545_f491_4f6c_dd1d)
+    }
+
+    /// uniform in 0..n
+    pub fn below(&mut self, n: usize) -> usize {
+        (self.next_u64() % n as u64) as usize
+    }
 }
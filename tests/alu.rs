@@ -0,0 +1,111 @@
+//! ALU virtual machine on the example programs from the puzzle
+
+use advent_of_code::day24::alu::{self, AluError, Registers};
+use advent_of_code::day24::word::Overflow;
+use advent_of_code::day24::{parse_program, Op, Program, Var};
+
+fn program(source: &str) -> Program {
+    parse_program(source).unwrap()
+}
+
+#[test]
+fn negates_input() {
+    let registers = alu::run(&program("inp x\nmul x -1"), [7]).unwrap();
+    assert_eq!(registers[Var::X], -7);
+}
+
+#[test]
+fn checks_second_input_is_three_times_the_first() {
+    let p = program("inp z\ninp x\nmul z 3\neql z x");
+    assert_eq!(alu::run(&p, [2, 6]).unwrap()[Var::Z], 1);
+    assert_eq!(alu::run(&p, [2, 5]).unwrap()[Var::Z], 0);
+}
+
+#[test]
+fn splits_input_into_bits() {
+    let p = program(
+        "inp w\nadd z w\nmod z 2\ndiv w 2\nadd y w\nmod y 2\ndiv w 2\nadd x w\nmod x 2\ndiv w 2\nmod w 2",
+    );
+    let registers = alu::run(&p, [13]).unwrap();
+    let bits: Vec<i64> = [Var::W, Var::X, Var::Y, Var::Z]
+        .iter()
+        .map(|v| registers[*v])
+        .collect();
+    assert_eq!(bits, vec![1, 1, 0, 1]);
+}
+
+#[test]
+fn fails_on_division_by_zero() {
+    let p = program("inp x\nadd y 5\ndiv y x");
+    assert_eq!(
+        alu::run(&p, [0]),
+        Err(AluError::DivisionByZero { pc: 2, op: Op::Div })
+    );
+    let p = program("inp x\nmod x z");
+    assert_eq!(
+        alu::run(&p, [3]),
+        Err(AluError::DivisionByZero { pc: 1, op: Op::Mod })
+    );
+}
+
+#[test]
+fn fails_when_input_runs_out() {
+    let p = program("inp x\ninp y\nadd x y");
+    assert_eq!(alu::run(&p, [1]), Err(AluError::InputExhausted { pc: 1 }));
+}
+
+#[test]
+fn overflow_follows_the_policy() {
+    let p = program("inp x\nmul x 100\nmul x 100");
+    assert_eq!(
+        alu::run_with::<i16, _>(&p, [5], Overflow::Checked),
+        Err(AluError::Overflow { pc: 2, op: Op::Mul })
+    );
+    let wrapped = alu::run_with::<i16, _>(&p, [5], Overflow::Wrapping).unwrap();
+    assert_eq!(wrapped[Var::X], 50000u16 as i16);
+    let saturated = alu::run_with::<i16, _>(&p, [5], Overflow::Saturating).unwrap();
+    assert_eq!(saturated[Var::X], i16::MAX);
+
+    // constants have to fit as well
+    let p = program("add y -200");
+    assert_eq!(
+        alu::run_with::<i8, _>(&p, [], Overflow::Checked),
+        Err(AluError::Overflow { pc: 0, op: Op::Add })
+    );
+    assert_eq!(
+        alu::run_with::<i8, _>(&p, [], Overflow::Wrapping).unwrap()[Var::Y],
+        56
+    );
+    assert_eq!(
+        alu::run_with::<i8, _>(&p, [], Overflow::Saturating).unwrap()[Var::Y],
+        i8::MIN
+    );
+
+    // i64 is checked by default
+    let p = program("inp x\ndiv x -1");
+    assert_eq!(
+        alu::run(&p, [i64::MIN]),
+        Err(AluError::Overflow { pc: 1, op: Op::Div })
+    );
+}
+
+#[test]
+fn runs_monad_on_other_widths() {
+    let p = program(include_str!("../data/day24/input_test.txt"));
+    let input = [9, 6, 8, 9, 2, 9, 1, 9];
+    assert_eq!(alu::run(&p, input).unwrap()[Var::Z], 0);
+    let z = |registers: Registers<i32>| registers[Var::Z];
+    assert_eq!(
+        alu::run_with(&p, input.map(|d| d as i32), Overflow::Checked).map(z),
+        Ok(0)
+    );
+    assert_eq!(
+        alu::run_with(&p, input.map(|d| d as i128), Overflow::Checked).unwrap()[Var::Z],
+        0
+    );
+    // z holds a base 26 stack, two levels of it don't fit into i8
+    assert!(matches!(
+        alu::run_with(&p, input.map(|d| d as i8), Overflow::Checked),
+        Err(AluError::Overflow { .. })
+    ));
+}
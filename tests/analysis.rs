@@ -0,0 +1,307 @@
+//! Static analysis and search of MONAD programs against running them on the ALU
+
+use advent_of_code::day24::analysis::{Analysis, AnalysisError, Constraint};
+use advent_of_code::day24::search::{
+    carried_register, compile_blocks, par_first_valid, ValidNumbers,
+};
+use advent_of_code::day24::word::Overflow;
+use advent_of_code::day24::{
+    code_blocks, is_valid, parse_program, ArithmeticLogicUnit, ModelNumbers, Order, Program, Var,
+};
+use advent_of_code::{Answer, Solver};
+
+/// MONAD program built from (divisor, check, offset) of each block
+fn monad(params: &[(i64, i64, i64)]) -> String {
+    params
+        .iter()
+        .map(|(divisor, check, offset)| {
+            format!(
+                "inp w\nmul x 0\nadd x z\nmod x 26\ndiv z {}\nadd x {}\neql x w\neql x 0\nmul y 0\nadd y 25\n\
+                 mul y x\nadd y 1\nmul z y\nmul y 0\nadd y w\nadd y {}\nmul y x\nadd z y\n",
+                divisor, check, offset
+            )
+        })
+        .collect()
+}
+
+/// the same program with a block shape the analysis doesn't know, so that it has to be searched
+fn unanalyzable(source: &str) -> String {
+    source.replace("add y 25", "add y 24\nadd y 1")
+}
+
+fn analyze(program: &Program) -> Result<Analysis, AnalysisError> {
+    Analysis::new(&code_blocks(program))
+}
+
+/// every valid number of a short program, in ascending order
+fn valid_numbers(program: &Program, digits: u32) -> Vec<Vec<u8>> {
+    (0..9u32.pow(digits))
+        .map(|mut n| {
+            let mut number = vec![0; digits as usize];
+            for d in number.iter_mut().rev() {
+                *d = (n % 9 + 1) as u8;
+                n /= 9;
+            }
+            number
+        })
+        .filter(|number| is_valid(program, number))
+        .collect()
+}
+
+#[test]
+fn pairs_pushes_with_pops() {
+    let program = parse_program(&monad(&[
+        (1, 12, 4),
+        (1, 11, 11),
+        (26, -13, 5),
+        (26, -2, 6),
+    ]))
+    .unwrap();
+    let analysis = analyze(&program).unwrap();
+    assert_eq!(
+        analysis.constraints,
+        vec![
+            Constraint {
+                earlier: 0,
+                later: 3,
+                diff: 2
+            },
+            Constraint {
+                earlier: 1,
+                later: 2,
+                diff: -2
+            },
+        ]
+    );
+    assert_eq!(analysis.max_model_number(), Some(vec![7, 9, 7, 9]));
+    assert_eq!(analysis.min_model_number(), Some(vec![1, 3, 1, 3]));
+}
+
+#[test]
+fn agrees_with_running_every_number() {
+    for params in [
+        vec![(1, 10, 0), (26, -5, 3), (1, 14, 16), (26, -16, 1)],
+        vec![(1, 13, 8), (1, 15, 2), (26, 0, 9), (26, -12, 7)],
+        vec![(1, 11, 3), (26, 5, 2), (1, 12, 1), (26, -9, 4)],
+    ] {
+        let program = parse_program(&monad(&params)).unwrap();
+        let analysis = analyze(&program).unwrap();
+        let valid = valid_numbers(&program, 4);
+        assert_eq!(
+            analysis.max_model_number(),
+            valid.last().cloned(),
+            "{:?}",
+            params
+        );
+        assert_eq!(
+            analysis.min_model_number(),
+            valid.first().cloned(),
+            "{:?}",
+            params
+        );
+    }
+}
+
+#[test]
+fn rejects_programs_it_cant_reason_about() {
+    let unbalanced = parse_program(&monad(&[(1, 12, 4), (1, 11, 11), (26, -13, 5)])).unwrap();
+    assert_eq!(
+        analyze(&unbalanced),
+        Err(AnalysisError::Unbalanced { unmatched: 1 })
+    );
+
+    let pop_first = parse_program(&monad(&[(26, -1, 4), (1, 11, 11)])).unwrap();
+    assert_eq!(
+        analyze(&pop_first),
+        Err(AnalysisError::EmptyStack { block: 0 })
+    );
+
+    let tweaked = parse_program(&monad(&[(1, 12, 4), (26, -3, 5)]).replacen(
+        "add y 25",
+        "add y 24\nadd y 1",
+        1,
+    ))
+    .unwrap();
+    assert_eq!(
+        analyze(&tweaked),
+        Err(AnalysisError::UnknownShape { block: 0, line: 9 })
+    );
+}
+
+#[test]
+fn solver_falls_back_to_search() {
+    let source = monad(&[(1, 12, 4), (1, 11, 11), (26, -13, 5), (26, -2, 6)]).replacen(
+        "add y 25",
+        "add y 24\nadd y 1",
+        2,
+    );
+    let program = ArithmeticLogicUnit.parse(&source).unwrap();
+    assert!(analyze(&program).is_err());
+    assert_eq!(
+        ArithmeticLogicUnit.part1(&program).unwrap(),
+        Answer::Number(7979)
+    );
+    assert_eq!(
+        ArithmeticLogicUnit.part2(&program).unwrap(),
+        Answer::Number(1313)
+    );
+}
+
+#[test]
+fn model_numbers_match_running_every_number() {
+    let source = monad(&[(1, 10, 0), (26, -5, 3), (1, 14, 16), (26, -16, 1)]);
+    for source in [source.clone(), unanalyzable(&source)] {
+        let program = parse_program(&source).unwrap();
+        let numbers = ModelNumbers::new(&program).unwrap();
+        let ascending = valid_numbers(&program, 4);
+        let descending: Vec<Vec<u8>> = ascending.iter().rev().cloned().collect();
+
+        assert_eq!(
+            numbers
+                .iter(Order::Ascending)
+                .collect::<Result<Vec<_>, _>>()
+                .unwrap(),
+            ascending
+        );
+        assert_eq!(
+            numbers
+                .iter(Order::Descending)
+                .collect::<Result<Vec<_>, _>>()
+                .unwrap(),
+            descending
+        );
+        assert_eq!(numbers.count().unwrap(), ascending.len() as u64);
+        assert_eq!(numbers.max().unwrap(), descending.first().cloned());
+        assert_eq!(numbers.min().unwrap(), ascending.first().cloned());
+    }
+}
+
+#[test]
+fn model_numbers_are_streamed_lazily() {
+    let program = parse_program(&monad(&[
+        (1, 12, 5),
+        (1, 11, 2),
+        (26, -4, 9),
+        (1, 13, 8),
+        (26, -8, 4),
+        (26, -3, 6),
+        (1, 10, 1),
+        (1, 15, 7),
+        (26, -6, 3),
+        (26, -2, 11),
+        (1, 14, 3),
+        (1, 12, 4),
+        (26, -5, 2),
+        (26, 0, 8),
+    ]))
+    .unwrap();
+    let numbers = ModelNumbers::new(&program).unwrap();
+    assert_eq!(numbers.count().unwrap(), 7 * 9 * 7 * 8 * 8 * 8 * 6);
+
+    let first: Vec<Vec<u8>> = numbers
+        .iter(Order::Descending)
+        .take(3)
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(Some(&first[0]), numbers.max().unwrap().as_ref());
+    assert!(first.windows(2).all(|pair| pair[0] > pair[1]));
+    assert!(first.iter().all(|number| is_valid(&program, number)));
+}
+
+#[test]
+fn search_counts_the_example() {
+    let source = std::fs::read_to_string(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/data/day24/input_test.txt"
+    ))
+    .unwrap();
+    let program = parse_program(&source).unwrap();
+    let searched = parse_program(&unanalyzable(&source)).unwrap();
+    assert_eq!(ModelNumbers::new(&program).unwrap().count().unwrap(), 96);
+    assert_eq!(ModelNumbers::new(&searched).unwrap().count().unwrap(), 96);
+}
+
+#[test]
+fn parallel_search_finds_the_same_numbers() {
+    let source = std::fs::read_to_string(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/data/day24/input_test.txt"
+    ))
+    .unwrap();
+    let program = parse_program(&unanalyzable(&source)).unwrap();
+    let blocks = compile_blocks(&code_blocks(&program), Overflow::default());
+    for order in [Order::Descending, Order::Ascending] {
+        let expected = ValidNumbers::new(&blocks, order).next().transpose();
+        for threads in [1, 2, 3, 8] {
+            assert_eq!(par_first_valid(&blocks, order, threads).0, expected);
+        }
+    }
+    for threads in [1, 4] {
+        let numbers = ModelNumbers::new(&program).unwrap().with_threads(threads);
+        assert_eq!(numbers.max().unwrap(), Some(vec![9, 6, 8, 9, 2, 9, 1, 9]));
+        assert_eq!(numbers.min().unwrap(), Some(vec![4, 1, 1, 2, 1, 9, 1, 8]));
+    }
+
+    // no valid numbers at all, every task has to run to the end
+    let none = parse_program(&unanalyzable(&monad(&[(1, 12, 4), (26, -20, 5)]))).unwrap();
+    let blocks = compile_blocks(&code_blocks(&none), Overflow::default());
+    assert_eq!(par_first_valid(&blocks, Order::Descending, 4).0, Ok(None));
+}
+
+#[test]
+fn overflows_stop_the_search() {
+    // z + w overflows for every digit and the answer depends on how that is handled
+    let program = parse_program("inp w\nadd z 9223372036854775807\nadd z w\nmul z 0").unwrap();
+    let error = ArithmeticLogicUnit.part1(&program).unwrap_err().to_string();
+    assert!(
+        error.ends_with("block 0, instruction 2: add overflows"),
+        "{}",
+        error
+    );
+    assert!(ArithmeticLogicUnit.part2(&program).is_err());
+    for threads in [1, 4] {
+        let numbers = ModelNumbers::new(&program).unwrap().with_threads(threads);
+        assert!(numbers.max().is_err());
+        assert!(numbers.count().is_err());
+        assert!(numbers.iter(Order::Ascending).next().unwrap().is_err());
+    }
+
+    for overflow in [Overflow::Wrapping, Overflow::Saturating] {
+        let numbers = ModelNumbers::with_overflow(&program, overflow).unwrap();
+        assert_eq!(numbers.max().unwrap(), Some(vec![9]));
+        assert_eq!(numbers.min().unwrap(), Some(vec![1]));
+        assert_eq!(numbers.count().unwrap(), 9);
+    }
+}
+
+#[test]
+fn search_only_carries_z() {
+    let block = "inp w\nmul x 0\nadd x z\nmod x 26\neql x w\nmul y 0\nadd y w\nadd z y";
+    assert_eq!(carried_register(&parse_program(block).unwrap()), None);
+    // y of the first block is still there in the second one
+    let program = parse_program("inp w\nadd y w\ninp w\nadd z y\nadd z -9").unwrap();
+    let blocks = code_blocks(&program);
+    assert_eq!(carried_register(blocks[1]), Some((1, Var::Y)));
+    let error = ModelNumbers::new(&program).err().unwrap().to_string();
+    assert!(
+        error.ends_with("block 1, instruction 1: reads y before writing it, the search only carries z from block to block"),
+        "{}",
+        error
+    );
+}
+
+#[test]
+fn model_numbers_must_fit_in_64_bits() {
+    // each push is popped by the next block and has to match it, 20 digits overflow for 9s
+    // but not for 1s
+    let pairs = |n: usize| monad(&[(1, 11, 0), (26, 0, 0)].repeat(n));
+    let program = parse_program(&pairs(10)).unwrap();
+    assert_eq!(
+        ArithmeticLogicUnit.part1(&program).unwrap_err().to_string(),
+        "day 24: model number 99999999999999999999 doesn't fit in 64 bits"
+    );
+    assert_eq!(
+        ArithmeticLogicUnit.part2(&program).unwrap(),
+        Answer::Unsigned(11_111_111_111_111_111_111)
+    );
+}
@@ -0,0 +1,113 @@
+//! Runs every registered day on the inputs in data/dayNN/ and compares answers with the fixtures:
+//! `<input>.part1.txt` and `<input>.part2.txt` next to `<input>.txt`, e.g. input_test.part1.txt
+
+use advent_of_code::report::solve_parts;
+use advent_of_code::{registry, Answer};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::thread;
+
+/// example inputs are committed, personal puzzle inputs are checked only when present
+const INPUTS: [(&str, bool); 2] = [("input_test", true), ("input", false)];
+
+fn day_dir(day: u8) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join(format!("data/day{:02}", day))
+}
+
+/// answers don't care about the trailing newline at the end of the fixture file
+fn normalized(answer: &str) -> &str {
+    answer.trim_end_matches('\n')
+}
+
+/// list of mismatches, empty if everything is fine
+fn check_day(day: u8) -> Vec<String> {
+    let registry = registry();
+    let solver = registry.get(day).unwrap();
+    let mut failures: Vec<String> = Vec::new();
+
+    for (input_name, required) in INPUTS {
+        let dir = day_dir(day);
+        let input_path = dir.join(format!("{}.txt", input_name));
+        let input = match fs::read_to_string(&input_path) {
+            Ok(input) => input,
+            Err(_) => {
+                if required {
+                    failures.push(format!("day {}: {} is missing", day, input_path.display()));
+                }
+                continue;
+            }
+        };
+
+        let mut expected: Vec<(u8, String)> = Vec::new();
+        for part in [1, 2] {
+            let fixture_path = dir.join(format!("{}.part{}.txt", input_name, part));
+            match fs::read_to_string(&fixture_path) {
+                Ok(answer) => expected.push((part, answer)),
+                Err(_) if required => failures.push(format!(
+                    "day {}: {} is missing",
+                    day,
+                    fixture_path.display()
+                )),
+                Err(_) => {}
+            }
+        }
+        if expected.is_empty() {
+            continue;
+        }
+
+        let parts: Vec<u8> = expected.iter().map(|(part, _)| *part).collect();
+        let reports = match solve_parts(solver, &input, &parts) {
+            Ok(reports) => reports,
+            Err(e) => {
+                failures.push(format!("day {} on {}.txt: {}", day, input_name, e));
+                continue;
+            }
+        };
+        for (report, (part, expected_answer)) in reports.iter().zip(&expected) {
+            match &report.answer {
+                Ok(answer) if normalized(&answer.to_string()) == normalized(expected_answer) => {}
+                Ok(answer) => failures.push(format!(
+                    "day {} part {} on {}.txt: expected\n{}\ngot\n{}",
+                    day,
+                    part,
+                    input_name,
+                    normalized(expected_answer),
+                    normalized(&answer.to_string())
+                )),
+                Err(e) => failures.push(format!(
+                    "day {} part {} on {}.txt: {}",
+                    day, part, input_name, e
+                )),
+            }
+        }
+    }
+    failures
+}
+
+#[test]
+fn every_day_matches_expected_answers() {
+    // solvers aren't Send, so each thread builds its own registry
+    let handles: Vec<(u8, thread::JoinHandle<Vec<String>>)> = registry()
+        .days()
+        .map(|day| (day, thread::spawn(move || check_day(day))))
+        .collect();
+
+    let mut failures: Vec<String> = Vec::new();
+    for (day, handle) in handles {
+        match handle.join() {
+            Ok(day_failures) => failures.extend(day_failures),
+            Err(_) => failures.push(format!("day {} panicked", day)),
+        }
+    }
+    assert!(failures.is_empty(), "\n{}\n", failures.join("\n\n"));
+}
+
+#[test]
+fn unsigned_answers_keep_their_value() {
+    assert_eq!(Answer::from(u64::MAX).to_string(), "18446744073709551615");
+    assert_eq!(
+        Answer::from(1usize << 63).to_string(),
+        "9223372036854775808"
+    );
+    assert_eq!(Answer::from(42u64), Answer::Number(42));
+}
@@ -0,0 +1,112 @@
+//! BITS encoder against the day 16 decoder
+
+use advent_of_code::day16::{
+    encode_packet, hexdump2bits, parse_packet, LengthType, Packet, PacketHeader,
+};
+use advent_of_code::utils::Rng;
+
+fn decode(hexdump: &str) -> Packet {
+    let bits = hexdump2bits(hexdump).unwrap();
+    parse_packet(&bits).unwrap().0
+}
+
+fn header(version: u8, type_id: u8) -> PacketHeader {
+    PacketHeader { version, type_id }
+}
+
+fn random_packet(rng: &mut Rng, depth: usize) -> Packet {
+    let version = rng.below(8) as u8;
+    if depth == 0 || rng.below(3) == 0 {
+        // values of every width, the longest ones take all 16 groups
+        let value = rng.next_u64() >> rng.below(64);
+        return Packet::Literal(header(version, 4), value);
+    }
+    let type_id = [0, 1, 2, 3, 5, 6, 7][rng.below(7)];
+    let length_type = if rng.below(2) == 0 {
+        LengthType::Bits
+    } else {
+        LengthType::Count
+    };
+    let subpackets = (0..rng.below(5))
+        .map(|_| random_packet(rng, depth - 1))
+        .collect();
+    Packet::Op(header(version, type_id), length_type, subpackets)
+}
+
+#[test]
+fn encodes_the_examples() {
+    assert_eq!(
+        Packet::Literal(header(6, 4), 2021).to_hexdump().unwrap(),
+        "D2FE28"
+    );
+    // the examples are padded to whole bytes, the encoder only to whole hex digits
+    let packet = Packet::Op(
+        header(1, 6),
+        LengthType::Bits,
+        vec![
+            Packet::Literal(header(6, 4), 10),
+            Packet::Literal(header(2, 4), 20),
+        ],
+    );
+    assert_eq!(decode("38006F45291200"), packet);
+    assert_eq!(packet.to_hexdump().unwrap(), "38006F4529120");
+    let packet = decode("EE00D40C823060");
+    assert!(matches!(packet, Packet::Op(_, LengthType::Count, _)));
+    assert_eq!(packet.to_hexdump().unwrap(), "EE00D40C82306");
+}
+
+#[test]
+fn rejects_what_does_not_fit() {
+    assert_eq!(Packet::Literal(header(8, 4), 1).to_hexdump(), None);
+    assert_eq!(Packet::Literal(header(0, 3), 1).to_hexdump(), None);
+    assert_eq!(
+        Packet::Op(header(0, 4), LengthType::Count, vec![]).to_hexdump(),
+        None
+    );
+    let many = vec![Packet::Literal(header(0, 4), 0); 3000];
+    assert_eq!(
+        Packet::Op(header(0, 0), LengthType::Count, many.clone()).to_hexdump(),
+        None
+    );
+    // 3000 literals of 11 bits are past the 15 bit length as well
+    assert_eq!(
+        Packet::Op(header(0, 0), LengthType::Bits, many).to_hexdump(),
+        None
+    );
+}
+
+#[test]
+fn decodes_what_it_encodes() {
+    let mut rng = Rng::new(16);
+    for _ in 0..500 {
+        let packet = random_packet(&mut rng, 4);
+        let hexdump = packet.to_hexdump().unwrap();
+        assert_eq!(decode(&hexdump), packet, "{}", hexdump);
+    }
+    // and the other way round for the puzzle examples
+    let example = include_str!("../data/day16/input_test.txt").trim();
+    let hexdump = decode(example).to_hexdump().unwrap();
+    assert_eq!(decode(&hexdump), decode(example));
+    assert!(example.starts_with(&hexdump));
+}
+
+#[test]
+fn leaves_the_bits_alone_on_failure() {
+    let mut bits = vec![true, false, true];
+    // the second literal has a version that doesn't fit after the first one is written
+    let packet = Packet::Op(
+        header(1, 0),
+        LengthType::Count,
+        vec![
+            Packet::Literal(header(2, 4), 5),
+            Packet::Literal(header(9, 4), 5),
+        ],
+    );
+    assert_eq!(encode_packet(&packet, &mut bits), None);
+    assert_eq!(bits, vec![true, false, true]);
+    assert_eq!(
+        encode_packet(&Packet::Literal(header(2, 4), 5), &mut bits),
+        Some(())
+    );
+    assert_eq!(bits.len(), 3 + 11);
+}
@@ -0,0 +1,399 @@
+//! Bracket checker on the day 10 example and on text with other characters
+
+use advent_of_code::day10::checker::{BracketChecker, LineStatus};
+use advent_of_code::day10::repair::Edit;
+use advent_of_code::day10::stream::{Report, StreamError};
+use advent_of_code::day10::table::{BracketTable, Scores};
+use advent_of_code::day10::{BracketType, BracketsParsing, NavigationSubsystem};
+use advent_of_code::solver::Solver;
+
+use std::io::{self, Read};
+
+const EXAMPLE: &str = include_str!("../data/day10/input_test.txt");
+
+#[test]
+fn classifies_the_example() {
+    let statuses = BracketChecker::new().check(EXAMPLE);
+    assert_eq!(statuses.len(), 10);
+    assert_eq!(
+        statuses[0],
+        LineStatus::Incomplete {
+            completion: "}}]])})]".to_string()
+        }
+    );
+    assert_eq!(
+        statuses[2],
+        LineStatus::Corrupted {
+            column: 13,
+            expected: BracketType::SQUARE,
+            found: BracketType::CURLY
+        }
+    );
+    let corrupted: Vec<BracketType> = statuses
+        .iter()
+        .filter_map(|status| match status {
+            LineStatus::Corrupted { found, .. } => Some(*found),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(
+        corrupted,
+        vec![
+            BracketType::CURLY,
+            BracketType::REGULAR,
+            BracketType::SQUARE,
+            BracketType::REGULAR,
+            BracketType::ANGLE
+        ]
+    );
+}
+
+#[test]
+fn handles_valid_lines_and_stray_closers() {
+    let checker = BracketChecker::new();
+    assert_eq!(checker.check_line("([]{<>})"), LineStatus::Valid);
+    assert_eq!(checker.check_line(""), LineStatus::Valid);
+    assert_eq!(
+        checker.check_line("()]"),
+        LineStatus::Unopened {
+            column: 3,
+            found: BracketType::SQUARE
+        }
+    );
+}
+
+#[test]
+fn skips_other_characters() {
+    let checker = BracketChecker::new();
+    assert_eq!(
+        checker.check_line("fn main() { let v = vec![1, 2]; }"),
+        LineStatus::Valid
+    );
+    assert_eq!(
+        checker.check_line("if (a == b) { f(x]"),
+        LineStatus::Corrupted {
+            column: 18,
+            expected: BracketType::REGULAR,
+            found: BracketType::SQUARE
+        }
+    );
+    assert_eq!(
+        checker.check_line("«ü» {"),
+        LineStatus::Incomplete {
+            completion: "}".to_string()
+        }
+    );
+}
+
+#[test]
+fn checks_with_a_custom_table() {
+    let table = BracketTable::parse("( ) 3 1\n« » 5 5\n\" \" 7 6\n").unwrap();
+    let quote = BracketType::new('"', '"');
+    let guillemets = BracketType::new('«', '»');
+    assert_eq!(table.scores(&guillemets), Scores::new(5, 5));
+    let checker = BracketChecker::with_table(table);
+    assert_eq!(checker.check_line("«a \"(b)\" c»"), LineStatus::Valid);
+    // square brackets are plain text now
+    assert_eq!(checker.check_line("([)"), LineStatus::Valid);
+    assert_eq!(
+        checker.check_line("«\"»"),
+        LineStatus::Corrupted {
+            column: 3,
+            expected: quote,
+            found: guillemets
+        }
+    );
+    // a quote inside parentheses opens a new quote rather than closing the outer one
+    assert_eq!(
+        checker.check_line("\"(\""),
+        LineStatus::Incomplete {
+            completion: "\")\"".to_string()
+        }
+    );
+}
+
+#[test]
+fn scores_with_a_custom_table() {
+    let table = BracketTable::parse("( ) 3 1\n« » 5 5\n\" \" 7 6\n").unwrap();
+    assert_eq!(table.completion_base(), 7);
+    let checker = BracketChecker::with_table(table.clone());
+    let lines = ["«(", "(\"", "«)"].map(|line| checker.check_line(line));
+    let subsystem = NavigationSubsystem::new(table, lines.to_vec());
+    // )» is 1 * 7 + 5 and ") is 6 * 7 + 1
+    assert_eq!(subsystem.scores().unwrap(), (3, vec![12, 43]));
+}
+
+#[test]
+fn scores_of_long_incomplete_lines_are_checked() {
+    assert_eq!(BracketTable::default().completion_base(), 5);
+    // 27 missing brackets with the highest score still fit, the next one doesn't
+    let subsystem = BracketsParsing.parse(&"<".repeat(27)).unwrap();
+    assert_eq!(subsystem.scores().unwrap().1, vec![5u64.pow(27) - 1]);
+    let subsystem = BracketsParsing.parse(&"<".repeat(28)).unwrap();
+    assert_eq!(
+        subsystem.scores().unwrap_err().to_string(),
+        "day 10: score of line 1 doesn't fit in 64 bits"
+    );
+}
+
+#[test]
+fn rejects_bad_tables() {
+    let error = |text: &str| BracketTable::parse(text).unwrap_err().to_string();
+    assert_eq!(
+        error("( ) 3 1\n[ ( 4 2"),
+        "2:1: bracket is already in the table"
+    );
+    assert_eq!(
+        error("<< >> 1 1"),
+        "1:1: opening bracket must be a single character"
+    );
+    assert_eq!(error("( ) 3"), "1:6: incomplete score is missing");
+    assert_eq!(
+        error("( ) 3 x"),
+        "1:7: can't parse incomplete score from 'x'"
+    );
+    assert_eq!(
+        BracketTable::new(vec![
+            (BracketType::REGULAR, Scores::new(1, 1)),
+            (BracketType::new(')', '('), Scores::new(1, 1)),
+        ]),
+        None
+    );
+}
+
+#[test]
+fn repairs_with_fewest_edits() {
+    let checker = BracketChecker::new();
+    let repair = checker.repair("(]");
+    assert_eq!(
+        repair.edits,
+        vec![Edit::Substitute {
+            column: 2,
+            from: ']',
+            to: ')'
+        }]
+    );
+    assert_eq!(repair.line, "()");
+
+    // stray closers are deleted, open brackets get closed at the end
+    let repair = checker.repair("())");
+    assert_eq!(repair.edits, vec![Edit::Delete { column: 3, c: ')' }]);
+    assert_eq!(repair.line, "()");
+    let repair = checker.repair("(<>");
+    assert_eq!(repair.edits, vec![Edit::Insert { column: 4, c: ')' }]);
+    assert_eq!(repair.line, "(<>)");
+    // a substitution is cheaper than completing both
+    let repair = checker.repair("[(");
+    assert_eq!(repair.edits.len(), 1);
+    assert_eq!(repair.line, "[]");
+
+    let repair = checker.repair("{[()]}");
+    assert!(repair.edits.is_empty());
+    assert_eq!(repair.line, "{[()]}");
+
+    // other characters stay where they are
+    let repair = checker.repair("f(a[0)");
+    assert_eq!(repair.edits.len(), 1);
+    assert_eq!(repair.line, "f(a[0])");
+}
+
+#[test]
+fn repairs_the_example() {
+    let checker = BracketChecker::new();
+    for line in EXAMPLE.lines() {
+        let repair = checker.repair(line);
+        assert_eq!(checker.check_line(&repair.line), LineStatus::Valid);
+        let removed = repair
+            .edits
+            .iter()
+            .filter(|edit| matches!(edit, Edit::Delete { .. }))
+            .count();
+        let inserted = repair
+            .edits
+            .iter()
+            .filter(|edit| matches!(edit, Edit::Insert { .. }))
+            .count();
+        assert_eq!(
+            repair.line.chars().count(),
+            line.chars().count() + inserted - removed
+        );
+        // the completion of an incomplete line is one way to fix it
+        if let LineStatus::Incomplete { completion } = checker.check_line(line) {
+            assert!(repair.edits.len() <= completion.chars().count());
+        }
+    }
+}
+
+#[test]
+fn repairs_with_a_custom_table() {
+    let table = BracketTable::parse("( ) 3 1\n\" \" 7 6").unwrap();
+    let checker = BracketChecker::with_table(table);
+    let repair = checker.repair("(\"a)");
+    assert_eq!(repair.edits.len(), 1);
+    assert_eq!(checker.check_line(&repair.line), LineStatus::Valid);
+}
+
+/// Hands out the bytes a few at a time, so multi-byte characters get split between reads
+struct Trickle<'a> {
+    bytes: &'a [u8],
+    chunk: usize,
+}
+
+impl Read for Trickle<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.chunk.min(buf.len()).min(self.bytes.len());
+        buf[..n].copy_from_slice(&self.bytes[..n]);
+        self.bytes = &self.bytes[n..];
+        Ok(n)
+    }
+}
+
+/// The same line over and over without keeping them all
+struct Repeat {
+    line: &'static [u8],
+    pos: usize,
+    lines: usize,
+}
+
+impl Read for Repeat {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let mut n = 0;
+        while n < buf.len() && self.lines > 0 {
+            buf[n] = self.line[self.pos];
+            n += 1;
+            self.pos += 1;
+            if self.pos == self.line.len() {
+                self.pos = 0;
+                self.lines -= 1;
+            }
+        }
+        Ok(n)
+    }
+}
+
+#[test]
+fn streams_the_example() {
+    let checker = BracketChecker::new();
+    let expected: Vec<Report> = checker
+        .check(EXAMPLE)
+        .into_iter()
+        .enumerate()
+        .filter(|(_, status)| *status != LineStatus::Valid)
+        .map(|(idx, status)| Report {
+            line: idx + 1,
+            status,
+        })
+        .collect();
+    for chunk in [1, 3, 4096] {
+        let reader = Trickle {
+            bytes: EXAMPLE.as_bytes(),
+            chunk,
+        };
+        let reports: Vec<Report> = checker.stream(reader).map(|r| r.unwrap()).collect();
+        assert_eq!(reports, expected);
+    }
+}
+
+#[test]
+fn streams_utf8_and_invalid_bytes() {
+    let table = BracketTable::parse("( ) 3 1\n« » 5 5").unwrap();
+    let checker = BracketChecker::with_table(table);
+    let mut bytes = "«(»\n".as_bytes().to_vec();
+    // a stray continuation byte and a truncated sequence are one character each
+    bytes.extend([0x80, b'(', 0xe2, 0x82, b')', b')', b'\n', b'\xc2', b'\xbb']);
+    let reports: Vec<Report> = checker
+        .stream(Trickle {
+            bytes: &bytes,
+            chunk: 1,
+        })
+        .map(|r| r.unwrap())
+        .collect();
+    assert_eq!(
+        reports,
+        vec![
+            Report {
+                line: 1,
+                status: LineStatus::Corrupted {
+                    column: 3,
+                    expected: BracketType::REGULAR,
+                    found: BracketType::new('«', '»'),
+                }
+            },
+            Report {
+                line: 2,
+                status: LineStatus::Unopened {
+                    column: 5,
+                    found: BracketType::REGULAR,
+                }
+            },
+            Report {
+                line: 3,
+                status: LineStatus::Unopened {
+                    column: 1,
+                    found: BracketType::new('«', '»'),
+                }
+            },
+        ]
+    );
+}
+
+#[test]
+fn streams_long_inputs_in_bounded_memory() {
+    let checker = BracketChecker::new();
+    let reader = Repeat {
+        line: b"[({(<(())[]>[[{[]{<()<>>\n[(()[<>])]({[<{<<[]>>(\n{([(<{}[<>[]}>{[]{[(<()>\n",
+        pos: 0,
+        lines: 30_000,
+    };
+    let mut corrupted = 0;
+    let mut incomplete = 0;
+    for report in checker.stream(reader) {
+        match report.unwrap().status {
+            LineStatus::Corrupted { .. } => corrupted += 1,
+            LineStatus::Incomplete { .. } => incomplete += 1,
+            status => panic!("unexpected {:?}", status),
+        }
+    }
+    assert_eq!((corrupted, incomplete), (30_000, 60_000));
+
+    // a single endless line is cut off at the nesting limit and the next one is still checked
+    let reader = Repeat {
+        line: b"(",
+        pos: 0,
+        lines: 1_000_000,
+    }
+    .chain(&b"\n(]"[..]);
+    let reports: Vec<_> = checker.stream(reader).with_max_depth(1000).collect();
+    assert_eq!(reports.len(), 2);
+    assert!(matches!(
+        reports[0],
+        Err(StreamError::TooDeep {
+            line: 1,
+            column: 1001
+        })
+    ));
+    assert!(matches!(
+        reports[1],
+        Ok(Report {
+            line: 2,
+            status: LineStatus::Corrupted { column: 2, .. }
+        })
+    ));
+}
+
+#[test]
+fn stream_stops_on_read_errors() {
+    struct Failing;
+    impl Read for Failing {
+        fn read(&mut self, _: &mut [u8]) -> io::Result<usize> {
+            Err(io::Error::other("disk is gone"))
+        }
+    }
+    let checker = BracketChecker::new();
+    let reports: Vec<_> = checker.stream((&b"(]\n"[..]).chain(Failing)).collect();
+    assert_eq!(reports.len(), 2);
+    assert!(reports[0].is_ok());
+    let error = reports[1].as_ref().unwrap_err();
+    assert!(matches!(error, StreamError::Io(_)));
+    assert_eq!(error.to_string(), "can't read brackets: disk is gone");
+}
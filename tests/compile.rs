@@ -0,0 +1,145 @@
+//! Compiled programs against the ALU interpreter
+
+use advent_of_code::day24::alu::{self, Alu, AluError, Registers};
+use advent_of_code::day24::compile::{compile, Compiled, Known};
+use advent_of_code::day24::symbolic::{evaluate_with_overflow, Bounds};
+use advent_of_code::day24::word::Overflow;
+use advent_of_code::day24::{code_blocks, parse_program, Op, Var};
+
+const EXAMPLE: &str = include_str!("../data/day24/input_test.txt");
+
+#[test]
+fn runs_the_example_like_the_interpreter() {
+    let program = parse_program(EXAMPLE).unwrap();
+    let compiled = compile(&program);
+    assert!(compiled.len() < program.len());
+    for n in [
+        11111111u64,
+        96892919,
+        41121918,
+        12345678,
+        99999999,
+        57319284,
+    ] {
+        let input: Vec<i64> = n.to_string().bytes().map(|b| (b - b'0') as i64).collect();
+        assert_eq!(
+            compiled.run(Registers::default(), input.clone()),
+            alu::run(&program, input),
+            "{}",
+            n
+        );
+    }
+}
+
+#[test]
+fn blocks_compiled_for_any_z() {
+    let program = parse_program(EXAMPLE).unwrap();
+    let start = Known::zeros().with(Var::Z, None);
+    for block in code_blocks(&program) {
+        let compiled = Compiled::new(block, start);
+        // mul x 0, mul y 0 and div z 1 are gone
+        assert!(compiled.len() <= block.len() - 2);
+        for z in [0, 1, 25, 26, 17 * 26 + 3, 123_456_789] {
+            for digit in 1..=9 {
+                let registers = Registers::default().with(Var::Z, z);
+                assert_eq!(
+                    compiled.run(registers, [digit]),
+                    alu::Alu::with_registers(block, registers).run([digit])
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn folds_known_values() {
+    let program =
+        parse_program("mul x 0\nadd x 5\nmul x 3\nadd y x\ndiv y 1\nadd w 0\nmul z 1").unwrap();
+    let compiled = compile(&program);
+    // x and y end up as plain stores, everything else is a no-op
+    assert_eq!(compiled.len(), 2);
+    let registers = compiled.run(Registers::default(), []).unwrap();
+    assert_eq!((registers[Var::X], registers[Var::Y]), (15, 15));
+}
+
+#[test]
+fn keeps_runtime_errors() {
+    // y is never read, but the division still has to fail
+    let program = parse_program("inp x\ndiv y x\nmul y 0\nadd z 1\nmod z 0").unwrap();
+    let compiled = Compiled::new(&program, Known::nothing());
+    let registers = Registers::default();
+    assert_eq!(
+        compiled.run(registers, [0]),
+        Err(AluError::DivisionByZero { pc: 1, op: Op::Div })
+    );
+    assert_eq!(
+        compiled.run(registers, [2]),
+        Err(AluError::DivisionByZero { pc: 4, op: Op::Mod })
+    );
+    assert_eq!(
+        compiled.run(registers, []),
+        Err(AluError::InputExhausted { pc: 0 })
+    );
+}
+
+#[test]
+fn overflows_are_errors() {
+    for (source, input, pc, op) in [
+        // folding would overflow, so it's left to run time
+        ("inp w\nadd x 9223372036854775807\nadd x 1", 1, 2, Op::Add),
+        (
+            "inp w\nmul w 9223372036854775807\nmul w 9\nmul z w",
+            1,
+            2,
+            Op::Mul,
+        ),
+        // the result is never read
+        ("inp w\nadd w 9223372036854775807\nmul w 0", 1, 1, Op::Add),
+        (
+            "inp w\nadd x -9223372036854775807\nadd x -1\ndiv x w",
+            -1,
+            3,
+            Op::Div,
+        ),
+    ] {
+        let program = parse_program(source).unwrap();
+        let expected = Err(AluError::Overflow { pc, op });
+        assert_eq!(alu::run(&program, [input]), expected, "{}", source);
+        assert_eq!(
+            compile(&program).run(Registers::default(), [input]),
+            expected,
+            "{}",
+            source
+        );
+    }
+}
+
+#[test]
+fn overflows_like_the_interpreter() {
+    for source in [
+        "inp w\nadd x 9223372036854775807\nadd x w",
+        "inp w\nmul w 9223372036854775807\nmul w 9\nmul z w",
+        "inp w\nadd w 9223372036854775807\nmul w 0",
+        "inp w\nadd x -9223372036854775807\nadd x -1\nmul w -1\ndiv x w\nmod x w",
+        "inp w\nadd x 9223372036854775800\nadd x w\nadd x 5\neql x w",
+    ] {
+        let program = parse_program(source).unwrap();
+        for overflow in [Overflow::Wrapping, Overflow::Checked, Overflow::Saturating] {
+            let symbolic = evaluate_with_overflow(&program, Bounds::DIGIT, overflow);
+            for start in [Known::zeros(), Known::nothing()] {
+                let compiled = Compiled::with_overflow(&program, start, overflow);
+                for digit in 1..=9 {
+                    let expected = Alu::new(&program).with_overflow(overflow).run([digit]);
+                    let context = format!("{} {:?} {}", source, overflow, digit);
+                    assert_eq!(
+                        compiled.run(Registers::default(), [digit]),
+                        expected,
+                        "{}",
+                        context
+                    );
+                    assert_eq!(symbolic.run(&[digit]), expected, "{}", context);
+                }
+            }
+        }
+    }
+}
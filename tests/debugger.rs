@@ -0,0 +1,82 @@
+//! Stepping through programs with the ALU debugger
+
+use advent_of_code::day24::alu::AluError;
+use advent_of_code::day24::debugger::{Breakpoint, Cmp, Debugger, Stop};
+use advent_of_code::day24::word::Overflow;
+use advent_of_code::day24::{parse_program, Op, Var};
+
+const EXAMPLE: &str = include_str!("../data/day24/input_test.txt");
+
+#[test]
+fn steps_and_runs_to_inputs() {
+    let program = parse_program(EXAMPLE).unwrap();
+    let mut debugger = Debugger::new(&program, [9, 6, 8, 9, 2, 9, 1, 9]);
+    assert_eq!(debugger.step(1), Stop::Stepped);
+    assert_eq!(debugger.registers()[Var::W], 9);
+    assert_eq!(debugger.next_input(), Stop::Input);
+    assert_eq!(debugger.pc(), 18);
+    assert_eq!(debugger.registers()[Var::Z], 9 + 7);
+    assert_eq!(debugger.resume(), Stop::Halted);
+    assert_eq!(debugger.registers()[Var::Z], 0);
+    assert_eq!(debugger.step(1), Stop::Halted);
+}
+
+#[test]
+fn stops_on_breakpoints() {
+    let program = parse_program("inp w\nadd z w\nadd z w\nadd z w\nmul z 0\nadd x 1").unwrap();
+    let mut debugger = Debugger::new(&program, [2]);
+    let at = debugger.add_breakpoint(Breakpoint::Pc(4));
+    let when = debugger.add_breakpoint(Breakpoint::When(Var::Z, Cmp::Ge, 4));
+    assert_eq!(debugger.resume(), Stop::Breakpoint(when));
+    assert_eq!((debugger.pc(), debugger.registers()[Var::Z]), (3, 4));
+    // the condition still holds but only becoming true stops
+    assert_eq!(debugger.resume(), Stop::Breakpoint(at));
+    assert_eq!(debugger.pc(), 4);
+    assert!(debugger.remove_breakpoint(when));
+    assert!(!debugger.remove_breakpoint(when));
+    assert_eq!(debugger.resume(), Stop::Halted);
+}
+
+#[test]
+fn reports_failures_where_they_happen() {
+    let program = parse_program("inp x\ndiv y x").unwrap();
+    let mut debugger = Debugger::new(&program, [0]);
+    let failure = Stop::Failed(AluError::DivisionByZero { pc: 1, op: Op::Div });
+    assert_eq!(debugger.resume(), failure);
+    assert_eq!(debugger.step(1), failure);
+}
+
+#[test]
+fn runs_scripts() {
+    let program = parse_program("inp w\nadd z w\nmul z 3\ninp x\neql z x").unwrap();
+    let mut debugger = Debugger::new(&program, [2, 6]);
+    let output = debugger
+        .run_script("next\nbreak z == 1  # eql matched\n\ncontinue\nstep 5\nregs\n")
+        .unwrap();
+    assert_eq!(
+        output,
+        "waiting for input\n   3 inp x        w = 2, x = 0, y = 0, z = 6\n\
+         breakpoint 0 when z == 1\n\
+         breakpoint 0\n   5 end          w = 2, x = 6, y = 0, z = 1\n\
+         halted\n   5 end          w = 2, x = 6, y = 0, z = 1\n\
+         \x20  5 end          w = 2, x = 6, y = 0, z = 1\n"
+    );
+
+    let e = debugger.run_script("step\nbreak q 3").unwrap_err();
+    assert_eq!(e.to_string(), "2:7: can't parse instruction index from 'q'");
+    let e = debugger.run_script("delete 7").unwrap_err();
+    assert_eq!(e.to_string(), "1:8: no such breakpoint");
+}
+
+#[test]
+fn follows_the_overflow() {
+    let program = parse_program("inp w\nadd z 9223372036854775807\nadd z w").unwrap();
+    let mut debugger = Debugger::new(&program, [1]);
+    assert_eq!(
+        debugger.resume(),
+        Stop::Failed(AluError::Overflow { pc: 2, op: Op::Add })
+    );
+    let mut debugger = Debugger::new(&program, [1]).with_overflow(Overflow::Wrapping);
+    assert_eq!(debugger.resume(), Stop::Halted);
+    assert_eq!(debugger.registers()[Var::Z], i64::MIN);
+}
@@ -0,0 +1,45 @@
+//! Side by side listing of code blocks
+
+use advent_of_code::day24::disasm::Disassembly;
+use advent_of_code::day24::parse_program;
+
+const EXAMPLE: &str = include_str!("../data/day24/input_test.txt");
+
+#[test]
+fn marks_rows_that_differ() {
+    let program = parse_program(EXAMPLE).unwrap();
+    // divisor, check and offset of the MONAD template
+    assert_eq!(Disassembly::new(&program).differing_rows(), vec![4, 5, 15]);
+}
+
+#[test]
+fn prints_blocks_side_by_side() {
+    let program = parse_program("inp w\nadd z w\ninp w\nadd z 3\ninp w\nadd z w\nmul z 2").unwrap();
+    let disassembly = Disassembly::new(&program);
+    assert_eq!(
+        disassembly.to_string(),
+        "      | block 0  block 1  block 2\n\
+         \x20   0 | inp w    inp w    inp w\n\
+         *   1 | add z w  add z 3  add z w\n\
+         *   2 |                   mul z 2\n"
+    );
+    assert_eq!(
+        disassembly.columns(2).to_string(),
+        "      | block 0  block 1\n\
+         \x20   0 | inp w    inp w\n\
+         *   1 | add z w  add z 3\n\
+         *   2 |\n\
+         \n      | block 2\n\
+         \x20   0 | inp w\n\
+         *   1 | add z w\n\
+         *   2 | mul z 2\n"
+    );
+}
+
+#[test]
+fn highlights_cells_off_the_common_instruction() {
+    let program = parse_program("inp w\nadd z 1\ninp w\nadd z 1\ninp w\nadd z 2").unwrap();
+    let colored = Disassembly::new(&program).colored(true).to_string();
+    assert_eq!(colored.matches("\x1b[1;33m").count(), 1);
+    assert!(colored.contains("\x1b[1;33madd z 2 "));
+}
@@ -0,0 +1,172 @@
+//! Random programs through every way of running them
+
+use advent_of_code::day24::alu::{self, AluError, Registers};
+use advent_of_code::day24::fuzz::{
+    backends, differential, fuzz, Backend, Generator, Interpreter, Narrow, SearchExecutor,
+};
+use advent_of_code::day24::symbolic::Bounds;
+use advent_of_code::day24::{parse_program, Instruction, Int, Op};
+use advent_of_code::utils::Rng;
+
+#[test]
+fn backends_agree_on_random_programs() {
+    let generator = Generator::new(4, 40);
+    let backends = backends(generator.digits);
+    if let Some((program, input, divergence)) = fuzz(&backends, &generator, 24, 500) {
+        let source: Vec<String> = program.iter().map(|i| i.to_string()).collect();
+        panic!("{}\ninput {:?}\n{}", source.join("\n"), input, divergence);
+    }
+}
+
+#[test]
+fn search_agrees_on_programs_that_reset_their_registers() {
+    let generator = Generator {
+        reset: true,
+        ..Generator::new(5, 40)
+    };
+    let backends = backends(generator.digits);
+    let mut rng = Rng::new(18);
+    let mut supported = 0;
+    for _ in 0..20 {
+        let program = generator.program(&mut rng);
+        let input = generator.input(&mut rng);
+        supported += (1..=program.len())
+            .filter(|len| SearchExecutor.supports(&program[..*len], &input))
+            .count();
+    }
+    // most prefixes end where the last block has rewritten what the blocks before it wrote
+    assert!(
+        supported > 20 * 40 / 2,
+        "search ran on {} prefixes",
+        supported
+    );
+    if let Some((program, input, divergence)) = fuzz(&backends, &generator, 18, 300) {
+        let source: Vec<String> = program.iter().map(|i| i.to_string()).collect();
+        panic!("{}\ninput {:?}\n{}", source.join("\n"), input, divergence);
+    }
+}
+
+#[test]
+fn search_reports_errors_at_their_place_in_the_program() {
+    let program = parse_program("inp w\nadd z w\ninp w\nmul y 0\ndiv z y").unwrap();
+    assert!(SearchExecutor.supports(&program, &[3, 4]));
+    assert_eq!(
+        SearchExecutor.run(&program, &[3, 4]),
+        Err(AluError::DivisionByZero { pc: 4, op: Op::Div })
+    );
+    assert_eq!(
+        SearchExecutor.run(&program, &[3]),
+        Err(AluError::InputExhausted { pc: 2 })
+    );
+    let backends: Vec<Box<dyn Backend>> = vec![Box::new(Interpreter), Box::new(SearchExecutor)];
+    assert_eq!(differential(&backends, &program, &[3, 4]), None);
+    // y isn't reset in the second block
+    let program = parse_program("inp w\nadd y w\ninp x\nadd z y").unwrap();
+    assert!(!SearchExecutor.supports(&program, &[3, 4]));
+}
+
+#[test]
+fn narrow_registers_run_programs_whose_values_fit() {
+    let narrow = Narrow(Bounds::DIGIT);
+    let generator = Generator::new(4, 40);
+    let mut rng = Rng::new(32);
+    let supported = (0..20)
+        .filter(|_| {
+            let program = generator.program(&mut rng);
+            narrow.supports(&program, &generator.input(&mut rng))
+        })
+        .count();
+    // random programs mostly stay far below the limit of the generator
+    assert!(supported > 10, "i32 ran {} programs", supported);
+    let program = parse_program("inp w\nmul w 100000\nmul w 100000\ndiv w 100000").unwrap();
+    assert!(narrow.supports(&program[..2], &[9]));
+    assert_eq!(
+        narrow.run(&program[..2], &[9]),
+        alu::run(&program[..2], [9])
+    );
+    // the end result fits but the value before it doesn't
+    assert!(!narrow.supports(&program, &[9]));
+    assert!(!narrow.supports(&program[..2], &[10]));
+}
+
+#[test]
+fn random_numbers_cover_wide_bounds() {
+    let mut rng = Rng::new(18);
+    for bounds in [
+        Bounds::new(Int::MIN, Int::MAX),
+        Bounds::new(Int::MIN, 0),
+        Bounds::new(-1, Int::MAX),
+        Bounds::new(Int::MAX, Int::MAX),
+    ] {
+        assert!((0..100).all(|_| bounds.contains(rng.within(bounds))));
+    }
+}
+
+#[test]
+fn generates_programs_of_the_requested_shape() {
+    let generator = Generator::new(3, 25);
+    let mut rng = Rng::new(7);
+    for _ in 0..50 {
+        let program = generator.program(&mut rng);
+        assert_eq!(program.len(), 25);
+        assert!(matches!(program[0], Instruction::Inp(_)));
+        let inputs = program
+            .iter()
+            .filter(|i| matches!(i, Instruction::Inp(_)))
+            .count();
+        assert_eq!(inputs, 3);
+        // values stay small enough for every backend to run without overflowing
+        let input = generator.input(&mut rng);
+        assert!(input.iter().all(|d| Bounds::DIGIT.contains(*d)));
+        let _ = alu::run(&program, input);
+    }
+}
+
+/// interpreter that gets mod wrong for negative numbers
+struct EuclideanMod;
+
+impl Backend for EuclideanMod {
+    fn name(&self) -> &str {
+        "euclidean mod"
+    }
+
+    fn run(&self, program: &[Instruction], input: &[Int]) -> Result<Registers, AluError> {
+        let rewritten: Vec<Instruction> = program
+            .iter()
+            .flat_map(|instruction| match instruction {
+                // x mod n as ((x mod n) + n) mod n
+                Instruction::Op(Op::Mod, var, value) => vec![
+                    instruction.clone(),
+                    Instruction::Op(Op::Add, *var, value.clone()),
+                    instruction.clone(),
+                ],
+                _ => vec![instruction.clone()],
+            })
+            .collect();
+        alu::run(&rewritten, input.iter().copied())
+    }
+}
+
+#[test]
+fn reports_the_first_diverging_instruction() {
+    let program = parse_program("inp w\nmul w -1\nadd z w\nmod w 4\nmod z 3\nadd x 1").unwrap();
+    let backends: Vec<Box<dyn Backend>> = vec![Box::new(Interpreter), Box::new(EuclideanMod)];
+    assert_eq!(differential(&backends, &program[..3], &[5]), None);
+
+    let divergence = differential(&backends, &program, &[5]).unwrap();
+    assert_eq!(divergence.pc, 3);
+    assert_eq!(divergence.instruction.to_string(), "mod w 4");
+    assert_eq!(
+        divergence.to_string(),
+        "backends disagree after 3: mod w 4\n    \
+         interpreter          w = -1, x = 0, y = 0, z = -5\n    \
+         euclidean mod        w = 3, x = 0, y = 0, z = -5\n"
+    );
+}
+
+#[test]
+fn random_numbers_never_get_stuck_at_zero() {
+    // this seed cancels the mixing constant
+    let mut rng = Rng::new(0x9e37_79b9_7f4a_7c15);
+    assert!((0..4).all(|_| rng.next_u64() != 0));
+}
@@ -0,0 +1,191 @@
+//! Input providers against a local stub server standing in for the site
+
+use advent_of_code::input::{FetchingCache, FileCache, HttpFetcher, InputKind, InputSource};
+use advent_of_code::Error;
+use std::fs;
+use std::io::{BufRead, BufReader, Write};
+use std::net::TcpListener;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+/// request line and cookie of every request the stub has served
+type Requests = Arc<Mutex<Vec<(String, Option<String>)>>>;
+
+/// Answers every request with `status` and `body`, the body is sent chunked when asked to
+fn stub_server(status: &'static str, body: &'static str, chunked: bool) -> (String, Requests) {
+    serve(
+        TcpListener::bind("127.0.0.1:0").unwrap(),
+        status,
+        body,
+        chunked,
+    )
+}
+
+/// The same on a listener bound by the caller
+fn serve(
+    listener: TcpListener,
+    status: &'static str,
+    body: &'static str,
+    chunked: bool,
+) -> (String, Requests) {
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    let requests: Requests = Arc::new(Mutex::new(Vec::new()));
+    let served = Arc::clone(&requests);
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            let mut stream = stream.unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut request_line = String::new();
+            reader.read_line(&mut request_line).unwrap();
+            let mut cookie = None;
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                if line.trim().is_empty() {
+                    break;
+                }
+                if let Some(value) = line.strip_prefix("Cookie:") {
+                    cookie = Some(value.trim().to_string());
+                }
+            }
+            served
+                .lock()
+                .unwrap()
+                .push((request_line.trim().to_string(), cookie));
+            let response = if chunked {
+                let (first, second) = body.split_at(body.len() / 2);
+                format!(
+                    "HTTP/1.1 {}\r\nTransfer-Encoding: chunked\r\n\r\n{:x}\r\n{}\r\n{:x}\r\n{}\r\n0\r\n\r\n",
+                    status,
+                    first.len(),
+                    first,
+                    second.len(),
+                    second
+                )
+            } else {
+                format!(
+                    "HTTP/1.1 {}\r\nContent-Length: {}\r\n\r\n{}",
+                    status,
+                    body.len(),
+                    body
+                )
+            };
+            stream.write_all(response.as_bytes()).unwrap();
+        }
+    });
+    (url, requests)
+}
+
+/// fresh empty directory under the system temp dir
+fn temp_data_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("aoc-input-test-{}-{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    dir
+}
+
+#[test]
+fn missing_input_is_downloaded_and_cached() {
+    let (url, requests) = stub_server("200 OK", "1\n2\n3\n", false);
+    let dir = temp_data_dir("download");
+    let source = FetchingCache::new(
+        FileCache::new(&dir, InputKind::Puzzle),
+        Box::new(HttpFetcher::new(&url, "secret").unwrap()),
+    );
+
+    assert_eq!(source.read(7).unwrap(), "1\n2\n3\n");
+    assert_eq!(
+        fs::read_to_string(dir.join("day07/input.txt")).unwrap(),
+        "1\n2\n3\n"
+    );
+    assert_eq!(
+        *requests.lock().unwrap(),
+        vec![(
+            "GET /2021/day/7/input HTTP/1.1".to_string(),
+            Some("session=secret".to_string())
+        )]
+    );
+
+    // the second read is served from the cache
+    assert_eq!(source.read(7).unwrap(), "1\n2\n3\n");
+    assert_eq!(requests.lock().unwrap().len(), 1);
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn chunked_response_is_decoded() {
+    let (url, _) = stub_server("200 OK", "target area: x=20..30, y=-10..-5\n", true);
+    let fetcher = HttpFetcher::new(&format!("{}/", url), "secret").unwrap();
+    let dir = temp_data_dir("chunked");
+    let source = FetchingCache::new(FileCache::new(&dir, InputKind::Puzzle), Box::new(fetcher));
+    assert_eq!(
+        source.read(17).unwrap(),
+        "target area: x=20..30, y=-10..-5\n"
+    );
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn server_error_is_reported_and_not_cached() {
+    let (url, _) = stub_server(
+        "400 Bad Request",
+        "Puzzle inputs differ by user.  Please log in to get your puzzle input.\n",
+        false,
+    );
+    let dir = temp_data_dir("error");
+    let source = FetchingCache::new(
+        FileCache::new(&dir, InputKind::Puzzle),
+        Box::new(HttpFetcher::new(&url, "expired").unwrap()),
+    );
+    match source.read(1) {
+        Err(Error::Fetch { day: 1, message }) => assert!(message.contains("400"), "{}", message),
+        other => panic!("expected a fetch error, got {:?}", other),
+    }
+    assert!(!dir.join("day01/input.txt").exists());
+}
+
+#[test]
+fn bad_urls_are_rejected() {
+    assert!(HttpFetcher::new("ftp://localhost", "s").is_err());
+    assert!(HttpFetcher::new("http://localhost:port", "s").is_err());
+    assert!(HttpFetcher::new("http://:8080", "s").is_err());
+    assert_eq!(
+        HttpFetcher::new("http://::1:8080", "s").unwrap_err(),
+        "bad host '::1' in 'http://::1:8080', IPv6 addresses go in brackets"
+    );
+    assert_eq!(
+        HttpFetcher::new("http://[::1", "s").unwrap_err(),
+        "bad host '[:' in 'http://[::1', IPv6 addresses go in brackets"
+    );
+    assert_eq!(
+        HttpFetcher::new("http://[localhost]:8080", "s").unwrap_err(),
+        "bad host '[localhost]' in 'http://[localhost]:8080', IPv6 addresses go in brackets"
+    );
+    assert_eq!(
+        HttpFetcher::new("http://[::1]:port", "s").unwrap_err(),
+        "bad port 'port' in 'http://[::1]:port'"
+    );
+}
+
+#[test]
+fn ipv6_hosts_are_taken_in_brackets() {
+    // without a port the address keeps all of its colons
+    let fetcher = HttpFetcher::new("http://[::1]/prefix", "s").unwrap();
+    let debug = format!("{:?}", fetcher);
+    assert!(debug.contains("host: \"[::1]\", port: 80,"), "{}", debug);
+
+    let Ok(listener) = TcpListener::bind("[::1]:0") else {
+        // no IPv6 loopback to serve from
+        return;
+    };
+    let (url, requests) = serve(listener, "200 OK", "4\n5\n", false);
+    assert!(url.starts_with("http://[::1]:"), "{}", url);
+    let dir = temp_data_dir("ipv6");
+    let source = FetchingCache::new(
+        FileCache::new(&dir, InputKind::Puzzle),
+        Box::new(HttpFetcher::new(&url, "secret").unwrap()),
+    );
+    assert_eq!(source.read(4).unwrap(), "4\n5\n");
+    assert_eq!(requests.lock().unwrap().len(), 1);
+    fs::remove_dir_all(&dir).unwrap();
+}
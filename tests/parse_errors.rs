@@ -0,0 +1,58 @@
+//! Inputs that parse into something the solutions can't handle are rejected by the parsers
+
+use advent_of_code::day16::{self, Bits};
+use advent_of_code::{day05, day13, day14, day15, day23, Solver};
+
+fn error<S: Solver>(solver: S, input: &str) -> String {
+    match solver.parse(input) {
+        Ok(_) => panic!("{:?} was parsed", input),
+        Err(e) => e.to_string(),
+    }
+}
+
+#[test]
+fn rejects_empty_inputs() {
+    assert_eq!(
+        error(day05::VentLines, ""),
+        "day 5, line 1, column 1: no lines"
+    );
+    assert_eq!(
+        error(day14::Polymers, "\n\nAB -> C"),
+        "day 14, line 1, column 1: polymer template is missing"
+    );
+    assert_eq!(
+        error(
+            day23::AmphipodRooms,
+            "#############\n#...........#\n#############"
+        ),
+        "day 23, line 3, column 14: rooms must have 2 rows of amphipods"
+    );
+}
+
+#[test]
+fn rejects_values_out_of_range() {
+    assert_eq!(
+        error(day15::Chitons, "19\n05"),
+        "day 15, line 2, column 1: risk level must be at least 1"
+    );
+    assert_eq!(
+        error(
+            day13::FoldingOrigami,
+            "20,0\n1,1\n\nfold along y=1\nfold along x=5"
+        ),
+        "day 13, line 5, column 1: fold would move points past the edge of the paper"
+    );
+    // a fold that fits after the previous one is fine
+    assert!(day13::FoldingOrigami
+        .parse("20,0\n\nfold along x=15\nfold along x=5")
+        .is_ok());
+
+    // 17 groups of 4 bits are past 64
+    let mut bits: Bits = "110100".chars().map(|c| c == '1').collect();
+    for group in 0..17 {
+        bits.push(group < 16);
+        bits.extend([false, false, false, true]);
+    }
+    let hexdump = day16::bits2hexdump(&bits);
+    assert!(error(day16::BitsDecoding, &hexdump).ends_with("truncated or malformed packet"));
+}
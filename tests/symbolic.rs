@@ -0,0 +1,102 @@
+//! Symbolic evaluation against running programs on the ALU
+
+use advent_of_code::day24::alu::{self, AluError};
+use advent_of_code::day24::symbolic::{evaluate, Bounds, Expr};
+use advent_of_code::day24::{parse_program, Op, Var};
+
+const EXAMPLE: &str = include_str!("../data/day24/input_test.txt");
+
+#[test]
+fn evaluates_like_the_alu() {
+    let program = parse_program(EXAMPLE).unwrap();
+    let registers = evaluate(&program, Bounds::DIGIT);
+    assert_eq!(registers.inputs, 8);
+    for n in [
+        11111111u64,
+        96892919,
+        41121918,
+        12345678,
+        99999999,
+        57319284,
+    ] {
+        let input: Vec<i64> = n.to_string().bytes().map(|b| (b - b'0') as i64).collect();
+        let expected = alu::run(&program, input.clone()).unwrap();
+        for var in [Var::W, Var::X, Var::Y, Var::Z] {
+            assert_eq!(
+                registers[var].eval(&input),
+                Some(expected[var]),
+                "{} {:?}",
+                n,
+                var
+            );
+            assert!(registers[var].bounds().contains(expected[var]));
+        }
+    }
+}
+
+#[test]
+fn folds_checks_that_cant_match() {
+    let block = "inp w\nmul x 0\nadd x z\nmod x 26\ndiv z 1\nadd x 12\neql x w\neql x 0\nmul y 0\n\
+                 add y 25\nmul y x\nadd y 1\nmul z y\nmul y 0\nadd y w\nadd y 7\nmul y x\nadd z y";
+    let registers = evaluate(&parse_program(block).unwrap(), Bounds::DIGIT);
+    assert_eq!(registers[Var::X].as_const(), Some(1));
+    assert_eq!(registers[Var::Z].to_string(), "d0 + 7");
+    assert_eq!(registers[Var::Z].bounds(), Bounds::new(8, 16));
+
+    // a digit can equal 5 but never 10
+    let digit = Expr::digit(0, Bounds::DIGIT);
+    assert_eq!(
+        Expr::apply(Op::Eql, &digit, &Expr::constant(10)).as_const(),
+        Some(0)
+    );
+    assert_eq!(
+        Expr::apply(Op::Eql, &digit, &Expr::constant(5)).as_const(),
+        None
+    );
+    assert_eq!(Expr::apply(Op::Eql, &digit, &digit).as_const(), Some(1));
+}
+
+#[test]
+fn prints_shared_subexpressions_once() {
+    let program = parse_program("inp w\nadd x w\nmul x 3\nadd z x\nmul z x\nmod z 5").unwrap();
+    let registers = evaluate(&program, Bounds::DIGIT);
+    assert_eq!(
+        registers[Var::Z].formula("z"),
+        "t0 = d0 * 3\nz = t0 * t0 % 5\n"
+    );
+    // division by zero is left in place and fails on evaluation
+    let program = parse_program("inp w\ndiv w z").unwrap();
+    assert_eq!(evaluate(&program, Bounds::DIGIT)[Var::W].eval(&[3]), None);
+    // even when the result is thrown away
+    let program = parse_program("inp w\ndiv w z\nmul w 0\ninp x").unwrap();
+    let registers = evaluate(&program, Bounds::DIGIT);
+    assert_eq!(registers[Var::W].as_const(), Some(0));
+    assert_eq!(
+        registers.run(&[3, 4]),
+        Err(AluError::DivisionByZero { pc: 1, op: Op::Div })
+    );
+    let program = parse_program("inp w\ninp x\nadd w 1").unwrap();
+    assert_eq!(
+        evaluate(&program, Bounds::DIGIT).run(&[3]),
+        Err(AluError::InputExhausted { pc: 1 })
+    );
+}
+
+#[test]
+fn leaves_overflows_symbolic() {
+    let program = parse_program("inp w\nadd x 9223372036854775807\nadd x 1").unwrap();
+    let registers = evaluate(&program, Bounds::DIGIT);
+    assert_eq!(registers[Var::X].eval(&[1]), None);
+    assert_eq!(
+        registers.run(&[1]),
+        Err(AluError::Overflow { pc: 2, op: Op::Add })
+    );
+    assert_eq!(registers.run(&[1]), alu::run(&program, vec![1]));
+
+    // only some digits overflow
+    let program = parse_program("inp w\nadd x 9223372036854775800\nadd x w").unwrap();
+    let registers = evaluate(&program, Bounds::DIGIT);
+    for digit in 1..=9 {
+        assert_eq!(registers.run(&[digit]), alu::run(&program, vec![digit]));
+    }
+}